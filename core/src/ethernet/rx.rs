@@ -54,7 +54,10 @@ macro_rules! ethernet_rx {
             }
 
             #[inline]
-            fn route<'a>(&mut self, packet: EthernetPacket<'a>) -> Result<(), $error_struct_name> {
+            fn route<'a>(
+                &mut self,
+                packet: EthernetPacket<&'a [u8]>,
+            ) -> Result<(), $error_struct_name> {
                 let ether_type = packet.ether_type();
                 $(if ether_type == $ether_type {
                     return self.$listener_name.recv(packet.payload())
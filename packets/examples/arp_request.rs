@@ -1,7 +1,8 @@
 extern crate rips_packets;
 
-use rips_packets::arp::{MutArpPacket, Operation};
-use rips_packets::ethernet::{MacAddr, MutEthernetPacket, EtherType};
+use rips_packets::arp::{ArpRepr, Operation};
+use rips_packets::builder::{BuildError, Ethernet};
+use rips_packets::ethernet::MacAddr;
 
 use std::net::Ipv4Addr;
 
@@ -32,33 +33,14 @@ fn format_arp_request_frame(
     src_mac: MacAddr,
     src_ip: Ipv4Addr,
     target_ip: Ipv4Addr,
-) -> Result<(), &'static str> {
-    static ERR_MSG: &str = "Too short buffer";
-
-    let mut ethernet_packet = MutEthernetPacket::new(buffer).ok_or(ERR_MSG)?;
-    format_broadcast_ethernet_arp(&mut ethernet_packet, src_mac);
-
-    let mut arp_packet = MutArpPacket::new(ethernet_packet.payload()).ok_or(ERR_MSG)?;
-    format_arp_request(&mut arp_packet, src_mac, src_ip, target_ip);
-    Ok(())
-}
-
-fn format_broadcast_ethernet_arp<'a>(packet: &mut MutEthernetPacket<'a>, src_mac: MacAddr) {
-    packet.set_destination(MacAddr::BROADCAST);
-    packet.set_source(src_mac);
-    packet.set_ether_type(EtherType::ARP);
-}
-
-fn format_arp_request<'a>(
-    packet: &mut MutArpPacket<'a>,
-    src_mac: MacAddr,
-    src_ip: Ipv4Addr,
-    target_ip: Ipv4Addr,
-) {
-    packet.set_ipv4_over_ethernet_values();
-    packet.set_operation(Operation::REQUEST);
-    packet.set_sender_mac_addr(src_mac);
-    packet.set_sender_ip_addr(src_ip);
-    // packet.set_target_mac_addr(); // Is ignored in a request anyway
-    packet.set_target_ip_addr(target_ip);
+) -> Result<usize, BuildError> {
+    let arp = ArpRepr {
+        operation: Operation::REQUEST,
+        sender_mac_addr: src_mac,
+        sender_ip_addr: src_ip,
+        // Is ignored in a request anyway
+        target_mac_addr: MacAddr([0; 6]),
+        target_ip_addr: target_ip,
+    };
+    Ethernet::new(src_mac, MacAddr::BROADCAST).arp(arp).build(buffer)
 }
@@ -22,8 +22,7 @@ fn main() {
     }
 
     // Create an immutable representation of the ethernet frame based on the same
-    // buffer. Where a mutable `MutEthernetPacket` has setters `EthernetPacket` has the
-    // corresponding getters.
+    // buffer. A packet over an immutable buffer only has the getters.
     let packet = EthernetPacket::new(&buffer[..]).expect("Too short buffer");
 
     println!("Destination MAC: {}", packet.destination());
@@ -1,47 +1,27 @@
+use core::error::Error;
+use core::fmt;
+use core::net::Ipv4Addr;
 use ethernet::{EtherType, MacAddr};
-use std::net::Ipv4Addr;
 
-packet!(ArpPacket, MutArpPacket, 28);
-
-getters!(ArpPacket
-    pub fn hardware_type(&self) -> HardwareType {
-        HardwareType(read_offset!(self.0.as_ref(), 0, u16, from_be))
-    }
-
-    pub fn protocol_type(&self) -> EtherType {
-        EtherType(read_offset!(self.0.as_ref(), 2, u16, from_be))
-    }
-
-    pub fn hardware_length(&self) -> u8 {
-        read_offset!(self.0.as_ref(), 4, u8)
-    }
-
-    pub fn protocol_length(&self) -> u8 {
-        read_offset!(self.0.as_ref(), 5, u8)
-    }
-
-    pub fn operation(&self) -> Operation {
-        Operation(read_offset!(self.0.as_ref(), 6, u16, from_be))
-    }
-
-    pub fn sender_mac_addr(&self) -> MacAddr {
-        MacAddr::from_slice(&self.0.as_ref()[8..14])
+packet_layout! {
+    packet: ArpPacket, MutArpPacket;
+    header_len: |_: &[u8]| ArpPacket::MIN_LEN;
+    packet_len: |data: &[u8]| 8 + 2 * (usize::from(data[4]) + usize::from(data[5]));
+    tests: arp_layout_tests;
+    fields: {
+        hardware_type / set_hardware_type: HardwareType = 0, 16, be;
+        protocol_type / set_protocol_type: EtherType = 16, 16, be;
+        hardware_length / set_hardware_length: u8 = 32, 8, be;
+        protocol_length / set_protocol_length: u8 = 40, 8, be;
+        operation / set_operation: Operation = 48, 16, be;
+        sender_mac_addr / set_sender_mac_addr: MacAddr = 64, 48, be;
+        sender_ip_addr / set_sender_ip_addr: Ipv4Addr = 112, 32, be;
+        target_mac_addr / set_target_mac_addr: MacAddr = 144, 48, be;
+        target_ip_addr / set_target_ip_addr: Ipv4Addr = 192, 32, be;
     }
+}
 
-    pub fn sender_ip_addr(&self) -> Ipv4Addr {
-        Ipv4Addr::from(read_offset!(self.0.as_ref(), 14, [u8; 4]))
-    }
-
-    pub fn target_mac_addr(&self) -> MacAddr {
-        MacAddr::from_slice(&self.0.as_ref()[18..24])
-    }
-
-    pub fn target_ip_addr(&self) -> Ipv4Addr {
-        Ipv4Addr::from(read_offset!(self.0.as_ref(), 24, [u8; 4]))
-    }
-);
-
-impl<'a> MutArpPacket<'a> {
+impl<B: AsRef<[u8]> + AsMut<[u8]>> ArpPacket<B> {
     /// Sets the hardware_type, hardware_length, protocol_type and
     /// protocol_length fields to correct values for an IPv4 over Ethernet
     /// packet.
@@ -53,44 +33,53 @@ impl<'a> MutArpPacket<'a> {
     }
 }
 
-setters!(MutArpPacket
-    pub fn set_hardware_type(&mut self, hardware_type: HardwareType) {
-        write_offset!(self.0, 0, hardware_type.value(), u16, to_be)
-    }
-
-    pub fn set_protocol_type(&mut self, protocol_type: EtherType) {
-        write_offset!(self.0, 2, protocol_type.value(), u16, to_be)
-    }
-
-    pub fn set_hardware_length(&mut self, hardware_length: u8) {
-        write_offset!(self.0, 4, hardware_length, u8, to_be);
-    }
-
-    pub fn set_protocol_length(&mut self, protocol_length: u8) {
-        write_offset!(self.0, 5, protocol_length, u8, to_be);
-    }
-
-    pub fn set_operation(&mut self, operation: Operation) {
-        write_offset!(self.0, 6, operation.value(), u16, to_be)
-    }
-
-    pub fn set_sender_mac_addr(&mut self, sender_mac: MacAddr) {
-        self.0[8..14].copy_from_slice(sender_mac.as_ref());
-    }
-
-    pub fn set_sender_ip_addr(&mut self, sender_ip: Ipv4Addr) {
-        self.0[14..18].copy_from_slice(&sender_ip.octets());
-    }
-
-    pub fn set_target_mac_addr(&mut self, target_mac: MacAddr) {
-        self.0[18..24].copy_from_slice(target_mac.as_ref());
+impl<B: AsRef<[u8]>> ArpPacket<B> {
+    /// Checks that the address lengths match the hardware and protocol types, for the types
+    /// where the length is known, and that the packet is long enough to hold all four addresses.
+    pub fn validate(&self) -> Result<(), ArpError> {
+        if self.hardware_type() == HardwareType::ETHERNET && self.hardware_length() != 6 {
+            return Err(ArpError::HardwareLength);
+        }
+        let protocol_length = match self.protocol_type() {
+            EtherType::IPV4 => Some(4),
+            EtherType::IPV6 => Some(16),
+            _ => None,
+        };
+        if protocol_length.is_some_and(|length| length != self.protocol_length()) {
+            return Err(ArpError::ProtocolLength);
+        }
+        let address_len = usize::from(self.hardware_length()) + usize::from(self.protocol_length());
+        if self.data().len() < 8 + 2 * address_len {
+            return Err(ArpError::Truncated);
+        }
+        Ok(())
     }
+}
 
-    pub fn set_target_ip_addr(&mut self, target_ip: Ipv4Addr) {
-        self.0[24..28].copy_from_slice(&target_ip.octets());
+new_checked!(ArpPacket, ArpError);
+
+/// Writes a one line summary of the packet in the style of tcpdump, like `ARP who-has 10.0.0.1
+/// tell 10.0.0.2` or `ARP 10.0.0.1 is-at 00:01:02:03:04:05`. Packets that are not for IPv4 over
+/// Ethernet only get their types and operation written.
+impl<B: AsRef<[u8]>> fmt::Display for ArpPacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match ArpRepr::parse(self) {
+            Ok(ref repr) if repr.operation == Operation::REQUEST => {
+                write!(f, "ARP who-has {} tell {}", repr.target_ip_addr, repr.sender_ip_addr)
+            }
+            Ok(ref repr) if repr.operation == Operation::REPLY => {
+                write!(f, "ARP {} is-at {}", repr.sender_ip_addr, repr.sender_mac_addr)
+            }
+            _ => write!(
+                f,
+                "ARP hrd={} pro={:#} op={}",
+                self.hardware_type().value(),
+                self.protocol_type(),
+                self.operation().value()
+            ),
+        }
     }
-);
-
+}
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct HardwareType(pub u16);
@@ -103,6 +92,8 @@ impl HardwareType {
     }
 }
 
+newtype_layout_value!(HardwareType, u16);
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Operation(pub u16);
 
@@ -115,6 +106,88 @@ impl Operation {
     }
 }
 
+newtype_layout_value!(Operation, u16);
+
+
+/// An owned representation of an ARP packet for IPv4 over Ethernet, the only combination of
+/// hardware and protocol types in common use.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ArpRepr {
+    pub operation: Operation,
+    pub sender_mac_addr: MacAddr,
+    pub sender_ip_addr: Ipv4Addr,
+    pub target_mac_addr: MacAddr,
+    pub target_ip_addr: Ipv4Addr,
+}
+
+impl ArpRepr {
+    /// Reads `packet` into an `ArpRepr`. Fails if the packet is not for IPv4 over Ethernet.
+    pub fn parse<B: AsRef<[u8]>>(packet: &ArpPacket<B>) -> Result<Self, ArpError> {
+        if packet.data().len() < ArpPacket::MIN_LEN {
+            return Err(ArpError::Truncated);
+        }
+        if packet.hardware_type() != HardwareType::ETHERNET || packet.hardware_length() != 6 {
+            return Err(ArpError::UnsupportedHardware);
+        }
+        if packet.protocol_type() != EtherType::IPV4 || packet.protocol_length() != 4 {
+            return Err(ArpError::UnsupportedProtocol);
+        }
+        Ok(ArpRepr {
+            operation: packet.operation(),
+            sender_mac_addr: packet.sender_mac_addr(),
+            sender_ip_addr: packet.sender_ip_addr(),
+            target_mac_addr: packet.target_mac_addr(),
+            target_ip_addr: packet.target_ip_addr(),
+        })
+    }
+
+    /// Returns the number of bytes needed to emit this packet.
+    pub fn buffer_len(&self) -> usize {
+        ArpPacket::MIN_LEN
+    }
+
+    /// Writes this packet to `packet`, including the hardware and protocol fields for IPv4 over
+    /// Ethernet.
+    pub fn emit<B: AsRef<[u8]> + AsMut<[u8]>>(&self, packet: &mut ArpPacket<B>) {
+        packet.set_ipv4_over_ethernet_values();
+        packet.set_operation(self.operation);
+        packet.set_sender_mac_addr(self.sender_mac_addr);
+        packet.set_sender_ip_addr(self.sender_ip_addr);
+        packet.set_target_mac_addr(self.target_mac_addr);
+        packet.set_target_ip_addr(self.target_ip_addr);
+    }
+}
+
+/// Error returned when an ARP packet is not valid or not supported.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ArpError {
+    /// The packet is too short to hold an ARP packet for IPv4 over Ethernet, or too short for the
+    /// address lengths given in the header.
+    Truncated,
+    /// The hardware address length does not match the hardware type.
+    HardwareLength,
+    /// The protocol address length does not match the protocol type.
+    ProtocolLength,
+    /// The hardware type or hardware address length is not the one for Ethernet.
+    UnsupportedHardware,
+    /// The protocol type or protocol address length is not the one for IPv4.
+    UnsupportedProtocol,
+}
+
+impl fmt::Display for ArpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArpError::Truncated => write!(f, "ARP packet truncated"),
+            ArpError::HardwareLength => write!(f, "Invalid ARP hardware address length"),
+            ArpError::ProtocolLength => write!(f, "Invalid ARP protocol address length"),
+            ArpError::UnsupportedHardware => write!(f, "Unsupported ARP hardware type"),
+            ArpError::UnsupportedProtocol => write!(f, "Unsupported ARP protocol type"),
+        }
+    }
+}
+
+impl Error for ArpError {}
+
 
 #[cfg(test)]
 mod tests {
@@ -197,4 +270,74 @@ mod tests {
         assert_eq!(6, testee.as_immutable().hardware_length());
         assert_eq!(4, testee.as_immutable().protocol_length());
     }
+
+    #[test]
+    fn repr_round_trip() {
+        let repr = ArpRepr {
+            operation: Operation::REPLY,
+            sender_mac_addr: MacAddr([1, 2, 3, 4, 5, 6]),
+            sender_ip_addr: Ipv4Addr::new(10, 0, 0, 1),
+            target_mac_addr: MacAddr([6, 5, 4, 3, 2, 1]),
+            target_ip_addr: Ipv4Addr::new(10, 0, 0, 2),
+        };
+        let mut backing_data = [0; 28];
+        let mut packet = ArpPacket::new(&mut backing_data[..]).unwrap();
+        repr.emit(&mut packet);
+        assert_eq!(28, repr.buffer_len());
+        assert_eq!(Ok(repr), ArpRepr::parse(&packet));
+    }
+
+    #[test]
+    fn repr_unsupported() {
+        let mut backing_data = [0; 28];
+        let mut packet = ArpPacket::new(&mut backing_data[..]).unwrap();
+        packet.set_ipv4_over_ethernet_values();
+        packet.set_hardware_length(8);
+        assert_eq!(Err(ArpError::UnsupportedHardware), ArpRepr::parse(&packet));
+        packet.set_ipv4_over_ethernet_values();
+        packet.set_protocol_type(EtherType::IPV6);
+        assert_eq!(Err(ArpError::UnsupportedProtocol), ArpRepr::parse(&packet));
+    }
+
+    #[test]
+    fn validate() {
+        let mut backing_data = [0; 28];
+        let mut packet = ArpPacket::new(&mut backing_data[..]).unwrap();
+        packet.set_ipv4_over_ethernet_values();
+        assert_eq!(Ok(()), packet.validate());
+        packet.set_hardware_length(8);
+        assert_eq!(Err(ArpError::HardwareLength), packet.validate());
+        packet.set_ipv4_over_ethernet_values();
+        packet.set_protocol_length(16);
+        assert_eq!(Err(ArpError::ProtocolLength), packet.validate());
+        packet.set_protocol_type(EtherType::IPV6);
+        assert_eq!(Err(ArpError::Truncated), packet.validate());
+        assert_eq!(Err(ArpError::Truncated), ArpPacket::new_checked(&[0; 27][..]));
+    }
+
+    #[test]
+    fn padded_packet() {
+        let mut backing_data = [0; 46];
+        let mut packet = ArpPacket::new(&mut backing_data[..]).unwrap();
+        packet.set_ipv4_over_ethernet_values();
+        assert_eq!(28, packet.packet_len());
+        assert!(packet.payload().is_empty());
+        assert_eq!(18, packet.trailer().len());
+    }
+
+    #[test]
+    fn display() {
+        let mut backing_data = [0; 28];
+        let mut packet = ArpPacket::new(&mut backing_data[..]).unwrap();
+        packet.set_ipv4_over_ethernet_values();
+        packet.set_operation(Operation::REQUEST);
+        packet.set_sender_ip_addr(Ipv4Addr::new(10, 0, 0, 2));
+        packet.set_target_ip_addr(Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!("ARP who-has 10.0.0.1 tell 10.0.0.2", packet.to_string());
+        packet.set_operation(Operation::REPLY);
+        packet.set_sender_mac_addr(MacAddr([0, 1, 2, 3, 4, 5]));
+        assert_eq!("ARP 10.0.0.2 is-at 00:01:02:03:04:05", packet.to_string());
+        packet.set_hardware_type(HardwareType(6));
+        assert_eq!("ARP hrd=6 pro=IPv4 op=2", packet.to_string());
+    }
 }
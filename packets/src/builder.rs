@@ -0,0 +1,757 @@
+//! Building whole packets layer by layer.
+//!
+//! A packet is described by stacking layers, starting from the outermost one, and is then
+//! written to a buffer in one go. The builder computes the size of the packet and fills in all
+//! fields that depend on the other layers: the EtherType, the IP protocol, the IPv4 header and
+//! total lengths, the IPv6 payload length, the UDP length and the TCP data offset, as well as
+//! the IPv4, TCP, UDP and ICMP checksums.
+//!
+//! ```rust
+//! extern crate rips_packets;
+//!
+//! use rips_packets::builder::{Ethernet, Ipv4, Udp};
+//! use rips_packets::ethernet::MacAddr;
+//! use std::net::Ipv4Addr;
+//!
+//! fn main() {
+//!     let builder = Ethernet::new(MacAddr([2, 0, 0, 0, 0, 1]), MacAddr::BROADCAST)
+//!         .ipv4(Ipv4::new(Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)))
+//!         .udp(Udp::new(1024, 53))
+//!         .payload(b"hello");
+//!
+//!     let mut buffer = [0; 64];
+//!     let len = builder.build(&mut buffer).expect("Too short buffer");
+//!     assert_eq!(14 + 20 + 8 + 5, len);
+//! }
+//! ```
+//!
+//! Fields without a dedicated builder option can be changed on the built packet with the
+//! setters of the packet types, as long as the checksums are filled in again afterwards.
+
+use arp::{ArpPacket, ArpRepr};
+use core::error::Error;
+use core::fmt;
+use core::net::{Ipv4Addr, Ipv6Addr};
+use ethernet::{EtherType, EthernetPacket, EthernetRepr, MacAddr};
+use icmp::{IcmpPacket, IcmpType};
+use icmpv6::{Icmpv6Packet, Icmpv6Type};
+use ip::{Protocol, PseudoHeader};
+use ipv4::{self, Ipv4Packet, Ipv4Repr};
+use ipv6::{Ipv6Packet, Ipv6Repr};
+use tcp::{self, TcpPacket};
+use types::*;
+use udp::UdpPacket;
+
+/// An Ethernet II header. The start of a builder for a whole frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Ethernet {
+    pub source: MacAddr,
+    pub destination: MacAddr,
+}
+
+impl Ethernet {
+    pub fn new(source: MacAddr, destination: MacAddr) -> Ethernet {
+        Ethernet {
+            source,
+            destination,
+        }
+    }
+
+    /// Adds an IPv4 header after this header.
+    pub fn ipv4(self, ipv4: Ipv4) -> IpBuilder<Ipv4> {
+        IpBuilder {
+            ethernet: Some(self),
+            ip: ipv4,
+        }
+    }
+
+    /// Adds an IPv6 header after this header.
+    pub fn ipv6(self, ipv6: Ipv6) -> IpBuilder<Ipv6> {
+        IpBuilder {
+            ethernet: Some(self),
+            ip: ipv6,
+        }
+    }
+
+    /// Adds an ARP packet after this header. ARP packets have no payload.
+    pub fn arp(self, arp: ArpRepr) -> PacketBuilder<'static> {
+        PacketBuilder::new(Some(self), Network::Arp(arp), None)
+    }
+
+    /// Announces a payload of the given EtherType, which the builder does not know how to
+    /// build. Set the payload with `payload()`.
+    pub fn raw(self, ether_type: EtherType) -> PacketBuilder<'static> {
+        PacketBuilder::new(Some(self), Network::Raw(ether_type), None)
+    }
+}
+
+/// An IPv4 header without options. `new` sets the TTL to 64 and the Don't Fragment flag, the
+/// other fields can be changed with struct update syntax:
+///
+/// ```rust
+/// # use rips_packets::builder::Ipv4;
+/// # use std::net::Ipv4Addr;
+/// let ipv4 = Ipv4 { ttl: 1, ..Ipv4::new(Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST) };
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Ipv4 {
+    pub source: Ipv4Addr,
+    pub destination: Ipv4Addr,
+    pub dscp: u6,
+    pub ecn: u2,
+    pub identification: u16,
+    pub flags: ipv4::Flags,
+    pub ttl: u8,
+}
+
+impl Ipv4 {
+    pub fn new(source: Ipv4Addr, destination: Ipv4Addr) -> Ipv4 {
+        Ipv4 {
+            source,
+            destination,
+            dscp: 0,
+            ecn: 0,
+            identification: 0,
+            flags: ipv4::Flags::DF,
+            ttl: 64,
+        }
+    }
+}
+
+/// A fixed IPv6 header. `new` sets the hop limit to 64.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Ipv6 {
+    pub source: Ipv6Addr,
+    pub destination: Ipv6Addr,
+    pub traffic_class: u8,
+    pub flow_label: u20,
+    pub hop_limit: u8,
+}
+
+impl Ipv6 {
+    pub fn new(source: Ipv6Addr, destination: Ipv6Addr) -> Ipv6 {
+        Ipv6 {
+            source,
+            destination,
+            traffic_class: 0,
+            flow_label: 0,
+            hop_limit: 64,
+        }
+    }
+}
+
+/// A builder with an IP header as its innermost layer. Created with `Ethernet::ipv4()` and
+/// `Ethernet::ipv6()`, or from an `Ipv4` or `Ipv6` header for packets without an Ethernet header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct IpBuilder<I> {
+    ethernet: Option<Ethernet>,
+    ip: I,
+}
+
+impl From<Ipv4> for IpBuilder<Ipv4> {
+    fn from(ipv4: Ipv4) -> Self {
+        IpBuilder {
+            ethernet: None,
+            ip: ipv4,
+        }
+    }
+}
+
+impl From<Ipv6> for IpBuilder<Ipv6> {
+    fn from(ipv6: Ipv6) -> Self {
+        IpBuilder {
+            ethernet: None,
+            ip: ipv6,
+        }
+    }
+}
+
+impl<I: Into<Network>> IpBuilder<I> {
+    /// Adds a UDP header after the IP header.
+    pub fn udp(self, udp: Udp) -> PacketBuilder<'static> {
+        self.transport(Transport::Udp(udp))
+    }
+
+    /// Adds a TCP header after the IP header.
+    pub fn tcp<'a>(self, tcp: Tcp<'a>) -> PacketBuilder<'a> {
+        self.transport(Transport::Tcp(tcp))
+    }
+
+    /// Announces a payload of the given protocol, which the builder does not know how to build.
+    /// Set the payload with `payload()`.
+    pub fn raw(self, protocol: Protocol) -> PacketBuilder<'static> {
+        self.transport(Transport::Raw(protocol))
+    }
+
+    fn transport<'a>(self, transport: Transport<'a>) -> PacketBuilder<'a> {
+        PacketBuilder::new(self.ethernet, self.ip.into(), Some(transport))
+    }
+}
+
+impl IpBuilder<Ipv4> {
+    /// Adds an ICMP header after the IPv4 header.
+    pub fn icmp(self, icmp: Icmp) -> PacketBuilder<'static> {
+        self.transport(Transport::Icmp(icmp))
+    }
+}
+
+impl IpBuilder<Ipv6> {
+    /// Adds an ICMPv6 header after the IPv6 header.
+    pub fn icmpv6(self, icmpv6: Icmpv6) -> PacketBuilder<'static> {
+        self.transport(Transport::Icmpv6(icmpv6))
+    }
+}
+
+/// A UDP header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Udp {
+    pub source_port: u16,
+    pub destination_port: u16,
+}
+
+impl Udp {
+    pub fn new(source_port: u16, destination_port: u16) -> Udp {
+        Udp {
+            source_port,
+            destination_port,
+        }
+    }
+}
+
+/// A TCP header. `new` creates a header without flags or options and with a window size of
+/// 65535. The options are raw option bytes, they are padded to a multiple of four bytes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Tcp<'a> {
+    pub source_port: u16,
+    pub destination_port: u16,
+    pub sequence_number: u32,
+    pub acknowledgment_number: u32,
+    pub flags: tcp::Flags,
+    pub window_size: u16,
+    pub urgent_pointer: u16,
+    pub options: &'a [u8],
+}
+
+impl Tcp<'static> {
+    pub fn new(source_port: u16, destination_port: u16) -> Tcp<'static> {
+        Tcp {
+            source_port,
+            destination_port,
+            sequence_number: 0,
+            acknowledgment_number: 0,
+            flags: tcp::Flags::empty(),
+            window_size: 0xffff,
+            urgent_pointer: 0,
+            options: &[],
+        }
+    }
+}
+
+impl<'a> Tcp<'a> {
+    fn header_len(&self) -> usize {
+        TcpPacket::MIN_LEN + ((self.options.len() + 3) & !3)
+    }
+}
+
+/// An ICMP header. The four bytes after the checksum depend on the message type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Icmp {
+    pub icmp_type: IcmpType,
+    pub code: u8,
+    pub rest_of_header: [u8; 4],
+}
+
+impl Icmp {
+    pub fn new(icmp_type: IcmpType, code: u8) -> Icmp {
+        Icmp {
+            icmp_type,
+            code,
+            rest_of_header: [0; 4],
+        }
+    }
+
+    pub fn echo_request(identifier: u16, sequence_number: u16) -> Icmp {
+        Icmp {
+            rest_of_header: echo_rest_of_header(identifier, sequence_number),
+            ..Icmp::new(IcmpType::ECHO_REQUEST, 0)
+        }
+    }
+
+    pub fn echo_reply(identifier: u16, sequence_number: u16) -> Icmp {
+        Icmp {
+            rest_of_header: echo_rest_of_header(identifier, sequence_number),
+            ..Icmp::new(IcmpType::ECHO_REPLY, 0)
+        }
+    }
+}
+
+/// An ICMPv6 header. The four bytes after the checksum depend on the message type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Icmpv6 {
+    pub icmp_type: Icmpv6Type,
+    pub code: u8,
+    pub rest_of_header: [u8; 4],
+}
+
+impl Icmpv6 {
+    pub fn new(icmp_type: Icmpv6Type, code: u8) -> Icmpv6 {
+        Icmpv6 {
+            icmp_type,
+            code,
+            rest_of_header: [0; 4],
+        }
+    }
+
+    pub fn echo_request(identifier: u16, sequence_number: u16) -> Icmpv6 {
+        Icmpv6 {
+            rest_of_header: echo_rest_of_header(identifier, sequence_number),
+            ..Icmpv6::new(Icmpv6Type::ECHO_REQUEST, 0)
+        }
+    }
+
+    pub fn echo_reply(identifier: u16, sequence_number: u16) -> Icmpv6 {
+        Icmpv6 {
+            rest_of_header: echo_rest_of_header(identifier, sequence_number),
+            ..Icmpv6::new(Icmpv6Type::ECHO_REPLY, 0)
+        }
+    }
+}
+
+fn echo_rest_of_header(identifier: u16, sequence_number: u16) -> [u8; 4] {
+    let identifier = identifier.to_be_bytes();
+    let sequence_number = sequence_number.to_be_bytes();
+    [identifier[0], identifier[1], sequence_number[0], sequence_number[1]]
+}
+
+/// The layer after the Ethernet header of a `PacketBuilder`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Network {
+    Ipv4(Ipv4),
+    Ipv6(Ipv6),
+    Arp(ArpRepr),
+    /// A payload of the given EtherType, written as is.
+    Raw(EtherType),
+}
+
+impl From<Ipv4> for Network {
+    fn from(ipv4: Ipv4) -> Self {
+        Network::Ipv4(ipv4)
+    }
+}
+
+impl From<Ipv6> for Network {
+    fn from(ipv6: Ipv6) -> Self {
+        Network::Ipv6(ipv6)
+    }
+}
+
+impl Network {
+    fn ether_type(&self) -> EtherType {
+        match *self {
+            Network::Ipv4(_) => EtherType::IPV4,
+            Network::Ipv6(_) => EtherType::IPV6,
+            Network::Arp(_) => EtherType::ARP,
+            Network::Raw(ether_type) => ether_type,
+        }
+    }
+
+    fn header_len(&self) -> usize {
+        match *self {
+            Network::Ipv4(_) => Ipv4Packet::MIN_LEN,
+            Network::Ipv6(_) => Ipv6Packet::MIN_LEN,
+            Network::Arp(_) => ArpPacket::MIN_LEN,
+            Network::Raw(_) => 0,
+        }
+    }
+}
+
+/// The layer after the IP header of a `PacketBuilder`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Transport<'a> {
+    Udp(Udp),
+    Tcp(Tcp<'a>),
+    Icmp(Icmp),
+    Icmpv6(Icmpv6),
+    /// A payload of the given protocol, written as is.
+    Raw(Protocol),
+}
+
+impl<'a> Transport<'a> {
+    fn protocol(&self) -> Protocol {
+        match *self {
+            Transport::Udp(_) => Protocol::UDP,
+            Transport::Tcp(_) => Protocol::TCP,
+            Transport::Icmp(_) => Protocol::ICMP,
+            Transport::Icmpv6(_) => Protocol::IPV6_ICMP,
+            Transport::Raw(protocol) => protocol,
+        }
+    }
+
+    fn header_len(&self) -> usize {
+        match *self {
+            Transport::Udp(_) => UdpPacket::MIN_LEN,
+            Transport::Tcp(ref tcp) => tcp.header_len(),
+            Transport::Icmp(_) => IcmpPacket::MIN_LEN,
+            Transport::Icmpv6(_) => Icmpv6Packet::MIN_LEN,
+            Transport::Raw(_) => 0,
+        }
+    }
+
+    /// Writes this header and `payload` to `buffer`, which must be exactly as long as both.
+    fn emit<P: PseudoHeader>(&self, buffer: &mut [u8], payload: &[u8], ip: &P) {
+        let header_len = self.header_len();
+        buffer[header_len..].copy_from_slice(payload);
+        match *self {
+            Transport::Udp(ref udp) => {
+                let mut packet = UdpPacket::new(buffer).unwrap();
+                packet.set_source_port(udp.source_port);
+                packet.set_destination_port(udp.destination_port);
+                packet.set_length((header_len + payload.len()) as u16);
+                packet.fill_checksum(ip);
+            }
+            Transport::Tcp(ref tcp) => {
+                let mut packet = TcpPacket::new(buffer).unwrap();
+                packet.set_source_port(tcp.source_port);
+                packet.set_destination_port(tcp.destination_port);
+                packet.set_sequence_number(tcp.sequence_number);
+                packet.set_acknowledgment_number(tcp.acknowledgment_number);
+                packet.set_options(tcp.options).unwrap();
+                packet.set_flags(tcp.flags);
+                packet.set_window_size(tcp.window_size);
+                packet.set_urgent_pointer(tcp.urgent_pointer);
+                packet.fill_checksum(ip);
+            }
+            Transport::Icmp(ref icmp) => {
+                let mut packet = IcmpPacket::new(buffer).unwrap();
+                packet.set_icmp_type(icmp.icmp_type);
+                packet.set_code(icmp.code);
+                packet.set_rest_of_header(icmp.rest_of_header);
+                packet.fill_checksum();
+            }
+            Transport::Icmpv6(ref icmpv6) => {
+                buffer[4..8].copy_from_slice(&icmpv6.rest_of_header);
+                let mut packet = Icmpv6Packet::new(buffer).unwrap();
+                packet.set_icmp_type(icmpv6.icmp_type);
+                packet.set_code(icmpv6.code);
+                packet.fill_checksum(ip);
+            }
+            Transport::Raw(_) => (),
+        }
+    }
+}
+
+/// A complete description of a packet, ready to be written with `build()`. Created by stacking
+/// layers, see the module documentation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PacketBuilder<'a> {
+    pub ethernet: Option<Ethernet>,
+    pub network: Network,
+    /// The layer after the IP header. Always `None` for ARP and raw network layers.
+    pub transport: Option<Transport<'a>>,
+    /// The payload of the innermost layer.
+    pub payload: &'a [u8],
+}
+
+impl<'a> PacketBuilder<'a> {
+    fn new(
+        ethernet: Option<Ethernet>,
+        network: Network,
+        transport: Option<Transport<'a>>,
+    ) -> PacketBuilder<'a> {
+        PacketBuilder {
+            ethernet,
+            network,
+            transport,
+            payload: &[],
+        }
+    }
+
+    /// Sets the payload of the innermost layer.
+    pub fn payload(mut self, payload: &'a [u8]) -> PacketBuilder<'a> {
+        self.payload = payload;
+        self
+    }
+
+    /// Returns the number of bytes needed to build this packet.
+    pub fn buffer_len(&self) -> usize {
+        let ethernet_len = self.ethernet.map_or(0, |_| EthernetPacket::MIN_LEN);
+        ethernet_len + self.network.header_len() + self.network_payload_len()
+    }
+
+    /// Returns the length of everything after the network layer header.
+    fn network_payload_len(&self) -> usize {
+        self.transport.map_or(0, |transport| transport.header_len()) + self.payload.len()
+    }
+
+    /// Checks that all length fields can hold their lengths and that the TCP options fit.
+    fn check(&self) -> Result<(), BuildError> {
+        if let Some(Transport::Tcp(ref tcp)) = self.transport {
+            if tcp.options.len() > tcp::MAX_OPTIONS_LEN {
+                return Err(BuildError::Options);
+            }
+        }
+        let max_payload_len = match self.network {
+            Network::Ipv4(_) => usize::from(u16::MAX) - Ipv4Packet::MIN_LEN,
+            Network::Ipv6(_) | Network::Arp(_) | Network::Raw(_) => usize::from(u16::MAX),
+        };
+        if self.network_payload_len() > max_payload_len {
+            return Err(BuildError::PayloadTooLong);
+        }
+        Ok(())
+    }
+
+    /// Writes the packet to the start of `buffer` and returns its length. Bytes after the packet
+    /// are left untouched.
+    pub fn build(&self, buffer: &mut [u8]) -> Result<usize, BuildError> {
+        self.check()?;
+        let len = self.buffer_len();
+        if buffer.len() < len {
+            return Err(BuildError::BufferTooShort);
+        }
+        for byte in &mut buffer[..len] {
+            *byte = 0;
+        }
+        let mut offset = 0;
+        if let Some(ref ethernet) = self.ethernet {
+            let repr = EthernetRepr {
+                destination: ethernet.destination,
+                source: ethernet.source,
+                ether_type: self.network.ether_type(),
+            };
+            repr.emit(&mut EthernetPacket::new(&mut buffer[..len]).unwrap());
+            offset = repr.buffer_len();
+        }
+        let buffer = &mut buffer[offset..len];
+        let header_len = self.network.header_len();
+        let payload_len = self.network_payload_len();
+        let protocol = self
+            .transport
+            .map_or(Protocol::IPV6_NONXT, |transport| transport.protocol());
+        match self.network {
+            Network::Ipv4(ref ipv4) => {
+                let repr = Ipv4Repr {
+                    dscp: ipv4.dscp,
+                    ecn: ipv4.ecn,
+                    identification: ipv4.identification,
+                    flags: ipv4.flags,
+                    fragment_offset: 0,
+                    ttl: ipv4.ttl,
+                    protocol,
+                    source: ipv4.source,
+                    destination: ipv4.destination,
+                    payload_len,
+                };
+                repr.emit(&mut Ipv4Packet::new(&mut *buffer).unwrap());
+                let ip = (ipv4.source, ipv4.destination);
+                self.emit_transport(&mut buffer[header_len..], &ip);
+            }
+            Network::Ipv6(ref ipv6) => {
+                let repr = Ipv6Repr {
+                    traffic_class: ipv6.traffic_class,
+                    flow_label: ipv6.flow_label,
+                    next_header: protocol,
+                    hop_limit: ipv6.hop_limit,
+                    source: ipv6.source,
+                    destination: ipv6.destination,
+                    payload_len,
+                };
+                repr.emit(&mut Ipv6Packet::new(&mut *buffer).unwrap());
+                let ip = (ipv6.source, ipv6.destination);
+                self.emit_transport(&mut buffer[header_len..], &ip);
+            }
+            Network::Arp(ref arp) => {
+                arp.emit(&mut ArpPacket::new(&mut *buffer).unwrap());
+                buffer[header_len..].copy_from_slice(self.payload);
+            }
+            Network::Raw(_) => buffer.copy_from_slice(self.payload),
+        }
+        Ok(len)
+    }
+
+    /// Builds the packet into a new `Vec`. Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn build_vec(&self) -> Result<Vec<u8>, BuildError> {
+        let mut buffer = vec![0; self.buffer_len()];
+        self.build(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn emit_transport<P: PseudoHeader>(&self, buffer: &mut [u8], ip: &P) {
+        match self.transport {
+            Some(ref transport) => transport.emit(buffer, self.payload, ip),
+            None => buffer.copy_from_slice(self.payload),
+        }
+    }
+}
+
+/// Error returned when a `PacketBuilder` can not build its packet.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BuildError {
+    /// The buffer is shorter than `buffer_len()`.
+    BufferTooShort,
+    /// The payload does not fit in the length field of one of the layers.
+    PayloadTooLong,
+    /// The TCP options are longer than `tcp::MAX_OPTIONS_LEN`.
+    Options,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::BufferTooShort => write!(f, "Buffer too short for packet"),
+            BuildError::PayloadTooLong => write!(f, "Payload too long for length field"),
+            BuildError::Options => write!(f, "TCP options too long"),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arp::Operation;
+    use dissect::dissect;
+
+    const SOURCE_MAC: MacAddr = MacAddr([2, 0, 0, 0, 0, 1]);
+    const SOURCE: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    const DESTINATION: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+
+    fn ethernet() -> Ethernet {
+        Ethernet::new(SOURCE_MAC, MacAddr::BROADCAST)
+    }
+
+    #[test]
+    fn udp() {
+        let builder = ethernet()
+            .ipv4(Ipv4::new(SOURCE, DESTINATION))
+            .udp(Udp::new(1024, 53))
+            .payload(b"hello");
+        assert_eq!(47, builder.buffer_len());
+        let mut buffer = [0xaa; 50];
+        assert_eq!(Ok(47), builder.build(&mut buffer));
+        assert_eq!([0xaa; 3], buffer[47..]);
+
+        let dissection = dissect(&buffer[..47]);
+        assert_eq!(EtherType::IPV4, dissection.ethernet().unwrap().ether_type());
+        let ipv4 = dissection.ipv4().unwrap();
+        assert_eq!(Ipv4Packet::MIN_LEN, ipv4.header_len());
+        assert_eq!(33, ipv4.total_length());
+        assert_eq!(Protocol::UDP, ipv4.protocol());
+        assert!(ipv4.verify_checksum());
+        let udp = dissection.udp().unwrap();
+        assert_eq!(13, udp.length());
+        assert!(udp.verify_checksum(&ipv4));
+        assert_eq!(Some(&b"hello"[..]), dissection.payload());
+    }
+
+    #[test]
+    fn tcp_with_options() {
+        let options = [2, 4, 0x05, 0xb4, 1];
+        let tcp = Tcp {
+            sequence_number: 1,
+            flags: tcp::Flags::SYN,
+            options: &options,
+            ..Tcp::new(1024, 80)
+        };
+        let builder = IpBuilder::from(Ipv4::new(SOURCE, DESTINATION)).tcp(tcp);
+        let mut buffer = [0xff; 48];
+        assert_eq!(Ok(48), builder.build(&mut buffer));
+        let ipv4 = Ipv4Packet::new(&buffer[..]).unwrap();
+        let packet = TcpPacket::new(ipv4.payload()).unwrap();
+        assert_eq!(7, packet.data_offset());
+        assert_eq!(&[2, 4, 0x05, 0xb4, 1, 0, 0, 0], packet.options_data());
+        assert_eq!(tcp::Flags::SYN, packet.flags());
+        assert_eq!(0xffff, packet.window_size());
+        assert!(packet.verify_checksum(&ipv4));
+    }
+
+    #[test]
+    fn icmp() {
+        let builder = ethernet()
+            .ipv4(Ipv4::new(SOURCE, DESTINATION))
+            .icmp(Icmp::echo_request(0x1234, 1))
+            .payload(&[1, 2, 3]);
+        let mut buffer = [0; 45];
+        builder.build(&mut buffer).unwrap();
+        let icmp = dissect(&buffer).icmp().unwrap();
+        assert_eq!("ICMP echo request id=4660 seq=1", icmp.to_string());
+        assert!(icmp.verify_checksum());
+    }
+
+    #[test]
+    fn icmpv6() {
+        let source = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+        let destination = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+        let builder = ethernet()
+            .ipv6(Ipv6::new(source, destination))
+            .icmpv6(Icmpv6::echo_reply(1, 2));
+        let mut buffer = [0; 62];
+        assert_eq!(Ok(62), builder.build(&mut buffer));
+        let dissection = dissect(&buffer);
+        let ipv6 = dissection.ipv6().unwrap();
+        assert_eq!(8, ipv6.payload_length());
+        assert_eq!(Protocol::IPV6_ICMP, ipv6.next_header());
+        let icmpv6 = dissection.icmpv6().unwrap();
+        assert_eq!("ICMPv6 echo reply id=1 seq=2", icmpv6.to_string());
+        assert!(icmpv6.verify_checksum(&ipv6));
+    }
+
+    #[test]
+    fn arp() {
+        let repr = ArpRepr {
+            operation: Operation::REQUEST,
+            sender_mac_addr: SOURCE_MAC,
+            sender_ip_addr: SOURCE,
+            target_mac_addr: MacAddr([0; 6]),
+            target_ip_addr: DESTINATION,
+        };
+        let builder = ethernet().arp(repr);
+        let mut buffer = [0; 42];
+        assert_eq!(Ok(42), builder.build(&mut buffer));
+        let dissection = dissect(&buffer);
+        assert_eq!(EtherType::ARP, dissection.ethernet().unwrap().ether_type());
+        assert_eq!(Ok(repr), ArpRepr::parse(&dissection.arp().unwrap()));
+    }
+
+    #[test]
+    fn raw() {
+        let builder = ethernet().raw(EtherType(0x88b5)).payload(&[1, 2]);
+        let mut buffer = [0; 16];
+        assert_eq!(Ok(16), builder.build(&mut buffer));
+        assert_eq!([0x88, 0xb5, 1, 2], buffer[12..]);
+
+        let builder = IpBuilder::from(Ipv4::new(SOURCE, DESTINATION)).raw(Protocol(253));
+        let mut buffer = [0; 20];
+        assert_eq!(Ok(20), builder.build(&mut buffer));
+        assert_eq!(Protocol(253), Ipv4Packet::new(&buffer[..]).unwrap().protocol());
+    }
+
+    #[test]
+    fn errors() {
+        let builder = ethernet().ipv4(Ipv4::new(SOURCE, DESTINATION)).udp(Udp::new(1, 2));
+        assert_eq!(Err(BuildError::BufferTooShort), builder.build(&mut [0; 41]));
+
+        let payload = [0; 0xffff - 28 + 1];
+        let builder = builder.payload(&payload);
+        assert_eq!(Err(BuildError::PayloadTooLong), builder.build(&mut [0; 0x10100]));
+
+        let options = [1; 41];
+        let tcp = Tcp {
+            options: &options,
+            ..Tcp::new(1, 2)
+        };
+        let builder = IpBuilder::from(Ipv4::new(SOURCE, DESTINATION)).tcp(tcp);
+        assert_eq!(Err(BuildError::Options), builder.build(&mut [0; 100]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn build_vec() {
+        let builder = ethernet().ipv4(Ipv4::new(SOURCE, DESTINATION)).udp(Udp::new(1, 2));
+        let mut buffer = [0; 42];
+        builder.build(&mut buffer).unwrap();
+        assert_eq!(Ok(buffer.to_vec()), builder.build_vec());
+    }
+}
@@ -0,0 +1,162 @@
+//! The Internet checksum, the 16 bit one's complement of the one's complement sum used by IPv4,
+//! TCP, UDP and ICMP. See [RFC 1071] for how it is computed and [RFC 1624] for how it can be
+//! incrementally updated.
+//!
+//! Checksums that cover more than one buffer, such as TCP and UDP with their pseudo-headers, are
+//! computed by feeding each buffer through [`sum`] and then calling [`finish`] on the result.
+//!
+//! [RFC 1071]: https://tools.ietf.org/html/rfc1071
+//! [RFC 1624]: https://tools.ietf.org/html/rfc1624
+//! [`sum`]: fn.sum.html
+//! [`finish`]: fn.finish.html
+
+/// Adds the 16 bit big endian words in `data` to the one's complement sum `initial` and returns
+/// the new sum. The returned sum is folded and always fits in 16 bits, so it can be passed in as
+/// `initial` again. If `data` has an odd length it is padded with a zero byte, so only the last
+/// buffer of a sequence may have an odd length.
+pub fn sum(initial: u32, data: &[u8]) -> u32 {
+    let mut sum = u64::from(initial);
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        sum += u64::from(u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+    }
+    let remainder = chunks.remainder();
+    let mut words = remainder.chunks_exact(2);
+    for word in &mut words {
+        sum += u64::from(u16::from_be_bytes([word[0], word[1]]));
+    }
+    if let Some(&last) = words.remainder().first() {
+        sum += u64::from(last) << 8;
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum as u32
+}
+
+/// Folds a one's complement sum into 16 bits and returns its complement, the final checksum.
+pub fn finish(sum: u32) -> u16 {
+    !fold(sum)
+}
+
+/// Computes the Internet checksum of `data`.
+pub fn checksum(data: &[u8]) -> u16 {
+    finish(sum(0, data))
+}
+
+/// Incrementally updates `checksum` after the 16 bit word `old` in the checksummed data has been
+/// changed to `new`. Implements equation 3 from [RFC 1624].
+///
+/// [RFC 1624]: https://tools.ietf.org/html/rfc1624
+pub fn update(checksum: u16, old: u16, new: u16) -> u16 {
+    !fold(u32::from(!checksum) + u32::from(!old) + u32::from(new))
+}
+
+/// Incrementally updates `checksum` after the bytes `old` in the checksummed data have been
+/// changed to `new`. The changed bytes must start at an even offset in the checksummed data.
+///
+/// # Panics
+///
+/// Panics if `old` and `new` have different lengths.
+pub fn update_slice(checksum: u16, old: &[u8], new: &[u8]) -> u16 {
+    assert_eq!(old.len(), new.len());
+    let sum = sum(u32::from(!checksum), new);
+    let old_sum = sum_complement(old);
+    !fold(sum + old_sum)
+}
+
+/// Returns the one's complement sum of the complement of every word in `data`.
+fn sum_complement(data: &[u8]) -> u32 {
+    let mut sum = 0u32;
+    for word in data.chunks(2) {
+        let word = if word.len() == 2 {
+            u16::from_be_bytes([word[0], word[1]])
+        } else {
+            u16::from(word[0]) << 8
+        };
+        sum = fold(sum + u32::from(!word)) as u32;
+    }
+    sum
+}
+
+fn fold(mut sum: u32) -> u16 {
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum as u16
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static IPV4_HEADER: [u8; 20] = [
+        0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00, 0xc0, 0xa8, 0x00,
+        0x01, 0xc0, 0xa8, 0x00, 0xc7,
+    ];
+
+    #[test]
+    fn ipv4_header() {
+        assert_eq!(0xb861, checksum(&IPV4_HEADER));
+    }
+
+    #[test]
+    fn verify_including_checksum() {
+        let mut data = IPV4_HEADER;
+        data[10] = 0xb8;
+        data[11] = 0x61;
+        assert_eq!(0, checksum(&data));
+    }
+
+    #[test]
+    fn odd_length() {
+        assert_eq!(!0x0201u16, checksum(&[0x01, 0x01, 0x00, 0x00, 0x01]));
+        assert_eq!(sum(0, &[0xab, 0xcd, 0xef]), sum(0, &[0xab, 0xcd, 0xef, 0x00]));
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(0xffff, checksum(&[]));
+    }
+
+    #[test]
+    fn sum_in_parts() {
+        let whole = sum(0, &IPV4_HEADER);
+        let parts = sum(sum(sum(0, &IPV4_HEADER[..6]), &IPV4_HEADER[6..12]), &IPV4_HEADER[12..]);
+        assert_eq!(whole, parts);
+    }
+
+    #[test]
+    fn sum_carries() {
+        let data = [0xff; 1024];
+        assert_eq!(0xffff, sum(0, &data));
+        assert_eq!(0, checksum(&data));
+    }
+
+    #[test]
+    fn incremental_update() {
+        let mut data = IPV4_HEADER;
+        let original = checksum(&data);
+        data[8] = 0x3f;
+        assert_eq!(checksum(&data), update(original, 0x4011, 0x3f11));
+    }
+
+    #[test]
+    fn incremental_update_slice() {
+        let mut data = IPV4_HEADER;
+        let original = checksum(&data);
+        let new_destination = [10, 20, 30, 40];
+        data[16..20].copy_from_slice(&new_destination);
+        assert_eq!(
+            checksum(&data),
+            update_slice(original, &IPV4_HEADER[16..20], &new_destination)
+        );
+    }
+
+    #[test]
+    fn incremental_update_to_zero_sum() {
+        // RFC 1624 example where equation 2 gives the wrong result
+        assert_eq!(0x0000, update(0xdd2f, 0x5555, 0x3285));
+    }
+}
@@ -0,0 +1,801 @@
+//! Dissection of a whole Ethernet frame into its protocol layers.
+//!
+//! [`dissect`] walks the frame from the Ethernet header and down, using the EtherType and IP
+//! protocol fields to decide how to interpret the next layer, and returns a [`Dissection`] with
+//! a typed view of every layer and its offset in the frame. Layers of unknown types and layers
+//! too short for their header end the walk and are reported as [`Layer::UnknownEtherType`],
+//! [`Layer::UnknownProtocol`] and [`Layer::Truncated`]. Header fields are not validated, a
+//! dissection never fails.
+//!
+//! The dissection is stored inline without allocating, so the number of layers is limited to
+//! [`MAX_LAYERS`]. Use [`dissect_with_depth`] to stop earlier.
+//!
+//! A dissection is displayed as a one line summary with the summary of every layer, like
+//! tcpdump does. [`Dissection::hexdump`] instead shows the bytes of the frame, annotated with the
+//! layer and, for packet types declared with `packet_layout!`, the header field they belong to.
+//!
+//! ```text
+//! 0000  Ethernet 01:02:03:04:05:06 > ff:ff:ff:ff:ff:ff IPv4
+//! 0000    ff ff ff ff ff ff        destination = ff:ff:ff:ff:ff:ff
+//! 0006    01 02 03 04 05 06        source = 01:02:03:04:05:06
+//! 000c    08 00                    ether_type = IPv4 (0x0800)
+//! 000e  IPv4 192.168.0.1 -> 10.0.0.1 ttl=64 proto=UDP len=32 DF
+//! 000e    45                       version = 4
+//! ...
+//! ```
+//!
+//! [`dissect`]: fn.dissect.html
+//! [`dissect_with_depth`]: fn.dissect_with_depth.html
+//! [`Dissection`]: struct.Dissection.html
+//! [`Dissection::hexdump`]: struct.Dissection.html#method.hexdump
+//! [`Layer::UnknownEtherType`]: enum.Layer.html#variant.UnknownEtherType
+//! [`Layer::UnknownProtocol`]: enum.Layer.html#variant.UnknownProtocol
+//! [`Layer::Truncated`]: enum.Layer.html#variant.Truncated
+//! [`MAX_LAYERS`]: constant.MAX_LAYERS.html
+
+use arp::ArpPacket;
+use core::fmt;
+use ethernet::{EtherType, EthernetPacket, LlcPacket, SnapPacket, VlanTag, VlanTags};
+use icmp::IcmpPacket;
+use icmpv6::Icmpv6Packet;
+use ip::Protocol;
+use ipv4::Ipv4Packet;
+use ipv6::{ExtensionHeader, ExtensionHeaders, Ipv6Packet};
+use layout::FieldInfo;
+use tcp::TcpPacket;
+use udp::UdpPacket;
+
+/// The maximum number of layers in a `Dissection`.
+pub const MAX_LAYERS: usize = 16;
+
+/// Dissects the Ethernet frame in `data` into at most `MAX_LAYERS` layers.
+pub fn dissect(data: &[u8]) -> Dissection<'_> {
+    dissect_with_depth(data, MAX_LAYERS)
+}
+
+/// Dissects the Ethernet frame in `data` into at most `max_layers` layers, or `MAX_LAYERS` if
+/// that is lower. The walk stops when the limit is reached, so a `max_layers` of one only
+/// dissects the Ethernet header and three typically stops at the transport layer.
+pub fn dissect_with_depth(data: &[u8], max_layers: usize) -> Dissection<'_> {
+    let mut dissection = Dissection {
+        data,
+        layers: [DissectedLayer {
+            offset: 0,
+            layer: Layer::Payload(&[]),
+        }; MAX_LAYERS],
+        len: 0,
+        max_layers: ::core::cmp::min(max_layers, MAX_LAYERS),
+        depth_limited: false,
+    };
+    dissection.dissect_ethernet(data);
+    dissection
+}
+
+/// The kind of a layer, used to tell what a `Layer::Truncated` layer should have been.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum LayerType {
+    Ethernet,
+    Vlan,
+    Llc,
+    Snap,
+    Arp,
+    Ipv4,
+    Ipv6,
+    Ipv6Extension,
+    Tcp,
+    Udp,
+    Icmp,
+    Icmpv6,
+}
+
+/// One layer of a dissected frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Layer<'a> {
+    Ethernet(EthernetPacket<&'a [u8]>),
+    Vlan(VlanTag<&'a [u8]>),
+    Llc(LlcPacket<&'a [u8]>),
+    Snap(SnapPacket<&'a [u8]>),
+    Arp(ArpPacket<&'a [u8]>),
+    Ipv4(Ipv4Packet<&'a [u8]>),
+    Ipv6(Ipv6Packet<&'a [u8]>),
+    /// An IPv6 extension header. Never one of the variants that end the header chain.
+    Ipv6Extension(ExtensionHeader<'a>),
+    Tcp(TcpPacket<&'a [u8]>),
+    Udp(UdpPacket<&'a [u8]>),
+    Icmp(IcmpPacket<&'a [u8]>),
+    Icmpv6(Icmpv6Packet<&'a [u8]>),
+    /// The payload of the innermost layer, such as the application data of a TCP segment or
+    /// the data of an IP fragment other than the first.
+    Payload(&'a [u8]),
+    /// Data announced by an EtherType that is not dissected.
+    UnknownEtherType(EtherType, &'a [u8]),
+    /// Data announced by an IP protocol number that is not dissected.
+    UnknownProtocol(Protocol, &'a [u8]),
+    /// Data that was too short to hold the header of the given type.
+    Truncated(LayerType, &'a [u8]),
+}
+
+impl<'a> Layer<'a> {
+    /// Returns the data of this layer, from the start of its header. For most layers this
+    /// extends to the end of the enclosing layer, or of the frame.
+    pub fn data(&self) -> &'a [u8] {
+        match *self {
+            Layer::Ethernet(packet) => packet.into_inner(),
+            Layer::Vlan(packet) => packet.into_inner(),
+            Layer::Llc(packet) => packet.into_inner(),
+            Layer::Snap(packet) => packet.into_inner(),
+            Layer::Arp(packet) => packet.into_inner(),
+            Layer::Ipv4(packet) => packet.into_inner(),
+            Layer::Ipv6(packet) => packet.into_inner(),
+            Layer::Ipv6Extension(header) => extension_data(header),
+            Layer::Tcp(packet) => packet.into_inner(),
+            Layer::Udp(packet) => packet.into_inner(),
+            Layer::Icmp(packet) => packet.into_inner(),
+            Layer::Icmpv6(packet) => packet.into_inner(),
+            Layer::Payload(data) |
+            Layer::UnknownEtherType(_, data) |
+            Layer::UnknownProtocol(_, data) |
+            Layer::Truncated(_, data) => data,
+        }
+    }
+
+    /// Returns the length of this layer as given by its length fields, excluding any trailer.
+    fn len(&self) -> usize {
+        match *self {
+            Layer::Arp(packet) => packet.packet_len(),
+            Layer::Ipv4(packet) => packet.packet_len(),
+            Layer::Ipv6(packet) => packet.packet_len(),
+            Layer::Udp(packet) => packet.packet_len(),
+            ref layer => layer.data().len(),
+        }
+    }
+
+    /// Writes `data`, the bytes of the frame from the start of this layer to the start of the
+    /// next, for a hexdump. These can extend past the data of the layer, like the EtherType
+    /// following a VLAN tag. Layers declared with `packet_layout!` get one line per header field
+    /// within those bytes, the rest is written as plain rows.
+    fn fmt_fields(&self, f: &mut fmt::Formatter, offset: usize, data: &[u8]) -> fmt::Result {
+        let fields: &[FieldInfo] = match *self {
+            Layer::Ethernet(_) => EthernetPacket::FIELDS,
+            Layer::Arp(_) => ArpPacket::FIELDS,
+            Layer::Ipv4(_) => Ipv4Packet::FIELDS,
+            _ => return hex_rows(f, offset, data),
+        };
+        let len = data.len();
+        let mut covered = 0;
+        for field in fields {
+            let start = field.byte_offset();
+            let end = start + field.byte_len();
+            if end > len {
+                break;
+            }
+            write!(f, "{:04x}    ", offset + start)?;
+            for byte in &data[start..end] {
+                write!(f, "{:02x} ", byte)?;
+            }
+            for _ in end - start..8 {
+                write!(f, "   ")?;
+            }
+            write!(f, " {} = ", field.name)?;
+            match *self {
+                Layer::Ethernet(packet) => packet.fmt_field(field.name, f)?,
+                Layer::Arp(packet) => packet.fmt_field(field.name, f)?,
+                Layer::Ipv4(packet) => packet.fmt_field(field.name, f)?,
+                _ => unreachable!(),
+            }
+            writeln!(f)?;
+            covered = ::core::cmp::max(covered, end);
+        }
+        hex_rows(f, offset + covered, &data[covered..len])
+    }
+}
+
+/// Writes the summary of the layer, the same as the `Display` implementation of its packet
+/// type. Layers without a packet type are written with their length, like `payload len=4`.
+impl<'a> fmt::Display for Layer<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Layer::Ethernet(packet) => packet.fmt(f),
+            Layer::Vlan(packet) => packet.fmt(f),
+            Layer::Llc(packet) => packet.fmt(f),
+            Layer::Snap(packet) => packet.fmt(f),
+            Layer::Arp(packet) => packet.fmt(f),
+            Layer::Ipv4(packet) => packet.fmt(f),
+            Layer::Ipv6(packet) => packet.fmt(f),
+            Layer::Ipv6Extension(header) => match header {
+                ExtensionHeader::HopByHop(_) => write!(f, "IPv6 hop-by-hop options"),
+                ExtensionHeader::Routing(header) => {
+                    write!(f, "IPv6 routing type={}", header.routing_type())
+                }
+                ExtensionHeader::Fragment(header) => {
+                    write!(f, "IPv6 fragment offset={}", u32::from(header.fragment_offset()) * 8)?;
+                    if header.more_fragments() {
+                        write!(f, " MF")?;
+                    }
+                    Ok(())
+                }
+                ExtensionHeader::DestinationOptions(_) => write!(f, "IPv6 destination options"),
+                ExtensionHeader::Authentication(_) => write!(f, "IPv6 authentication"),
+                _ => write!(f, "IPv6 extension"),
+            },
+            Layer::Tcp(packet) => packet.fmt(f),
+            Layer::Udp(packet) => packet.fmt(f),
+            Layer::Icmp(packet) => packet.fmt(f),
+            Layer::Icmpv6(packet) => packet.fmt(f),
+            Layer::Payload(data) => write!(f, "payload len={}", data.len()),
+            Layer::UnknownEtherType(ether_type, data) => {
+                write!(f, "EtherType {:#} len={}", ether_type, data.len())
+            }
+            Layer::UnknownProtocol(protocol, data) => {
+                write!(f, "protocol {:#} len={}", protocol, data.len())
+            }
+            Layer::Truncated(layer_type, data) => {
+                write!(f, "truncated {:?} len={}", layer_type, data.len())
+            }
+        }
+    }
+}
+
+/// A layer together with its offset from the start of the frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct DissectedLayer<'a> {
+    pub offset: usize,
+    pub layer: Layer<'a>,
+}
+
+/// The layers of a frame, outermost first. Created with `dissect()`.
+#[derive(Debug, Copy, Clone)]
+pub struct Dissection<'a> {
+    data: &'a [u8],
+    layers: [DissectedLayer<'a>; MAX_LAYERS],
+    len: usize,
+    max_layers: usize,
+    depth_limited: bool,
+}
+
+macro_rules! layer_getter {
+    ($(#[$meta:meta])* $fn:ident, $variant:ident, $type:ty) => {
+        $(#[$meta])*
+        pub fn $fn(&self) -> Option<$type> {
+            self.layers().iter().find_map(|dissected| match dissected.layer {
+                Layer::$variant(packet) => Some(packet),
+                _ => None,
+            })
+        }
+    };
+}
+
+impl<'a> Dissection<'a> {
+    /// Returns the dissected frame.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns all layers, outermost first.
+    pub fn layers(&self) -> &[DissectedLayer<'a>] {
+        &self.layers[..self.len]
+    }
+
+    /// Returns true if the frame ended in the middle of a header.
+    pub fn is_truncated(&self) -> bool {
+        self.layers()
+            .iter()
+            .any(|dissected| matches!(dissected.layer, Layer::Truncated(..)))
+    }
+
+    /// Returns true if the walk was stopped by the layer limit rather than by reaching the
+    /// innermost layer.
+    pub fn is_depth_limited(&self) -> bool {
+        self.depth_limited
+    }
+
+    /// Returns a value that displays as an annotated hexdump of the frame. See the module
+    /// documentation for an example.
+    pub fn hexdump(&self) -> Hexdump<'_, 'a> {
+        Hexdump(self)
+    }
+
+    layer_getter!(ethernet, Ethernet, EthernetPacket<&'a [u8]>);
+    layer_getter!(arp, Arp, ArpPacket<&'a [u8]>);
+    layer_getter!(
+        /// Returns the outermost IPv4 layer.
+        ipv4, Ipv4, Ipv4Packet<&'a [u8]>
+    );
+    layer_getter!(
+        /// Returns the outermost IPv6 layer.
+        ipv6, Ipv6, Ipv6Packet<&'a [u8]>
+    );
+    layer_getter!(tcp, Tcp, TcpPacket<&'a [u8]>);
+    layer_getter!(udp, Udp, UdpPacket<&'a [u8]>);
+    layer_getter!(icmp, Icmp, IcmpPacket<&'a [u8]>);
+    layer_getter!(icmpv6, Icmpv6, Icmpv6Packet<&'a [u8]>);
+    layer_getter!(
+        /// Returns the application payload, if the frame has a non-empty one.
+        payload, Payload, &'a [u8]
+    );
+
+    /// Adds a layer starting at `data`, which must be a part of the dissected frame. Returns
+    /// false without adding it if the layer limit is reached, which ends the walk.
+    fn push(&mut self, data: &'a [u8], layer: Layer<'a>) -> bool {
+        if self.len == self.max_layers {
+            self.depth_limited = true;
+            return false;
+        }
+        let offset = data.as_ptr() as usize - self.data.as_ptr() as usize;
+        self.layers[self.len] = DissectedLayer { offset, layer };
+        self.len += 1;
+        true
+    }
+
+    /// Creates a packet from `data` with `new` and adds it as a layer. Adds a truncated layer
+    /// instead if `data` is too short. Returns the packet if it was added.
+    fn push_packet<P: Copy>(
+        &mut self,
+        data: &'a [u8],
+        layer_type: LayerType,
+        new: fn(&'a [u8]) -> Option<P>,
+        layer: fn(P) -> Layer<'a>,
+    ) -> Option<P> {
+        match new(data) {
+            Some(packet) if self.push(data, layer(packet)) => Some(packet),
+            Some(_) => None,
+            None => {
+                self.push(data, Layer::Truncated(layer_type, data));
+                None
+            }
+        }
+    }
+
+    /// Adds the payload as the innermost layer, unless it is empty.
+    fn payload_layer(&mut self, data: &'a [u8]) {
+        if !data.is_empty() {
+            self.push(data, Layer::Payload(data));
+        }
+    }
+
+    fn dissect_ethernet(&mut self, data: &'a [u8]) {
+        let ethernet = match self.push_packet(
+            data,
+            LayerType::Ethernet,
+            EthernetPacket::new,
+            Layer::Ethernet,
+        ) {
+            Some(ethernet) => ethernet,
+            None => return,
+        };
+        for tag in VlanTags::new(data) {
+            let tag_data = tag.into_inner();
+            if !self.push(tag_data, Layer::Vlan(tag)) {
+                return;
+            }
+        }
+        let header_len = ethernet.effective_header_len();
+        let ether_type = ethernet.effective_ether_type();
+        if ether_type.is_vlan_tag() {
+            // Tags are only iterated if they fit, so this one was cut off
+            let rest = &data[header_len - 2..];
+            self.push(rest, Layer::Truncated(LayerType::Vlan, rest));
+        } else if ether_type.is_length() {
+            let length = ::core::cmp::min(usize::from(ether_type.value()), data.len() - header_len);
+            self.dissect_llc(&data[header_len..header_len + length]);
+        } else {
+            self.dissect_ether_type(ether_type, &data[header_len..]);
+        }
+    }
+
+    fn dissect_llc(&mut self, data: &'a [u8]) {
+        // Information and supervisory frames cut off inside their control field are truncated
+        let new = |data| LlcPacket::new(data).filter(|llc| !llc.is_truncated());
+        let llc = match self.push_packet(data, LayerType::Llc, new, Layer::Llc) {
+            Some(llc) => llc,
+            None => return,
+        };
+        let payload = &data[llc.header_len()..];
+        if llc.snap().is_none() {
+            self.payload_layer(payload);
+            return;
+        }
+        let snap = match self.push_packet(payload, LayerType::Snap, SnapPacket::new, Layer::Snap) {
+            Some(snap) => snap,
+            None => return,
+        };
+        let snap_payload = &payload[SnapPacket::MIN_LEN..];
+        match snap.ether_type() {
+            Some(ether_type) => self.dissect_ether_type(ether_type, snap_payload),
+            None => self.payload_layer(snap_payload),
+        }
+    }
+
+    fn dissect_ether_type(&mut self, ether_type: EtherType, data: &'a [u8]) {
+        match ether_type {
+            EtherType::ARP => {
+                self.push_packet(data, LayerType::Arp, ArpPacket::new, Layer::Arp);
+            }
+            EtherType::IPV4 => self.dissect_ipv4(data),
+            EtherType::IPV6 => self.dissect_ipv6(data),
+            _ => {
+                self.push(data, Layer::UnknownEtherType(ether_type, data));
+            }
+        }
+    }
+
+    fn dissect_ipv4(&mut self, data: &'a [u8]) {
+        let ipv4 = match self.push_packet(data, LayerType::Ipv4, Ipv4Packet::new, Layer::Ipv4) {
+            Some(ipv4) => ipv4,
+            None => return,
+        };
+        let payload = &data[ipv4.header_len()..ipv4.packet_len()];
+        if ipv4.fragment_offset() != 0 {
+            self.payload_layer(payload);
+        } else {
+            self.dissect_protocol(ipv4.protocol(), payload);
+        }
+    }
+
+    fn dissect_ipv6(&mut self, data: &'a [u8]) {
+        let ipv6 = match self.push_packet(data, LayerType::Ipv6, Ipv6Packet::new, Layer::Ipv6) {
+            Some(ipv6) => ipv6,
+            None => return,
+        };
+        let payload = &data[ipv6.header_len()..ipv6.packet_len()];
+        for header in ExtensionHeaders::new(ipv6.next_header(), payload) {
+            match header {
+                ExtensionHeader::NoNextHeader => return,
+                ExtensionHeader::UpperLayer(protocol, upper_layer) => {
+                    return self.dissect_protocol(protocol, upper_layer);
+                }
+                ExtensionHeader::FragmentPayload(_, fragment) => {
+                    return self.payload_layer(fragment);
+                }
+                ExtensionHeader::Truncated(_, rest) => {
+                    self.push(rest, Layer::Truncated(LayerType::Ipv6Extension, rest));
+                    return;
+                }
+                _ => (),
+            }
+            if !self.push(extension_data(header), Layer::Ipv6Extension(header)) {
+                return;
+            }
+        }
+    }
+
+    fn dissect_protocol(&mut self, protocol: Protocol, data: &'a [u8]) {
+        match protocol {
+            Protocol::TCP => {
+                let tcp = self.push_packet(data, LayerType::Tcp, TcpPacket::new, Layer::Tcp);
+                if let Some(tcp) = tcp {
+                    self.payload_layer(&data[tcp.header_len()..]);
+                }
+            }
+            Protocol::UDP => {
+                let udp = self.push_packet(data, LayerType::Udp, UdpPacket::new, Layer::Udp);
+                if let Some(udp) = udp {
+                    self.payload_layer(&data[udp.header_len()..udp.packet_len()]);
+                }
+            }
+            Protocol::ICMP => {
+                self.push_packet(data, LayerType::Icmp, IcmpPacket::new, Layer::Icmp);
+            }
+            Protocol::IPV6_ICMP => {
+                self.push_packet(data, LayerType::Icmpv6, Icmpv6Packet::new, Layer::Icmpv6);
+            }
+            _ => {
+                self.push(data, Layer::UnknownProtocol(protocol, data));
+            }
+        }
+    }
+}
+
+/// Writes a one line summary of every layer, separated by commas.
+impl<'a> fmt::Display for Dissection<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, dissected) in self.layers().iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", dissected.layer)?;
+        }
+        Ok(())
+    }
+}
+
+/// An annotated hexdump of a dissected frame. Created with `Dissection::hexdump()`.
+///
+/// Every layer starts with a line with its offset and summary, followed by its bytes. The header
+/// fields of packet types declared with `packet_layout!` get one line each, with the name and
+/// value of the field. Other bytes are written 16 per line. Bytes after the last layer, such as
+/// Ethernet padding, are written last under the heading `trailer`.
+#[derive(Debug, Copy, Clone)]
+pub struct Hexdump<'b, 'a: 'b>(&'b Dissection<'a>);
+
+impl<'b, 'a> fmt::Display for Hexdump<'b, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let layers = self.0.layers();
+        let data = self.0.data();
+        let mut end = 0;
+        for (i, dissected) in layers.iter().enumerate() {
+            let offset = dissected.offset;
+            // A layer ends where the next one starts, the innermost one at its length field
+            let len = match layers.get(i + 1) {
+                Some(next) => next.offset - offset,
+                None => dissected.layer.len(),
+            };
+            end = ::core::cmp::min(offset + len, data.len());
+            writeln!(f, "{:04x}  {}", offset, dissected.layer)?;
+            dissected.layer.fmt_fields(f, offset, &data[offset..end])?;
+        }
+        if end < data.len() {
+            writeln!(f, "{:04x}  trailer", end)?;
+            hex_rows(f, end, &data[end..])?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `data` as hex, 16 bytes per line, each line starting with its offset.
+fn hex_rows(f: &mut fmt::Formatter, offset: usize, data: &[u8]) -> fmt::Result {
+    for (i, row) in data.chunks(16).enumerate() {
+        write!(f, "{:04x}   ", offset + i * 16)?;
+        for byte in row {
+            write!(f, " {:02x}", byte)?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+/// Returns the bytes of an extension header, or nothing for the variants that end the chain.
+fn extension_data<'a>(header: ExtensionHeader<'a>) -> &'a [u8] {
+    match header {
+        ExtensionHeader::HopByHop(header) | ExtensionHeader::DestinationOptions(header) => {
+            header.data()
+        }
+        ExtensionHeader::Routing(header) => header.data(),
+        ExtensionHeader::Fragment(header) => header.data(),
+        ExtensionHeader::Authentication(header) => header.data(),
+        ExtensionHeader::NoNextHeader |
+        ExtensionHeader::UpperLayer(..) |
+        ExtensionHeader::FragmentPayload(..) |
+        ExtensionHeader::Truncated(..) => &[],
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::net::{Ipv4Addr, Ipv6Addr};
+    use ethernet::{EthernetRepr, MacAddr};
+    use ipv4::{Flags, Ipv4Repr};
+    use ipv6::Ipv6Repr;
+
+    fn ethernet(data: &mut [u8], ether_type: EtherType) -> usize {
+        let repr = EthernetRepr {
+            destination: MacAddr::BROADCAST,
+            source: MacAddr([1, 2, 3, 4, 5, 6]),
+            ether_type,
+        };
+        repr.emit(&mut EthernetPacket::new(data).unwrap());
+        repr.buffer_len()
+    }
+
+    fn ipv4(data: &mut [u8], protocol: Protocol, payload_len: usize) -> usize {
+        let repr = Ipv4Repr {
+            dscp: 0,
+            ecn: 0,
+            identification: 1,
+            flags: Flags::DF,
+            fragment_offset: 0,
+            ttl: 64,
+            protocol,
+            source: Ipv4Addr::new(192, 168, 0, 1),
+            destination: Ipv4Addr::new(10, 0, 0, 1),
+            payload_len,
+        };
+        repr.emit(&mut Ipv4Packet::new(data).unwrap());
+        repr.buffer_len()
+    }
+
+    /// Builds an Ethernet frame with an IPv4 packet with a UDP datagram with four bytes of
+    /// payload, padded to 60 bytes.
+    fn udp_frame() -> [u8; 60] {
+        let mut frame = [0; 60];
+        let offset = ethernet(&mut frame, EtherType::IPV4);
+        let offset = offset + ipv4(&mut frame[offset..], Protocol::UDP, 12);
+        let mut udp = UdpPacket::new(&mut frame[offset..]).unwrap();
+        udp.set_length(12);
+        udp.payload_mut().copy_from_slice(&[1, 2, 3, 4]);
+        frame
+    }
+
+    fn offsets(dissection: &Dissection) -> Vec<usize> {
+        dissection.layers().iter().map(|dissected| dissected.offset).collect()
+    }
+
+    #[test]
+    fn udp() {
+        let frame = udp_frame();
+        let dissection = dissect(&frame);
+        assert_eq!(vec![0, 14, 34, 42], offsets(&dissection));
+        assert_eq!(EtherType::IPV4, dissection.ethernet().unwrap().ether_type());
+        assert_eq!(Protocol::UDP, dissection.ipv4().unwrap().protocol());
+        assert_eq!(12, dissection.udp().unwrap().length());
+        assert_eq!(Some(&[1, 2, 3, 4][..]), dissection.payload());
+        assert!(!dissection.is_truncated());
+        assert!(!dissection.is_depth_limited());
+    }
+
+    #[test]
+    fn display() {
+        let frame = udp_frame();
+        assert_eq!(
+            "Ethernet 01:02:03:04:05:06 > ff:ff:ff:ff:ff:ff IPv4, \
+             IPv4 192.168.0.1 -> 10.0.0.1 ttl=64 proto=UDP len=32 DF, \
+             UDP 0 -> 0 len=12, payload len=4",
+            dissect(&frame).to_string()
+        );
+    }
+
+    #[test]
+    fn hexdump() {
+        let frame = udp_frame();
+        let hexdump = dissect(&frame).hexdump().to_string();
+        let lines: Vec<&str> = hexdump.lines().collect();
+        assert_eq!(24, lines.len());
+        assert_eq!("0000  Ethernet 01:02:03:04:05:06 > ff:ff:ff:ff:ff:ff IPv4", lines[0]);
+        assert_eq!("000c    08 00                    ether_type = IPv4 (0x0800)", lines[3]);
+        assert_eq!("000e    45                       header_length = 5", lines[6]);
+        assert_eq!("0017    11                       protocol = UDP (17)", lines[14]);
+        assert_eq!("001e    0a 00 00 01              destination = 10.0.0.1", lines[17]);
+        assert_eq!("0022  UDP 0 -> 0 len=12", lines[18]);
+        assert_eq!("0022    00 00 00 00 00 0c 00 00", lines[19]);
+        assert_eq!("002a    01 02 03 04", lines[21]);
+        assert_eq!("002e  trailer", lines[22]);
+        assert_eq!("002e    00 00 00 00 00 00 00 00 00 00 00 00 00 00", lines[23]);
+    }
+
+    #[test]
+    fn depth() {
+        let frame = udp_frame();
+        let dissection = dissect_with_depth(&frame, 2);
+        assert_eq!(vec![0, 14], offsets(&dissection));
+        assert!(dissection.ipv4().is_some());
+        assert!(dissection.udp().is_none());
+        assert!(dissection.is_depth_limited());
+        assert!(dissect_with_depth(&frame, 0).layers().is_empty());
+        assert!(!dissect_with_depth(&frame, 4).is_depth_limited());
+    }
+
+    #[test]
+    fn truncated() {
+        let frame = udp_frame();
+        let dissection = dissect(&frame[..40]);
+        assert!(dissection.is_truncated());
+        let last = dissection.layers()[2];
+        assert_eq!(34, last.offset);
+        assert_eq!(Layer::Truncated(LayerType::Udp, &frame[34..40]), last.layer);
+        let dissection = dissect(&frame[..10]);
+        let first = dissection.layers()[0];
+        assert_eq!(Layer::Truncated(LayerType::Ethernet, &frame[..10]), first.layer);
+    }
+
+    #[test]
+    fn unknown() {
+        let mut frame = udp_frame();
+        frame[12] = 0x88;
+        frame[13] = 0xcc;
+        let dissection = dissect(&frame);
+        assert_eq!(
+            Layer::UnknownEtherType(EtherType(0x88cc), &frame[14..]),
+            dissection.layers()[1].layer
+        );
+        let mut frame = udp_frame();
+        frame[23] = 47;
+        let dissection = dissect(&frame);
+        assert_eq!(
+            Layer::UnknownProtocol(Protocol(47), &frame[34..46]),
+            dissection.layers()[2].layer
+        );
+    }
+
+    #[test]
+    fn vlan_arp() {
+        let mut frame = [0; 60];
+        ethernet(&mut frame, EtherType::VLAN);
+        frame[12..18].copy_from_slice(&[0x81, 0x00, 0x00, 0x05, 0x08, 0x06]);
+        ArpPacket::new(&mut frame[18..]).unwrap().set_ipv4_over_ethernet_values();
+        let dissection = dissect(&frame);
+        assert_eq!(vec![0, 12, 18], offsets(&dissection));
+        match dissection.layers()[1].layer {
+            Layer::Vlan(tag) => assert_eq!(5, tag.vid()),
+            layer => panic!("Unexpected layer {:?}", layer),
+        }
+        assert!(dissection.arp().is_some());
+    }
+
+    #[test]
+    fn vlan_hexdump() {
+        let mut frame = [0; 60];
+        ethernet(&mut frame, EtherType::VLAN);
+        frame[12..18].copy_from_slice(&[0x81, 0x00, 0x00, 0x05, 0x08, 0x06]);
+        ArpPacket::new(&mut frame[18..]).unwrap().set_ipv4_over_ethernet_values();
+        let hexdump = dissect(&frame).hexdump().to_string();
+        let lines: Vec<&str> = hexdump.lines().collect();
+        assert_eq!(17, lines.len());
+        assert_eq!("000c  VLAN 5 pcp=0", lines[3]);
+        // The VLAN layer extends over the EtherType following the tag
+        assert_eq!("000c    81 00 00 05 08 06", lines[4]);
+        assert_eq!("0012  ARP hrd=1 pro=IPv4 op=0", lines[5]);
+    }
+
+    #[test]
+    fn qinq_hexdump() {
+        let mut frame = [0; 64];
+        ethernet(&mut frame, EtherType::QINQ);
+        frame[12..22].copy_from_slice(&[
+            0x88, 0xa8, 0x00, 0x64, 0x81, 0x00, 0x00, 0x05, 0x08, 0x06,
+        ]);
+        ArpPacket::new(&mut frame[22..]).unwrap().set_ipv4_over_ethernet_values();
+        let hexdump = dissect(&frame).hexdump().to_string();
+        let lines: Vec<&str> = hexdump.lines().collect();
+        assert_eq!(19, lines.len());
+        assert_eq!("000c  VLAN 802.1ad 100 pcp=0", lines[3]);
+        assert_eq!("000c    88 a8 00 64", lines[4]);
+        assert_eq!("0010  VLAN 5 pcp=0", lines[5]);
+        assert_eq!("0010    81 00 00 05 08 06", lines[6]);
+        assert_eq!("0016  ARP hrd=1 pro=IPv4 op=0", lines[7]);
+    }
+
+    #[test]
+    fn ipv6_fragment() {
+        let mut frame = [0; 76];
+        let offset = ethernet(&mut frame, EtherType::IPV6);
+        Ipv6Repr {
+            traffic_class: 0,
+            flow_label: 0,
+            next_header: Protocol::IPV6_FRAG,
+            hop_limit: 64,
+            source: Ipv6Addr::LOCALHOST,
+            destination: Ipv6Addr::LOCALHOST,
+            payload_len: 22,
+        }.emit(&mut Ipv6Packet::new(&mut frame[offset..]).unwrap());
+        frame[54] = Protocol::UDP.value();
+        frame[67] = 14;
+        let dissection = dissect(&frame);
+        assert_eq!(vec![0, 14, 54, 62, 70], offsets(&dissection));
+        assert!(dissection.udp().is_some());
+
+        frame[57] = 0x08;
+        let dissection = dissect(&frame);
+        assert_eq!(vec![0, 14, 54, 62], offsets(&dissection));
+        assert_eq!(Some(&frame[62..76]), dissection.payload());
+    }
+
+    #[test]
+    fn llc_snap() {
+        let mut frame = [0; 60];
+        ethernet(&mut frame, EtherType(36));
+        frame[14..22].copy_from_slice(&[0xaa, 0xaa, 0x03, 0, 0, 0, 0x08, 0x06]);
+        let dissection = dissect(&frame);
+        assert_eq!(vec![0, 14, 17, 22], offsets(&dissection));
+        assert!(dissection.arp().is_some());
+        // The length field cuts the ARP packet short, the rest is padding
+        frame[13] = 35;
+        let dissection = dissect(&frame);
+        assert_eq!(
+            Layer::Truncated(LayerType::Arp, &frame[22..49]),
+            dissection.layers()[3].layer
+        );
+    }
+
+    #[test]
+    fn llc_truncated_control() {
+        let mut frame = [0; 17];
+        ethernet(&mut frame, EtherType(3));
+        frame[14..].copy_from_slice(&[0xaa, 0xaa, 0xd0]);
+        let dissection = dissect(&frame);
+        assert_eq!(vec![0, 14], offsets(&dissection));
+        assert_eq!(Layer::Truncated(LayerType::Llc, &frame[14..]), dissection.layers()[1].layer);
+        assert!(dissection.is_truncated());
+    }
+}
@@ -0,0 +1,100 @@
+//! The Ethernet frame check sequence, a CRC-32 over the whole frame from the destination address
+//! to the end of the payload. It uses the polynomial 0x04C11DB7 in its reflected form, like zlib
+//! and PNG, and is transmitted least significant byte first.
+
+/// The length of the frame check sequence at the end of an Ethernet frame.
+pub const FCS_LEN: usize = 4;
+
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Computes the CRC-32 of `data`, the value used as Ethernet frame check sequence.
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Returns true if `frame` ends with a valid frame check sequence. Returns false if `frame` is
+/// too short to contain one.
+pub fn verify_fcs(frame: &[u8]) -> bool {
+    match split_fcs(frame) {
+        Some((frame, fcs)) => crc32(frame) == fcs,
+        None => false,
+    }
+}
+
+/// Splits `frame` into the frame without its trailing frame check sequence and the value of the
+/// frame check sequence. Returns `None` if `frame` is shorter than `FCS_LEN`.
+pub fn split_fcs(frame: &[u8]) -> Option<(&[u8], u32)> {
+    if frame.len() < FCS_LEN {
+        return None;
+    }
+    let (frame, fcs) = frame.split_at(frame.len() - FCS_LEN);
+    Some((frame, u32::from_le_bytes([fcs[0], fcs[1], fcs[2], fcs[3]])))
+}
+
+/// Computes the frame check sequence over the first `frame_len` bytes of `buffer` and writes it
+/// directly after them. Returns the length of the frame including the frame check sequence, or
+/// `None` without writing anything if `buffer` is too short.
+pub fn append_fcs(buffer: &mut [u8], frame_len: usize) -> Option<usize> {
+    let len = match frame_len.checked_add(FCS_LEN) {
+        Some(len) if len <= buffer.len() => len,
+        _ => return None,
+    };
+    let fcs = crc32(&buffer[..frame_len]);
+    buffer[frame_len..len].copy_from_slice(&fcs.to_le_bytes());
+    Some(len)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+        assert_eq!(0, crc32(&[]));
+    }
+
+    #[test]
+    fn append_and_verify() {
+        let mut buffer = [0xab; 68];
+        assert_eq!(Some(64), append_fcs(&mut buffer, 60));
+        assert!(verify_fcs(&buffer[..64]));
+        buffer[20] = 0;
+        assert!(!verify_fcs(&buffer[..64]));
+    }
+
+    #[test]
+    fn append_too_short() {
+        let mut buffer = [0; 63];
+        assert_eq!(None, append_fcs(&mut buffer, 60));
+        assert_eq!(None, append_fcs(&mut buffer, usize::MAX));
+        assert_eq!([0; 63][..], buffer[..]);
+    }
+
+    #[test]
+    fn split() {
+        let frame = [1, 2, 0x78, 0x56, 0x34, 0x12];
+        assert_eq!(Some((&frame[..2], 0x1234_5678)), split_fcs(&frame));
+        assert_eq!(None, split_fcs(&frame[..3]));
+        assert!(!verify_fcs(&frame[..3]));
+    }
+}
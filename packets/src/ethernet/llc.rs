@@ -0,0 +1,277 @@
+use super::EtherType;
+use core::fmt;
+
+packet!(LlcPacket, MutLlcPacket, 3, |data: &[u8]| {
+    if read_offset!(data, 2, u8) & 0x03 == 0x03 { 3 } else { 4 }
+});
+
+getters!(LlcPacket
+    /// Returns the Destination Service Access Point, including the individual/group bit.
+    pub fn dsap(&self) -> Sap {
+        Sap(read_offset!(self.data(), 0, u8))
+    }
+
+    /// Returns the Source Service Access Point, including the command/response bit.
+    pub fn ssap(&self) -> Sap {
+        Sap(read_offset!(self.data(), 1, u8))
+    }
+
+    /// Returns true if the destination is a group address.
+    pub fn is_group(&self) -> bool {
+        read_offset!(self.data(), 0, u8) & 0x01 != 0
+    }
+
+    /// Returns true if this is a response frame.
+    pub fn is_response(&self) -> bool {
+        read_offset!(self.data(), 1, u8) & 0x01 != 0
+    }
+
+    /// Returns the control field. Unnumbered (U-format) frames have a one byte control field,
+    /// information and supervisory frames have two. The first byte on the wire is the low byte
+    /// of the returned value, since that is where the format bits are. If the second byte is
+    /// missing, see `is_truncated()`, only the first byte is returned.
+    pub fn control(&self) -> u16 {
+        if self.header_len() == 4 {
+            u16::from_le_bytes(read_offset!(self.data(), 2, [u8; 2]))
+        } else {
+            u16::from(read_offset!(self.data(), 2, u8))
+        }
+    }
+
+    /// Returns true if this is an information or supervisory frame and the buffer ends before
+    /// the second byte of its control field.
+    pub fn is_truncated(&self) -> bool {
+        !self.is_unnumbered() && self.header_len() < 4
+    }
+
+    /// Returns true if this is an unnumbered (U-format) frame with a one byte control field.
+    pub fn is_unnumbered(&self) -> bool {
+        read_offset!(self.data(), 2, u8) & 0x03 == 0x03
+    }
+
+    /// Returns the SNAP header following the LLC header if both SAPs are `Sap::SNAP` and the
+    /// frame is an Unnumbered Information frame.
+    pub fn snap(&self) -> Option<SnapPacket<&[u8]>> {
+        if self.dsap() == Sap::SNAP && self.ssap() == Sap::SNAP &&
+            self.control() == LlcPacket::UNNUMBERED_INFORMATION
+        {
+            SnapPacket::new(self.payload())
+        } else {
+            None
+        }
+    }
+);
+
+/// Writes a one line summary of the header, like `LLC dsap=0xaa ssap=0xaa ctrl=0x03`.
+impl<B: AsRef<[u8]>> fmt::Display for LlcPacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "LLC dsap={:#04x} ssap={:#04x} ctrl={:#04x}",
+            self.dsap().value(),
+            self.ssap().value(),
+            self.control()
+        )
+    }
+}
+
+impl LlcPacket<&[u8]> {
+    /// The control field value of an Unnumbered Information (UI) frame.
+    pub const UNNUMBERED_INFORMATION: u16 = 0x03;
+}
+
+setters!(LlcPacket
+    pub fn set_dsap(&mut self, dsap: Sap) {
+        write_offset!(self.data_mut(), 0, dsap.value(), u8);
+    }
+
+    pub fn set_ssap(&mut self, ssap: Sap) {
+        write_offset!(self.data_mut(), 1, ssap.value(), u8);
+    }
+
+    /// Sets the control field. Writes one byte if the format bits in `control` say this is an
+    /// unnumbered frame, two otherwise. See `LlcPacket::control()` for the byte order.
+    ///
+    /// # Panics
+    /// Panics if `control` is two bytes long and the buffer is only three bytes long.
+    pub fn set_control(&mut self, control: u16) {
+        if control & 0x03 == 0x03 {
+            write_offset!(self.data_mut(), 2, control as u8, u8);
+        } else {
+            self.data_mut()[2..4].copy_from_slice(&control.to_le_bytes());
+        }
+    }
+);
+
+/// An IEEE 802.2 Service Access Point address. See [IANA's list] for some of the defined values.
+///
+/// [IANA's list]: https://www.iana.org/assignments/ieee-802-numbers/ieee-802-numbers.xhtml
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Sap(pub u8);
+
+impl Sap {
+    pub const NULL: Sap = Sap(0x00);
+    pub const STP: Sap = Sap(0x42);
+    pub const SNAP: Sap = Sap(0xAA);
+    pub const IPX: Sap = Sap(0xE0);
+    pub const NETBIOS: Sap = Sap(0xF0);
+    pub const GLOBAL: Sap = Sap(0xFF);
+
+    #[inline]
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+
+packet!(SnapPacket, MutSnapPacket, 5);
+
+getters!(SnapPacket
+    /// Returns the Organizationally Unique Identifier of the organization that defines the
+    /// protocol ID.
+    pub fn oui(&self) -> [u8; 3] {
+        read_offset!(self.data(), 0, [u8; 3])
+    }
+
+    pub fn protocol_id(&self) -> u16 {
+        read_offset!(self.data(), 3, u16, from_be)
+    }
+
+    /// Returns the protocol ID as an `EtherType` if the OUI is zero, meaning the protocol ID is an
+    /// EtherType as defined in [RFC 1042].
+    ///
+    /// [RFC 1042]: https://tools.ietf.org/html/rfc1042
+    pub fn ether_type(&self) -> Option<EtherType> {
+        if self.oui() == [0; 3] {
+            Some(EtherType(self.protocol_id()))
+        } else {
+            None
+        }
+    }
+);
+
+/// Writes a one line summary of the header, like `SNAP IPv4` for encapsulated EtherTypes or
+/// `SNAP oui=00:00:0c pid=0x2000` otherwise.
+impl<B: AsRef<[u8]>> fmt::Display for SnapPacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.ether_type() {
+            Some(ether_type) => write!(f, "SNAP {:#}", ether_type),
+            None => {
+                let oui = self.oui();
+                write!(
+                    f,
+                    "SNAP oui={:02x}:{:02x}:{:02x} pid={:#06x}",
+                    oui[0],
+                    oui[1],
+                    oui[2],
+                    self.protocol_id()
+                )
+            }
+        }
+    }
+}
+
+setters!(SnapPacket
+    pub fn set_oui(&mut self, oui: [u8; 3]) {
+        write_offset!(self.data_mut(), 0, oui, [u8; 3]);
+    }
+
+    pub fn set_protocol_id(&mut self, protocol_id: u16) {
+        write_offset!(self.data_mut(), 3, protocol_id, u16, to_be);
+    }
+);
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! llc_setget_test {
+        ($name:ident, $set_name:ident, $value:expr, $offset:expr, $expected:expr) => {
+            setget_test!(MutLlcPacket, $name, $set_name, $value, $offset, $expected);
+        }
+    }
+
+    llc_setget_test!(dsap, set_dsap, Sap(0xff), 0, [0xff]);
+    llc_setget_test!(ssap, set_ssap, Sap(0xff), 1, [0xff]);
+    llc_setget_test!(control, set_control, 0xff, 2, [0xff]);
+
+    #[test]
+    fn set_control_two_bytes() {
+        let mut backing_data = [0; 4];
+        MutLlcPacket::new(&mut backing_data).unwrap().set_control(0xabcd);
+        assert_eq!([0, 0, 0xcd, 0xab], backing_data);
+    }
+
+    macro_rules! snap_setget_test {
+        ($name:ident, $set_name:ident, $value:expr, $offset:expr, $expected:expr) => {
+            setget_test!(MutSnapPacket, $name, $set_name, $value, $offset, $expected);
+        }
+    }
+
+    snap_setget_test!(oui, set_oui, [1, 2, 3], 0, [1, 2, 3]);
+    snap_setget_test!(protocol_id, set_protocol_id, 0xabcd, 3, [0xab, 0xcd]);
+
+    #[test]
+    fn stp_header() {
+        let backing_data = [0x42, 0x42, 0x03, 0, 0];
+        let testee = LlcPacket::new(&backing_data).unwrap();
+        assert_eq!(Sap::STP, testee.dsap());
+        assert!(!testee.is_group());
+        assert!(!testee.is_response());
+        assert!(testee.is_unnumbered());
+        assert_eq!(3, testee.header_len());
+        assert_eq!(&[0, 0], testee.payload());
+        assert!(testee.snap().is_none());
+    }
+
+    #[test]
+    fn information_frame() {
+        let backing_data = [0xf0, 0xf1, 0x02, 0x04, 0xff];
+        let testee = LlcPacket::new(&backing_data).unwrap();
+        assert!(testee.is_response());
+        assert!(!testee.is_unnumbered());
+        assert_eq!(0x0402, testee.control());
+        assert_eq!(4, testee.header_len());
+        assert_eq!(&[0xff], testee.payload());
+    }
+
+    #[test]
+    fn information_frame_truncated() {
+        let backing_data = [0xf0, 0xf0, 0x00];
+        let testee = LlcPacket::new(&backing_data).unwrap();
+        assert_eq!(3, testee.header_len());
+        assert!(testee.payload().is_empty());
+        assert!(testee.is_truncated());
+        assert_eq!(0x00, testee.control());
+        assert_eq!(None, testee.snap());
+        assert_eq!("LLC dsap=0xf0 ssap=0xf0 ctrl=0x00", testee.to_string());
+        let snap_data = [0xaa, 0xaa, 0xd0];
+        assert_eq!(None, LlcPacket::new(&snap_data).unwrap().snap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_control_truncated() {
+        let mut backing_data = [0xf0, 0xf0, 0x03];
+        MutLlcPacket::new(&mut backing_data).unwrap().set_control(0x0100);
+    }
+
+    #[test]
+    fn snap_header() {
+        let backing_data = [0xaa, 0xaa, 0x03, 0, 0, 0, 0x08, 0x06, 0xff];
+        let testee = LlcPacket::new(&backing_data).unwrap();
+        let snap = testee.snap().unwrap();
+        assert_eq!([0, 0, 0], snap.oui());
+        assert_eq!(Some(EtherType::ARP), snap.ether_type());
+        assert_eq!(&[0xff], snap.payload());
+    }
+
+    #[test]
+    fn snap_header_vendor_protocol() {
+        let backing_data = [0, 0, 0x0c, 0x20, 0x00];
+        let testee = SnapPacket::new(&backing_data).unwrap();
+        assert_eq!(0x2000, testee.protocol_id());
+        assert_eq!(None, testee.ether_type());
+    }
+}
@@ -1,21 +1,18 @@
-use std::error::Error;
-use std::fmt;
-use std::str::FromStr;
+use core::error::Error;
+use core::fmt;
+use core::net::{Ipv4Addr, Ipv6Addr};
+use core::str::{self, FromStr};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct MacAddrLengthError;
 
 impl fmt::Display for MacAddrLengthError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.description().fmt(f)
+        write!(f, "Given data not six bytes long")
     }
 }
 
-impl Error for MacAddrLengthError {
-    fn description(&self) -> &str {
-        "Given data not six bytes long"
-    }
-}
+impl Error for MacAddrLengthError {}
 
 /// A MAC address. Six bytes representing a link layer network address.
 #[derive(Copy, Clone, Default, Eq, PartialEq, Hash)]
@@ -25,6 +22,40 @@ impl MacAddr {
     /// The broadcast MAC address. Used to broadcast to the local network.
     pub const BROADCAST: MacAddr = MacAddr([0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
 
+    /// Returns the MAC address IPv4 packets to the given multicast group are sent to,
+    /// `01:00:5e` followed by the lower 23 bits of the group, see RFC 1112. Returns `None` if
+    /// `ip` is not a multicast address.
+    pub fn from_ipv4_multicast(ip: Ipv4Addr) -> Option<MacAddr> {
+        if !ip.is_multicast() {
+            return None;
+        }
+        let ip = ip.octets();
+        Some(MacAddr([0x01, 0x00, 0x5e, ip[1] & 0x7f, ip[2], ip[3]]))
+    }
+
+    /// Returns the MAC address IPv6 packets to the given multicast group are sent to, `33:33`
+    /// followed by the lower 32 bits of the group, see RFC 2464. Returns `None` if `ip` is not a
+    /// multicast address.
+    pub fn from_ipv6_multicast(ip: Ipv6Addr) -> Option<MacAddr> {
+        if !ip.is_multicast() {
+            return None;
+        }
+        let ip = ip.octets();
+        Some(MacAddr([0x33, 0x33, ip[12], ip[13], ip[14], ip[15]]))
+    }
+
+    /// Returns true if this is in the range of MAC addresses IPv4 multicast groups map to,
+    /// `01:00:5e:00:00:00` to `01:00:5e:7f:ff:ff`.
+    pub fn is_ipv4_multicast(&self) -> bool {
+        self.0[..3] == [0x01, 0x00, 0x5e] && self.0[3] & 0x80 == 0
+    }
+
+    /// Returns true if this is in the range of MAC addresses IPv6 multicast groups map to,
+    /// `33:33:00:00:00:00` to `33:33:ff:ff:ff:ff`.
+    pub fn is_ipv6_multicast(&self) -> bool {
+        self.0[..2] == [0x33, 0x33]
+    }
+
     /// Constructs a `MacAddr` from a slice of bytes.
     /// Will fail if the given slice is not 6 bytes long.
     pub fn try_from_slice(slice: &[u8]) -> Result<MacAddr, MacAddrLengthError> {
@@ -48,6 +79,69 @@ impl MacAddr {
     pub fn from_bytes(b0: u8, b1: u8, b2: u8, b3: u8, b4: u8, b5: u8) -> MacAddr {
         MacAddr([b0, b1, b2, b3, b4, b5])
     }
+
+    /// Returns true if this is the broadcast address, `ff:ff:ff:ff:ff:ff`.
+    pub fn is_broadcast(&self) -> bool {
+        *self == MacAddr::BROADCAST
+    }
+
+    /// Returns true if the group bit, the least significant bit of the first byte, is set. This
+    /// includes the broadcast address.
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    /// Returns true if the address identifies a single interface, meaning it is not multicast.
+    pub fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    /// Returns true if the local bit, the second least significant bit of the first byte, is set.
+    /// Such addresses are assigned by software or the network administrator instead of being
+    /// derived from an OUI assigned by the IEEE.
+    pub fn is_locally_administered(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
+
+    /// Returns the Organizationally Unique Identifier, the first three bytes of the address. For
+    /// universally administered addresses this identifies the vendor of the interface.
+    pub fn oui(&self) -> [u8; 3] {
+        [self.0[0], self.0[1], self.0[2]]
+    }
+
+    /// Returns the modified EUI-64 form of the address, used as IPv6 interface identifier by
+    /// stateless address autoconfiguration. `ff:fe` is inserted in the middle of the address and
+    /// the local bit is inverted, see RFC 4291 appendix A.
+    pub fn to_modified_eui64(&self) -> [u8; 8] {
+        let m = self.0;
+        [m[0] ^ 0x02, m[1], m[2], 0xff, 0xfe, m[3], m[4], m[5]]
+    }
+
+    /// Returns the IPv6 link-local address in `fe80::/64` with the modified EUI-64 form of this
+    /// address as interface identifier.
+    pub fn to_link_local(&self) -> Ipv6Addr {
+        let mut ip = [0; 16];
+        ip[0] = 0xfe;
+        ip[1] = 0x80;
+        ip[8..].copy_from_slice(&self.to_modified_eui64());
+        Ipv6Addr::from(ip)
+    }
+}
+
+/// Returns the solicited-node multicast address of `ip`, `ff02::1:ff00:0/104` followed by the
+/// lower 24 bits of `ip`. Neighbor solicitations for `ip` are sent to this group, see RFC 4291.
+pub fn solicited_node(ip: Ipv6Addr) -> Ipv6Addr {
+    let ip = ip.octets();
+    let mut group = [0; 16];
+    group[..2].copy_from_slice(&[0xff, 0x02]);
+    group[11..13].copy_from_slice(&[0x01, 0xff]);
+    group[13..].copy_from_slice(&ip[13..]);
+    Ipv6Addr::from(group)
+}
+
+/// Returns true if `ip` is a solicited-node multicast address, in `ff02::1:ff00:0/104`.
+pub fn is_solicited_node(ip: Ipv6Addr) -> bool {
+    ip.octets()[..13] == [0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0xff]
 }
 
 impl AsRef<[u8]> for MacAddr {
@@ -68,33 +162,90 @@ impl From<[u8; 6]> for MacAddr {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub struct MacAddrParseError(String);
+/// Error returned when parsing a MAC address from a string fails.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MacAddrParseError {
+    /// The string contains a character that is neither a hexadecimal digit nor a separator.
+    InvalidDigit,
+    /// The string mixes the `:`, `-` and `.` separators.
+    Separator,
+    /// A group of digits between separators is empty or has too many digits, or a dotted group
+    /// does not have four digits.
+    GroupLength,
+    /// The string has too few or too many groups, or bare hexadecimal is not twelve digits long.
+    Length,
+}
 
 impl fmt::Display for MacAddrParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Invalid MAC string: {}", self.0)
+        match *self {
+            MacAddrParseError::InvalidDigit => write!(f, "Invalid digit in MAC address"),
+            MacAddrParseError::Separator => write!(f, "Mixed separators in MAC address"),
+            MacAddrParseError::GroupLength => write!(f, "Wrong group length in MAC address"),
+            MacAddrParseError::Length => write!(f, "Wrong number of groups in MAC address"),
+        }
     }
 }
 
-impl Error for MacAddrParseError {
-    fn description(&self) -> &str {
-        "Invalid MAC address string"
-    }
-}
+impl Error for MacAddrParseError {}
 
 impl FromStr for MacAddr {
     type Err = MacAddrParseError;
 
+    /// Parses a MAC address in any of the common notations: bytes separated by colons
+    /// (`00:11:22:33:44:55`) or dashes (`00-11-22-33-44-55`), where leading zeros can be left
+    /// out, Cisco style groups of two bytes separated by dots (`0011.2233.4455`) or bare
+    /// hexadecimal (`001122334455`). Digits can be upper or lower case.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use std::num::ParseIntError;
+        let mut mac = MacAddr::default();
+        match s.chars().find(|c| !c.is_ascii_hexdigit()) {
+            None => parse_groups(&mut mac.0, s, 6, None)?,
+            Some(separator @ ':') | Some(separator @ '-') => {
+                parse_groups(&mut mac.0, s, 1, Some(separator))?
+            }
+            Some('.') => parse_groups(&mut mac.0, s, 2, Some('.'))?,
+            Some(_) => return Err(MacAddrParseError::InvalidDigit),
+        }
+        Ok(mac)
+    }
+}
 
-        let bytes: Result<Vec<u8>, ParseIntError> =
-            s.split(":").map(|s| u8::from_str_radix(s, 16)).collect();
-        match bytes {
-            Ok(ref bytes) if bytes.len() == 6 => Ok(Self::from_slice(&bytes)),
-            _ => Err(MacAddrParseError(s.to_owned())),
+/// Parses `s` into `mac` as groups of `group_bytes` bytes separated by `separator`. Groups of
+/// one byte may leave out the leading zero, longer groups must have all their digits.
+fn parse_groups(
+    mac: &mut [u8; 6],
+    s: &str,
+    group_bytes: usize,
+    separator: Option<char>,
+) -> Result<(), MacAddrParseError> {
+    let mut groups = s.split(separator.unwrap_or(' '));
+    for bytes in mac.chunks_mut(group_bytes) {
+        let group = groups.next().ok_or(MacAddrParseError::Length)?;
+        if let Some(c) = group.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(match c {
+                ':' | '-' | '.' => MacAddrParseError::Separator,
+                _ => MacAddrParseError::InvalidDigit,
+            });
+        }
+        let max_digits = 2 * group_bytes;
+        let group_length_ok = match group_bytes {
+            1 => !group.is_empty() && group.len() <= max_digits,
+            _ => group.len() == max_digits,
+        };
+        if !group_length_ok {
+            return Err(match separator {
+                Some(_) => MacAddrParseError::GroupLength,
+                None => MacAddrParseError::Length,
+            });
         }
+        for (byte, digits) in bytes.iter_mut().rev().zip(group.as_bytes().rchunks(2)) {
+            // Only ASCII hexadecimal digits remain, so this can not fail
+            *byte = u8::from_str_radix(str::from_utf8(digits).unwrap(), 16).unwrap();
+        }
+    }
+    match groups.next() {
+        None => Ok(()),
+        Some(_) => Err(MacAddrParseError::Length),
     }
 }
 
@@ -112,7 +263,7 @@ impl fmt::Display for MacAddr {
 
 impl fmt::Debug for MacAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        (self as &fmt::Display).fmt(f)
+        fmt::Display::fmt(self, f)
     }
 }
 
@@ -164,4 +315,82 @@ mod tests {
         let result = MacAddr::from_str("01:02:ff:ac:13:37");
         assert_eq!(result, Ok(MacAddr([0x01, 0x02, 0xff, 0xac, 0x13, 0x37])));
     }
+
+    #[test]
+    fn from_str_notations() {
+        let mac = MacAddr([0x01, 0x02, 0xff, 0xac, 0x13, 0x37]);
+        assert_eq!(Ok(mac), MacAddr::from_str("01-02-FF-AC-13-37"));
+        assert_eq!(Ok(mac), MacAddr::from_str("0102.ffac.1337"));
+        assert_eq!(Ok(mac), MacAddr::from_str("0102ffAC1337"));
+        assert_eq!(Ok(MacAddr([0, 1, 2, 0xa, 0, 0])), MacAddr::from_str("0:1:2:a:00:0"));
+    }
+
+    #[test]
+    fn from_str_invalid() {
+        use self::MacAddrParseError::*;
+        assert_eq!(Err(Length), MacAddr::from_str("01:02:ff:ac:13"));
+        assert_eq!(Err(Length), MacAddr::from_str("01:02:ff:ac:13:37:00"));
+        assert_eq!(Err(Length), MacAddr::from_str("01:02:ff:ac:13:37:"));
+        assert_eq!(Err(InvalidDigit), MacAddr::from_str("01:02:ff:ac:13:xx"));
+        assert_eq!(Err(InvalidDigit), MacAddr::from_str("01 02 ff ac 13 37"));
+        assert_eq!(Err(InvalidDigit), MacAddr::from_str("01:02:ff:ac:13:+3"));
+        assert_eq!(Err(Length), MacAddr::from_str(""));
+        assert_eq!(Err(Length), MacAddr::from_str("0102ffac133"));
+        assert_eq!(Err(Separator), MacAddr::from_str("01:02-ff:ac:13:37"));
+        assert_eq!(Err(GroupLength), MacAddr::from_str("01:002:ff:ac:13:37"));
+        assert_eq!(Err(GroupLength), MacAddr::from_str("01::ff:ac:13:37"));
+        assert_eq!(Err(GroupLength), MacAddr::from_str("102.ffac.1337"));
+        assert_eq!(Err(Length), MacAddr::from_str("0102.ffac"));
+    }
+
+    #[test]
+    fn classification() {
+        assert!(MacAddr::BROADCAST.is_broadcast());
+        assert!(MacAddr::BROADCAST.is_multicast());
+        let multicast = MacAddr([0x01, 0x00, 0x5e, 0, 0, 1]);
+        assert!(multicast.is_multicast() && !multicast.is_unicast() && !multicast.is_broadcast());
+        let unicast = MacAddr([0x00, 0x1b, 0x21, 1, 2, 3]);
+        assert!(unicast.is_unicast() && !unicast.is_locally_administered());
+        assert_eq!([0x00, 0x1b, 0x21], unicast.oui());
+        assert!(MacAddr([0x02, 0, 0, 0, 0, 1]).is_locally_administered());
+    }
+
+    #[test]
+    fn ipv4_multicast() {
+        let mac = MacAddr::from_ipv4_multicast(Ipv4Addr::new(239, 255, 1, 2)).unwrap();
+        assert_eq!(MacAddr([0x01, 0x00, 0x5e, 0x7f, 0x01, 0x02]), mac);
+        assert!(mac.is_ipv4_multicast() && mac.is_multicast());
+        // The upper 5 bits of the group are lost
+        assert_eq!(Some(mac), MacAddr::from_ipv4_multicast(Ipv4Addr::new(224, 127, 1, 2)));
+        assert_eq!(None, MacAddr::from_ipv4_multicast(Ipv4Addr::new(192, 168, 1, 2)));
+        assert!(!MacAddr([0x01, 0x00, 0x5e, 0x80, 0, 1]).is_ipv4_multicast());
+    }
+
+    #[test]
+    fn ipv6_multicast() {
+        let ip = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 1, 0xff0a, 0x0b0c);
+        let mac = MacAddr::from_ipv6_multicast(ip).unwrap();
+        assert_eq!(MacAddr([0x33, 0x33, 0xff, 0x0a, 0x0b, 0x0c]), mac);
+        assert!(mac.is_ipv6_multicast() && !mac.is_ipv4_multicast());
+        assert_eq!(None, MacAddr::from_ipv6_multicast(Ipv6Addr::LOCALHOST));
+    }
+
+    #[test]
+    fn solicited_node_address() {
+        let ip = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0x21b, 0x21ff, 0xfe0a, 0x0b0c);
+        let group = solicited_node(ip);
+        assert_eq!(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 1, 0xff0a, 0x0b0c), group);
+        assert!(is_solicited_node(group));
+        assert!(!is_solicited_node(ip));
+        assert!(!is_solicited_node(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1)));
+    }
+
+    #[test]
+    fn eui64() {
+        let mac = MacAddr([0x00, 0x1b, 0x21, 0x0a, 0x0b, 0x0c]);
+        assert_eq!([0x02, 0x1b, 0x21, 0xff, 0xfe, 0x0a, 0x0b, 0x0c], mac.to_modified_eui64());
+        assert_eq!("fe80::21b:21ff:fe0a:b0c".parse::<Ipv6Addr>().unwrap(), mac.to_link_local());
+        let local = MacAddr([0x02, 0, 0, 0, 0, 1]);
+        assert_eq!([0, 0, 0, 0xff, 0xfe, 0, 0, 1], local.to_modified_eui64());
+    }
 }
@@ -1,57 +1,482 @@
+use core::error::Error;
+use core::fmt;
+
 mod macaddr;
 pub use self::macaddr::*;
 
-packet!(EthernetPacket, MutEthernetPacket, 14);
+mod fcs;
+pub use self::fcs::*;
+
+mod llc;
+pub use self::llc::*;
+
+mod vlan;
+pub use self::vlan::*;
+
+packet_layout! {
+    packet: EthernetPacket, MutEthernetPacket;
+    tests: ethernet_layout_tests;
+    fields: {
+        // The addresses are copied as slices, which is faster than going through a u64.
+        [manual]
+        destination / set_destination: MacAddr = 0, 48, be;
+        [manual]
+        source / set_source: MacAddr = 48, 48, be;
+        /// Returns the EtherType field directly following the source address. For tagged frames
+        /// this is the TPID of the outermost VLAN tag, see `effective_ether_type()`.
+        ether_type / set_ether_type: EtherType = 96, 16, be;
+    }
+}
+
+impl<'a> EthernetPacket<&'a [u8]> {
+    /// Creates a packet from a frame that ends with a frame check sequence. The returned packet
+    /// covers everything but the last `FCS_LEN` bytes, so the FCS is not part of `payload()`.
+    /// The FCS is not verified, use `verify_fcs()` on `data` for that.
+    ///
+    /// Returns `None` if `data` is too short to hold both the header and the FCS.
+    pub fn new_with_fcs(data: &'a [u8]) -> Option<EthernetPacket<&'a [u8]>> {
+        if data.len() < Self::MIN_LEN + FCS_LEN {
+            return None;
+        }
+        Self::new(&data[..data.len() - FCS_LEN])
+    }
+}
 
 getters!(EthernetPacket
     pub fn destination(&self) -> MacAddr {
-        MacAddr::from_slice(&self.0[0..6])
+        MacAddr::from_slice(&self.data()[0..6])
     }
 
     pub fn source(&self) -> MacAddr {
-        MacAddr::from_slice(&self.0[6..12])
+        MacAddr::from_slice(&self.data()[6..12])
+    }
+
+    /// Returns an iterator over the 802.1Q and 802.1ad VLAN tags in this frame, outermost first.
+    pub fn vlan_tags(&self) -> VlanTags<'_> {
+        VlanTags::new(self.data())
+    }
+
+    /// Returns the length of the header including all VLAN tags and the EtherType following
+    /// them.
+    pub fn effective_header_len(&self) -> usize {
+        let mut tags = self.vlan_tags();
+        while tags.next().is_some() {}
+        tags.offset() + 2
+    }
+
+    /// Returns the EtherType of the payload, after skipping all VLAN tags. For untagged frames
+    /// this is the same as `ether_type()`.
+    pub fn effective_ether_type(&self) -> EtherType {
+        let offset = self.effective_header_len() - 2;
+        EtherType(read_offset!(self.data(), offset, u16, from_be))
+    }
+
+    /// Returns the payload after all VLAN tags. For untagged frames this is the same as
+    /// `payload()`.
+    pub fn effective_payload(&self) -> &[u8] {
+        &self.data()[self.effective_header_len()..]
+    }
+
+    /// Returns whether this is an Ethernet II frame or an IEEE 802.3 frame, based on the
+    /// effective EtherType field.
+    pub fn frame_format(&self) -> FrameFormat {
+        if self.effective_ether_type().is_length() {
+            FrameFormat::Ieee8023
+        } else {
+            FrameFormat::EthernetII
+        }
+    }
+
+    /// Returns the value of the length field if this is an IEEE 802.3 frame.
+    pub fn ieee8023_length(&self) -> Option<u16> {
+        let ether_type = self.effective_ether_type();
+        if ether_type.is_length() {
+            Some(ether_type.value())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the payload of an IEEE 802.3 frame trimmed to the length field, excluding any
+    /// padding. Returns `None` for Ethernet II frames. The payload usually starts with an LLC
+    /// header, see `LlcPacket`.
+    pub fn ieee8023_payload(&self) -> Option<&[u8]> {
+        let length = self.ieee8023_length()? as usize;
+        let payload = self.effective_payload();
+        Some(&payload[..::core::cmp::min(length, payload.len())])
+    }
+
+    /// Computes the frame check sequence over the whole packet. The packet must not include a
+    /// trailing FCS itself.
+    pub fn calculate_fcs(&self) -> u32 {
+        crc32(self.data())
     }
 
-    pub fn ether_type(&self) -> EtherType {
-        EtherType(read_offset!(self.0, 12, u16, from_be))
+    /// Returns the LLC header at the start of the payload of an IEEE 802.3 frame. Returns `None`
+    /// for Ethernet II frames and frames too short to hold an LLC header.
+    pub fn llc(&self) -> Option<LlcPacket<&[u8]>> {
+        LlcPacket::new(self.ieee8023_payload()?)
     }
 );
 
-setters!(MutEthernetPacket
+impl<B: AsRef<[u8]>> EthernetPacket<B> {
+    /// Checks that the frame holds all of its VLAN tags and the EtherType following them, and
+    /// that the length field of an IEEE 802.3 frame is not longer than the payload. A shorter
+    /// length field is allowed, the rest of the payload is padding.
+    pub fn validate(&self) -> Result<(), EthernetError> {
+        // Tags are only skipped if they fit, so a tag here is one that was cut off
+        if self.effective_ether_type().is_vlan_tag() {
+            return Err(EthernetError::Truncated);
+        }
+        match self.ieee8023_length() {
+            Some(length) if usize::from(length) > self.effective_payload().len() => {
+                Err(EthernetError::Length)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+new_checked!(EthernetPacket, EthernetError);
+
+/// Writes a one line summary of the header, like `Ethernet 00:01:02:03:04:05 > ff:ff:ff:ff:ff:ff
+/// IPv4`. For IEEE 802.3 frames the length is written instead of the EtherType.
+impl<B: AsRef<[u8]>> fmt::Display for EthernetPacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Ethernet {} > {}", self.source(), self.destination())?;
+        let ether_type = self.ether_type();
+        if ether_type.is_length() {
+            write!(f, " length {}", ether_type.value())
+        } else {
+            write!(f, " {:#}", ether_type)
+        }
+    }
+}
+
+setters!(EthernetPacket
     pub fn set_destination(&mut self, destination: MacAddr) {
-        self.0[0..6].copy_from_slice(destination.as_ref());
+        self.data_mut()[0..6].copy_from_slice(destination.as_ref());
     }
 
     pub fn set_source(&mut self, source: MacAddr) {
-        self.0[6..12].copy_from_slice(source.as_ref());
+        self.data_mut()[6..12].copy_from_slice(source.as_ref());
     }
+);
 
-    pub fn set_ether_type(&mut self, ether_type: EtherType) {
-        write_offset!(self.0, 12, ether_type.value(), u16, to_be)
+impl<'a> EthernetPacket<&'a mut [u8]> {
+    /// Creates a mutable packet from a buffer that ends with room for a frame check sequence,
+    /// see `new_with_fcs()`. Once the frame is complete, write its FCS with `append_fcs()` on
+    /// the buffer.
+    pub fn new_with_fcs_mut(data: &'a mut [u8]) -> Option<MutEthernetPacket<'a>> {
+        if data.len() < EthernetPacket::MIN_LEN + FCS_LEN {
+            return None;
+        }
+        let len = data.len() - FCS_LEN;
+        Self::new(&mut data[..len])
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> EthernetPacket<B> {
+    /// Writes a single 802.1Q tag with the given TCI after the source address, followed by
+    /// `ether_type`. See `set_vlan_tags()`.
+    pub fn set_vlan_tag(&mut self, tci: Tci, ether_type: EtherType) -> Option<usize> {
+        self.set_vlan_tags(&[(EtherType::VLAN, tci)], ether_type)
     }
-);
+
+    /// Writes the given VLAN tags, outermost first, after the source address, followed by
+    /// `ether_type`. Each tag is given as its TPID and TCI. For 802.1ad QinQ the outer tag
+    /// should have the TPID `EtherType::QINQ` and the inner `EtherType::VLAN`.
+    ///
+    /// Returns the length of the written header, where the payload starts, or `None` without
+    /// writing anything if the buffer is too short.
+    pub fn set_vlan_tags(
+        &mut self,
+        tags: &[(EtherType, Tci)],
+        ether_type: EtherType,
+    ) -> Option<usize> {
+        let header_len = 14 + tags.len() * VlanTag::MIN_LEN;
+        if self.data().len() < header_len {
+            return None;
+        }
+        for (i, &(tpid, tci)) in tags.iter().enumerate() {
+            let offset = 12 + i * VlanTag::MIN_LEN;
+            let mut tag = MutVlanTag::new(&mut self.data_mut()[offset..]).unwrap();
+            tag.set_tpid(tpid);
+            tag.set_tci(tci);
+        }
+        write_offset!(self.data_mut(), header_len - 2, ether_type.value(), u16, to_be);
+        Some(header_len)
+    }
+
+    /// Returns a mutable slice to the payload after all VLAN tags.
+    pub fn effective_payload_mut(&mut self) -> &mut [u8] {
+        let header_len = self.effective_header_len();
+        &mut self.data_mut()[header_len..]
+    }
+}
+
+
+/// The two frame formats that can be told apart by the EtherType field.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum FrameFormat {
+    /// The EtherType field contains the protocol of the payload.
+    EthernetII,
+    /// The EtherType field contains the length of the payload, which starts with an LLC header.
+    Ieee8023,
+}
 
 
 /// A representation of the 16 bit EtherType header field of an Ethernet packet.
 ///
-/// A few select, commonly used, values are attached as associated constants. Their values are
-/// defined on [IANA's website].
+/// The EtherTypes of the protocols in [IANA's list] are attached as associated constants, along
+/// with `QINQ_LEGACY`. The blocks of values registered to individual vendors are left out.
+/// `name()` returns a short name for each of them, and `FromStr` parses names as well as
+/// numbers.
 ///
-/// [IANA's website]: https://www.iana.org/assignments/ieee-802-numbers/ieee-802-numbers.xhtml
+/// [IANA's list]: https://www.iana.org/assignments/ieee-802-numbers/ieee-802-numbers.xhtml
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct EtherType(pub u16);
 
+named_values!(EtherType, u16, EtherTypeParseError, "Unknown EtherType", {
+    /// Internet Protocol version 4.
+    IPV4 = 0x0800, "IPv4";
+    /// X.75 Internet.
+    X75 = 0x0801, "X.75";
+    /// NBS Internet.
+    NBS = 0x0802, "NBS";
+    /// ECMA Internet.
+    ECMA = 0x0803, "ECMA";
+    CHAOSNET = 0x0804, "Chaosnet";
+    /// X.25 Level 3.
+    X25 = 0x0805, "X.25";
+    /// Address Resolution Protocol.
+    ARP = 0x0806, "ARP";
+    /// XNS Compatibility.
+    XNS = 0x0807, "XNS";
+    /// Frame Relay ARP.
+    FRAME_RELAY_ARP = 0x0808, "FR-ARP";
+    /// Banyan VINES.
+    VINES = 0x0BAD, "VINES";
+    /// VINES Loopback.
+    VINES_LOOPBACK = 0x0BAE, "VINES-Loopback";
+    /// VINES Echo.
+    VINES_ECHO = 0x0BAF, "VINES-Echo";
+    /// Transparent Interconnection of Lots of Links.
+    TRILL = 0x22F3, "TRILL";
+    /// Layer 2 IS-IS.
+    L2_IS_IS = 0x22F4, "L2-IS-IS";
+    /// DEC MOP Dump/Load.
+    DEC_MOP_DUMP_LOAD = 0x6001, "MOP-DL";
+    /// DEC MOP Remote Console.
+    DEC_MOP_REMOTE_CONSOLE = 0x6002, "MOP-RC";
+    /// DEC DECNET Phase IV Route.
+    DECNET = 0x6003, "DECnet";
+    /// DEC Local Area Transport.
+    DEC_LAT = 0x6004, "LAT";
+    /// Transparent Ethernet Bridging.
+    TRANSPARENT_ETHERNET_BRIDGING = 0x6558, "TEB";
+    /// Raw Frame Relay.
+    RAW_FRAME_RELAY = 0x6559, "Raw-FR";
+    /// Reverse Address Resolution Protocol.
+    RARP = 0x8035, "RARP";
+    APPLETALK = 0x809B, "AppleTalk";
+    /// AppleTalk Address Resolution Protocol.
+    AARP = 0x80F3, "AARP";
+    /// IEEE 802.1Q VLAN tag, also called customer tag.
+    VLAN = 0x8100, "802.1Q";
+    /// Novell IPX.
+    IPX = 0x8137, "IPX";
+    /// SNMP over Ethernet.
+    SNMP = 0x814C, "SNMP";
+    /// Internet Protocol version 6.
+    IPV6 = 0x86DD, "IPv6";
+    /// TCP/IP Compression.
+    TCP_IP_COMPRESSION = 0x876B, "TCP/IP-Compression";
+    /// IP Autonomous Systems.
+    IP_AUTONOMOUS_SYSTEMS = 0x876C, "IP-AS";
+    /// Secure Data.
+    SECURE_DATA = 0x876D, "Secure-Data";
+    /// IEEE 802.3 Ethernet Passive Optical Network and MAC control frames such as pause frames.
+    EPON = 0x8808, "EPON";
+    /// Point-to-Point Protocol.
+    PPP = 0x880B, "PPP";
+    /// General Switch Management Protocol.
+    GSMP = 0x880C, "GSMP";
+    /// Ethernet NIC hardware and software testing.
+    NIC_TEST = 0x8822, "NIC-Test";
+    MPLS = 0x8847, "MPLS";
+    /// MPLS with upstream-assigned label.
+    MPLS_UPSTREAM = 0x8848, "MPLS-UA";
+    /// Multicast Channel Allocation Protocol.
+    MCAP = 0x8861, "MCAP";
+    /// PPP over Ethernet discovery stage.
+    PPPOE_DISCOVERY = 0x8863, "PPPoE-Discovery";
+    /// PPP over Ethernet session stage.
+    PPPOE_SESSION = 0x8864, "PPPoE-Session";
+    /// IEEE 802.1X port-based network access control.
+    EAPOL = 0x888E, "802.1X";
+    /// IEEE 802.1ad service tag, the outer tag in QinQ frames.
+    QINQ = 0x88A8, "802.1ad";
+    /// IEEE 802 local experimental EtherType 1.
+    LOCAL_EXPERIMENTAL_1 = 0x88B5, "Local-Experimental-1";
+    /// IEEE 802 local experimental EtherType 2.
+    LOCAL_EXPERIMENTAL_2 = 0x88B6, "Local-Experimental-2";
+    /// IEEE 802 OUI extended EtherType.
+    OUI_EXTENDED = 0x88B7, "OUI-Extended";
+    /// IEEE 802.11 pre-authentication.
+    PRE_AUTHENTICATION = 0x88C7, "802.11i";
+    /// IEEE 802.1AB Link Layer Discovery Protocol.
+    LLDP = 0x88CC, "LLDP";
+    /// IEEE 802.1AE MAC security.
+    MACSEC = 0x88E5, "MACsec";
+    /// Provider Backbone Bridging instance tag.
+    PBB = 0x88E7, "PBB";
+    /// IEEE 802.1Q Multiple VLAN Registration Protocol.
+    MVRP = 0x88F5, "MVRP";
+    /// IEEE 802.1Q Multiple Multicast Registration Protocol.
+    MMRP = 0x88F6, "MMRP";
+    /// IEEE 802.11 fast roaming remote request.
+    FAST_ROAMING = 0x890D, "802.11r";
+    /// IEEE 802.21 Media Independent Handover Protocol.
+    MIH = 0x8917, "802.21";
+    /// IEEE 802.1Qbe Multiple I-SID Registration Protocol.
+    MIRP = 0x8929, "MIRP";
+    /// TRILL fine grained labeling.
+    TRILL_FGL = 0x893B, "TRILL-FGL";
+    /// IEEE 802.1Qbg Edge Control Protocol.
+    ECP = 0x8940, "ECP";
+    /// TRILL RBridge channel.
+    TRILL_RBRIDGE_CHANNEL = 0x8946, "TRILL-RBridge-Channel";
+    /// GeoNetworking as defined in ETSI EN 302 636-4-1.
+    GEONETWORKING = 0x8947, "GeoNetworking";
+    /// Network Service Header.
+    NSH = 0x894F, "NSH";
+    /// Loopback, the Ethernet configuration testing protocol.
+    LOOPBACK = 0x9000, "Loopback";
+    /// Non-standard QinQ outer tag used by older equipment.
+    QINQ_LEGACY = 0x9100, "QinQ";
+    /// Multi-topology routing.
+    MULTI_TOPOLOGY = 0x9A22, "Multi-Topology";
+    /// LoWPAN encapsulation.
+    LOWPAN = 0xA0ED, "LoWPAN";
+    /// Control channel of GRE packets.
+    GRE_CONTROL_CHANNEL = 0xB7EA, "GRE-Control-Channel";
+    RESERVED = 0xFFFF, "Reserved";
+});
+
 impl EtherType {
-    pub const IPV4: EtherType = EtherType(0x0800);
-    pub const ARP: EtherType = EtherType(0x0806);
-    pub const IPV6: EtherType = EtherType(0x86DD);
+    /// The largest value of the EtherType field that is interpreted as an IEEE 802.3 length.
+    pub const MAX_LENGTH: u16 = 1500;
 
     #[inline]
     pub fn value(&self) -> u16 {
         self.0
     }
+
+    /// Returns true if this value is not an EtherType but the length of an IEEE 802.3 frame.
+    /// That is the case for all values up to and including `EtherType::MAX_LENGTH`.
+    pub fn is_length(&self) -> bool {
+        self.0 <= EtherType::MAX_LENGTH
+    }
+
+    /// Returns true if this EtherType announces a VLAN tag, that is, it is one of
+    /// `EtherType::VLAN`, `EtherType::QINQ` and `EtherType::QINQ_LEGACY`.
+    pub fn is_vlan_tag(&self) -> bool {
+        matches!(*self, EtherType::VLAN | EtherType::QINQ | EtherType::QINQ_LEGACY)
+    }
+
+    /// Returns true if this is `EtherType::IPV4` or `EtherType::IPV6`.
+    pub fn is_ip(&self) -> bool {
+        *self == EtherType::IPV4 || *self == EtherType::IPV6
+    }
+
+    /// Returns true if this is `EtherType::MPLS` or `EtherType::MPLS_UPSTREAM`.
+    pub fn is_mpls(&self) -> bool {
+        *self == EtherType::MPLS || *self == EtherType::MPLS_UPSTREAM
+    }
+
+    /// Returns true if this value is reserved for experiments, either in the range 0x0101 to
+    /// 0x01ff that IANA lists as experimental or one of the IEEE 802 local experimental
+    /// EtherTypes. The range is below `MAX_LENGTH`, so it only applies outside of Ethernet
+    /// frames, such as in SNAP headers.
+    pub fn is_experimental(&self) -> bool {
+        matches!(self.0, 0x0101..=0x01ff)
+            || *self == EtherType::LOCAL_EXPERIMENTAL_1
+            || *self == EtherType::LOCAL_EXPERIMENTAL_2
+    }
 }
 
+/// Writes the name and value of the EtherType, like `IPv4 (0x0800)`, or only the value in hex,
+/// like `0x1234`, for unnamed values. The alternate form, `{:#}`, leaves out the value of named
+/// EtherTypes and is used in the packet summaries.
+impl fmt::Display for EtherType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) if f.alternate() => write!(f, "{}", name),
+            Some(name) => write!(f, "{} ({:#06x})", name, self.0),
+            None => write!(f, "{:#06x}", self.0),
+        }
+    }
+}
+
+newtype_layout_value!(EtherType, u16, Display);
+
+
+/// An owned representation of an Ethernet II header. VLAN tags are not decoded, `ether_type` is
+/// the field directly following the source address.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct EthernetRepr {
+    pub destination: MacAddr,
+    pub source: MacAddr,
+    pub ether_type: EtherType,
+}
+
+impl EthernetRepr {
+    /// Reads the header of `packet` into an `EthernetRepr`.
+    pub fn parse<B: AsRef<[u8]>>(packet: &EthernetPacket<B>) -> Result<Self, EthernetError> {
+        if packet.data().len() < EthernetPacket::MIN_LEN {
+            return Err(EthernetError::Truncated);
+        }
+        Ok(EthernetRepr {
+            destination: packet.destination(),
+            source: packet.source(),
+            ether_type: packet.ether_type(),
+        })
+    }
+
+    /// Returns the number of bytes needed to emit this header.
+    pub fn buffer_len(&self) -> usize {
+        EthernetPacket::MIN_LEN
+    }
+
+    /// Writes this header to `packet`. The payload is left untouched.
+    pub fn emit<B: AsRef<[u8]> + AsMut<[u8]>>(&self, packet: &mut EthernetPacket<B>) {
+        packet.set_destination(self.destination);
+        packet.set_source(self.source);
+        packet.set_ether_type(self.ether_type);
+    }
+}
+
+/// Error returned when an Ethernet frame is not valid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EthernetError {
+    /// The frame is too short to hold the Ethernet header and all of its VLAN tags.
+    Truncated,
+    /// The length field of an IEEE 802.3 frame is longer than the payload.
+    Length,
+}
+
+impl fmt::Display for EthernetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EthernetError::Truncated => write!(f, "Ethernet frame truncated"),
+            EthernetError::Length => write!(f, "Invalid IEEE 802.3 length"),
+        }
+    }
+}
+
+impl Error for EthernetError {}
+
 
 #[cfg(test)]
 mod tests {
@@ -69,13 +494,295 @@ mod tests {
     eth_setget_test!(source, set_source, MacAddr(MAC), 6, MAC);
     eth_setget_test!(ether_type, set_ether_type, EtherType(0xffff), 12, [0xff; 2]);
 
+    #[test]
+    fn untagged() {
+        let mut backing_data = [0; 32];
+        backing_data[12..14].copy_from_slice(&[0x08, 0x00]);
+        let testee = EthernetPacket::new(&backing_data).unwrap();
+        assert_eq!(0, testee.vlan_tags().count());
+        assert_eq!(EtherType::IPV4, testee.effective_ether_type());
+        assert_eq!(testee.payload(), testee.effective_payload());
+    }
+
+    #[test]
+    fn single_tag() {
+        let mut backing_data = [0; 32];
+        backing_data[12..20].copy_from_slice(&[0x81, 0x00, 0xa0, 0x64, 0x86, 0xdd, 1, 2]);
+        let testee = EthernetPacket::new(&backing_data[..20]).unwrap();
+        assert_eq!(EtherType::VLAN, testee.ether_type());
+        let tags: Vec<_> = testee.vlan_tags().collect();
+        assert_eq!(1, tags.len());
+        assert_eq!(EtherType::VLAN, tags[0].tpid());
+        assert_eq!(5, tags[0].pcp());
+        assert!(!tags[0].dei());
+        assert_eq!(100, tags[0].vid());
+        assert_eq!(18, testee.effective_header_len());
+        assert_eq!(EtherType::IPV6, testee.effective_ether_type());
+        assert_eq!(&[1, 2], testee.effective_payload());
+    }
+
+    #[test]
+    fn write_qinq_tags() {
+        let mut backing_data = [0; 32];
+        {
+            let mut testee = MutEthernetPacket::new(&mut backing_data[..24]).unwrap();
+            let tags = [
+                (EtherType::QINQ, Tci::new(0, false, 10)),
+                (EtherType::VLAN, Tci::new(0, false, 20)),
+            ];
+            assert_eq!(Some(22), testee.set_vlan_tags(&tags, EtherType::ARP));
+            testee.effective_payload_mut()[0] = 0xff;
+        }
+        assert_eq!(
+            [0x88, 0xa8, 0, 10, 0x81, 0x00, 0, 20, 0x08, 0x06, 0xff, 0],
+            backing_data[12..24]
+        );
+        let testee = EthernetPacket::new(&backing_data[..24]).unwrap();
+        let vids: Vec<_> = testee.vlan_tags().map(|tag| tag.vid()).collect();
+        assert_eq!(vec![10, 20], vids);
+        assert_eq!(EtherType::ARP, testee.effective_ether_type());
+    }
+
+    #[test]
+    fn write_tag_too_short_buffer() {
+        let mut backing_data = [0; 32];
+        let mut testee = MutEthernetPacket::new(&mut backing_data[..17]).unwrap();
+        assert_eq!(None, testee.set_vlan_tag(Tci(1), EtherType::IPV4));
+        assert_eq!(EtherType(0), testee.as_immutable().ether_type());
+    }
+
+    #[test]
+    fn truncated_tag() {
+        let mut backing_data = [0; 32];
+        backing_data[12..14].copy_from_slice(&[0x81, 0x00]);
+        let testee = EthernetPacket::new(&backing_data[..17]).unwrap();
+        assert_eq!(0, testee.vlan_tags().count());
+        assert_eq!(EtherType::VLAN, testee.effective_ether_type());
+        assert_eq!(3, testee.effective_payload().len());
+    }
+
+    #[test]
+    fn owned_buffer() {
+        let mut testee = EthernetPacket::new(vec![0; 20]).unwrap();
+        testee.set_ether_type(EtherType::ARP);
+        testee.payload_mut()[0] = 0xff;
+        assert_eq!(EtherType::ARP, testee.ether_type());
+        assert_eq!(&[0xff, 0, 0, 0, 0, 0], testee.payload());
+
+        let queue = vec![testee];
+        let data = queue.into_iter().next().unwrap().into_inner();
+        assert_eq!([0x08, 0x06, 0xff], data[12..15]);
+    }
+
+    #[test]
+    fn owned_buffer_too_short() {
+        assert!(EthernetPacket::new(vec![0; 13].into_boxed_slice()).is_none());
+    }
+
+    #[test]
+    fn min_len() {
+        use MinLen;
+
+        fn generic_min_len<B>(_: &EthernetPacket<B>) -> usize {
+            EthernetPacket::<B>::MIN_LEN
+        }
+
+        assert_eq!(14, EthernetPacket::MIN_LEN);
+        assert_eq!(14, MutEthernetPacket::MIN_LEN);
+        assert_eq!(14, EthernetPacket::<Vec<u8>>::MIN_LEN);
+        assert_eq!(14, generic_min_len(&EthernetPacket::new(vec![0; 14]).unwrap()));
+    }
+
+    #[test]
+    fn as_mutable() {
+        let mut backing_data = [0; 14];
+        let mut testee = EthernetPacket::new(&mut backing_data[..]).unwrap();
+        {
+            let mut borrowed: MutEthernetPacket = testee.as_mutable();
+            borrowed.set_ether_type(EtherType::IPV6);
+        }
+        assert_eq!(EtherType::IPV6, testee.as_immutable().ether_type());
+    }
+
+    #[test]
+    fn with_fcs() {
+        let mut backing_data = [0; 32];
+        backing_data[12..14].copy_from_slice(&[0x08, 0x00]);
+        backing_data[14] = 0xff;
+        assert_eq!(Some(19), append_fcs(&mut backing_data, 15));
+        let testee = EthernetPacket::new_with_fcs(&backing_data[..19]).unwrap();
+        assert_eq!(&[0xff], testee.payload());
+        assert_eq!(split_fcs(&backing_data[..19]).unwrap().1, testee.calculate_fcs());
+        assert!(EthernetPacket::new_with_fcs(&backing_data[..17]).is_none());
+    }
+
+    #[test]
+    fn mut_with_fcs() {
+        let mut backing_data = [0; 32];
+        {
+            let mut testee = MutEthernetPacket::new_with_fcs_mut(&mut backing_data[..20]).unwrap();
+            assert_eq!(2, testee.payload().len());
+            testee.set_ether_type(EtherType::IPV4);
+        }
+        append_fcs(&mut backing_data, 16);
+        assert!(verify_fcs(&backing_data[..20]));
+    }
+
+    #[test]
+    fn ethernet_ii() {
+        let mut backing_data = [0; 32];
+        backing_data[12..14].copy_from_slice(&[0x86, 0xdd]);
+        let testee = EthernetPacket::new(&backing_data).unwrap();
+        assert_eq!(FrameFormat::EthernetII, testee.frame_format());
+        assert_eq!(None, testee.ieee8023_length());
+        assert_eq!(None, testee.ieee8023_payload());
+        assert!(testee.llc().is_none());
+    }
+
+    #[test]
+    fn ieee8023_padded() {
+        let mut backing_data = [0; 64];
+        backing_data[12..14].copy_from_slice(&[0x00, 0x07]);
+        backing_data[14..21].copy_from_slice(&[0x42, 0x42, 0x03, 0, 0, 0, 0]);
+        let testee = EthernetPacket::new(&backing_data[..60]).unwrap();
+        assert_eq!(FrameFormat::Ieee8023, testee.frame_format());
+        assert_eq!(Some(7), testee.ieee8023_length());
+        assert_eq!(Some(&backing_data[14..21]), testee.ieee8023_payload());
+        let llc = testee.llc().unwrap();
+        assert_eq!(Sap::STP, llc.dsap());
+        assert_eq!(4, llc.payload().len());
+    }
+
+    #[test]
+    fn ieee8023_length_larger_than_frame() {
+        let mut backing_data = [0; 32];
+        backing_data[12..14].copy_from_slice(&[0x05, 0xdc]);
+        let testee = EthernetPacket::new(&backing_data[..20]).unwrap();
+        assert_eq!(Some(1500), testee.ieee8023_length());
+        assert_eq!(Some(6), testee.ieee8023_payload().map(|payload| payload.len()));
+    }
+
+    #[test]
+    fn tagged_ieee8023() {
+        let mut backing_data = [0; 32];
+        backing_data[12..18].copy_from_slice(&[0x81, 0x00, 0, 1, 0, 3]);
+        backing_data[18..21].copy_from_slice(&[0xaa, 0xaa, 0x03]);
+        let testee = EthernetPacket::new(&backing_data).unwrap();
+        assert_eq!(FrameFormat::Ieee8023, testee.frame_format());
+        assert_eq!(Some(&[0xaa, 0xaa, 0x03][..]), testee.ieee8023_payload());
+    }
+
+    #[test]
+    fn ether_type_is_length() {
+        assert!(EtherType(0).is_length());
+        assert!(EtherType(1500).is_length());
+        assert!(!EtherType(1501).is_length());
+        assert!(!EtherType::IPV4.is_length());
+    }
+
     #[test]
     fn set_payload() {
         let mut backing_data = [0; 15];
         {
             let mut testee = MutEthernetPacket::new(&mut backing_data).unwrap();
-            testee.payload()[0] = 99;
+            testee.payload_mut()[0] = 99;
         }
         assert_eq!(99, backing_data[14]);
     }
+
+    #[test]
+    fn repr_round_trip() {
+        let repr = EthernetRepr {
+            destination: MacAddr::BROADCAST,
+            source: MacAddr([1, 2, 3, 4, 5, 6]),
+            ether_type: EtherType::ARP,
+        };
+        let mut backing_data = [0xff; 20];
+        let mut packet = EthernetPacket::new(&mut backing_data[..]).unwrap();
+        repr.emit(&mut packet);
+        assert_eq!(14, repr.buffer_len());
+        assert_eq!([0xff; 6], packet.payload());
+        assert_eq!(Ok(repr), EthernetRepr::parse(&packet));
+    }
+
+    #[test]
+    fn validate() {
+        let mut backing_data = [0; 20];
+        backing_data[12..14].copy_from_slice(&[0x08, 0x00]);
+        assert!(EthernetPacket::new_checked(&backing_data[..]).is_ok());
+        backing_data[12..14].copy_from_slice(&[0x00, 0x06]);
+        assert_eq!(Ok(()), EthernetPacket::new(&backing_data[..]).unwrap().validate());
+        backing_data[12..14].copy_from_slice(&[0x00, 0x07]);
+        assert_eq!(Err(EthernetError::Length), EthernetPacket::new_checked(&backing_data[..]));
+    }
+
+    #[test]
+    fn validate_truncated_tag() {
+        let mut backing_data = [0; 17];
+        backing_data[12..14].copy_from_slice(&[0x81, 0x00]);
+        assert_eq!(Err(EthernetError::Truncated), EthernetPacket::new_checked(&backing_data[..]));
+        assert_eq!(Err(EthernetError::Truncated), EthernetPacket::new_checked(&backing_data[..13]));
+    }
+
+    #[test]
+    fn display() {
+        let mut backing_data = [0; 14];
+        {
+            let mut packet = MutEthernetPacket::new(&mut backing_data[..]).unwrap();
+            packet.set_destination(MacAddr::BROADCAST);
+            packet.set_source(MacAddr([0, 1, 2, 3, 4, 5]));
+            packet.set_ether_type(EtherType::IPV4);
+        }
+        assert_eq!(
+            "Ethernet 00:01:02:03:04:05 > ff:ff:ff:ff:ff:ff IPv4",
+            EthernetPacket::new(&backing_data[..]).unwrap().to_string()
+        );
+        backing_data[12..14].copy_from_slice(&[0x00, 0x26]);
+        assert_eq!(
+            "Ethernet 00:01:02:03:04:05 > ff:ff:ff:ff:ff:ff length 38",
+            EthernetPacket::new(&backing_data[..]).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn ether_type_names() {
+        assert_eq!(Some("802.1Q"), EtherType::VLAN.name());
+        assert_eq!(None, EtherType(0x1234).name());
+        assert_eq!(Some(EtherType::LLDP), EtherType::from_name("lldp"));
+        for value in 0..=0xffff {
+            let ether_type = EtherType(value);
+            if let Some(name) = ether_type.name() {
+                assert_eq!(Some(ether_type), EtherType::from_name(name));
+            }
+        }
+    }
+
+    #[test]
+    fn ether_type_display() {
+        assert_eq!("IPv4 (0x0800)", EtherType::IPV4.to_string());
+        assert_eq!("IPv4", format!("{:#}", EtherType::IPV4));
+        assert_eq!("802.1Q (0x8100)", EtherType::VLAN.to_string());
+        assert_eq!("LLDP (0x88cc)", EtherType(0x88cc).to_string());
+        assert_eq!("0x1234", EtherType(0x1234).to_string());
+        assert_eq!("0x1234", format!("{:#}", EtherType(0x1234)));
+    }
+
+    #[test]
+    fn ether_type_from_str() {
+        assert_eq!(Ok(EtherType::IPV6), "IPv6".parse());
+        assert_eq!(Ok(EtherType::PPPOE_SESSION), "pppoe-session".parse());
+        assert_eq!(Ok(EtherType::ARP), "0x0806".parse());
+        assert_eq!(Ok(EtherType(1500)), "1500".parse());
+        assert_eq!(Err(EtherTypeParseError), "0x10000".parse::<EtherType>());
+        assert_eq!(Err(EtherTypeParseError), "IPv5".parse::<EtherType>());
+    }
+
+    #[test]
+    fn ether_type_classification() {
+        assert!(EtherType::IPV6.is_ip() && !EtherType::ARP.is_ip());
+        assert!(EtherType::MPLS_UPSTREAM.is_mpls());
+        assert!(EtherType(0x0101).is_experimental());
+        assert!(EtherType::LOCAL_EXPERIMENTAL_2.is_experimental());
+        assert!(!EtherType::IPV4.is_experimental());
+    }
 }
@@ -0,0 +1,165 @@
+use super::EtherType;
+use core::fmt;
+use types::*;
+
+packet!(VlanTag, MutVlanTag, 4);
+
+getters!(VlanTag
+    /// Returns the Tag Protocol Identifier. This is the `EtherType` that announced the tag,
+    /// `EtherType::VLAN` for a customer tag or `EtherType::QINQ` for a service tag.
+    pub fn tpid(&self) -> EtherType {
+        EtherType(read_offset!(self.data(), 0, u16, from_be))
+    }
+
+    pub fn tci(&self) -> Tci {
+        Tci(read_offset!(self.data(), 2, u16, from_be))
+    }
+
+    pub fn pcp(&self) -> u3 {
+        self.tci().pcp()
+    }
+
+    pub fn dei(&self) -> bool {
+        self.tci().dei()
+    }
+
+    pub fn vid(&self) -> u12 {
+        self.tci().vid()
+    }
+);
+
+setters!(VlanTag
+    pub fn set_tpid(&mut self, tpid: EtherType) {
+        write_offset!(self.data_mut(), 0, tpid.value(), u16, to_be);
+    }
+
+    pub fn set_tci(&mut self, tci: Tci) {
+        write_offset!(self.data_mut(), 2, tci.value(), u16, to_be);
+    }
+
+    pub fn set_pcp(&mut self, pcp: u3) {
+        let tci = self.tci();
+        self.set_tci(Tci::new(pcp, tci.dei(), tci.vid()));
+    }
+
+    pub fn set_dei(&mut self, dei: bool) {
+        let tci = self.tci();
+        self.set_tci(Tci::new(tci.pcp(), dei, tci.vid()));
+    }
+
+    pub fn set_vid(&mut self, vid: u12) {
+        let tci = self.tci();
+        self.set_tci(Tci::new(tci.pcp(), tci.dei(), vid));
+    }
+);
+
+/// Writes a one line summary of the tag, like `VLAN 10 pcp=3`, with ` DEI` appended if the drop
+/// eligible indicator is set. Service tags are written as `VLAN 802.1ad 10 pcp=3`.
+impl<B: AsRef<[u8]>> fmt::Display for VlanTag<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "VLAN ")?;
+        if self.tpid() != EtherType::VLAN {
+            write!(f, "{:#} ", self.tpid())?;
+        }
+        write!(f, "{} pcp={}", self.vid(), self.pcp())?;
+        if self.dei() {
+            write!(f, " DEI")?;
+        }
+        Ok(())
+    }
+}
+
+/// The 16 bit Tag Control Information of a VLAN tag. Contains the priority code point, the drop
+/// eligible indicator and the VLAN identifier.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Tci(pub u16);
+
+impl Tci {
+    /// Creates a TCI from its three fields. Bits outside the field widths are ignored.
+    pub fn new(pcp: u3, dei: bool, vid: u12) -> Tci {
+        Tci((u16::from(pcp & 0x07) << 13) | (u16::from(dei) << 12) | (vid & 0x0fff))
+    }
+
+    #[inline]
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns the Priority Code Point, the IEEE 802.1p class of service.
+    pub fn pcp(&self) -> u3 {
+        (self.0 >> 13) as u8
+    }
+
+    /// Returns the Drop Eligible Indicator.
+    pub fn dei(&self) -> bool {
+        self.0 & 0x1000 != 0
+    }
+
+    /// Returns the VLAN Identifier.
+    pub fn vid(&self) -> u12 {
+        self.0 & 0x0fff
+    }
+}
+
+/// Iterator over the VLAN tags of an Ethernet frame, outermost tag first. Created with
+/// `EthernetPacket::vlan_tags()`.
+#[derive(Debug, Clone)]
+pub struct VlanTags<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> VlanTags<'a> {
+    /// Creates an iterator over the tags in the Ethernet frame `data`.
+    pub fn new(data: &'a [u8]) -> VlanTags<'a> {
+        VlanTags { data, offset: 12 }
+    }
+
+    /// Returns the offset of the EtherType following the last tag yielded so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> Iterator for VlanTags<'a> {
+    type Item = VlanTag<&'a [u8]>;
+
+    fn next(&mut self) -> Option<VlanTag<&'a [u8]>> {
+        // A tag is only yielded if the EtherType following it fits in the data as well
+        let tag = self.data.get(self.offset..self.offset + 6)?;
+        if EtherType(u16::from_be_bytes([tag[0], tag[1]])).is_vlan_tag() {
+            self.offset += 4;
+            Some(VlanTag(&tag[..4]))
+        } else {
+            None
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! vlan_setget_test {
+        ($name:ident, $set_name:ident, $value:expr, $offset:expr, $expected:expr) => {
+            setget_test!(MutVlanTag, $name, $set_name, $value, $offset, $expected);
+        }
+    }
+
+    vlan_setget_test!(tpid, set_tpid, EtherType(0xffff), 0, [0xff, 0xff]);
+    vlan_setget_test!(tci, set_tci, Tci(0xffff), 2, [0xff, 0xff]);
+    vlan_setget_test!(pcp, set_pcp, 0x7, 2, [0xe0]);
+    vlan_setget_test!(dei, set_dei, true, 2, [0x10]);
+    vlan_setget_test!(vid, set_vid, 0xfff, 2, [0x0f, 0xff]);
+
+    #[test]
+    fn tci_fields() {
+        let tci = Tci::new(5, true, 100);
+        assert_eq!(0xb064, tci.value());
+        assert_eq!(5, tci.pcp());
+        assert!(tci.dei());
+        assert_eq!(100, tci.vid());
+        assert_eq!(Tci(0x0fff), Tci::new(0, false, 0xffff));
+    }
+}
@@ -0,0 +1,487 @@
+use checksum;
+use ipv4::Ipv4Packet;
+use core::fmt;
+use core::net::Ipv4Addr;
+
+/// Generates the getters and setters for the type, code and checksum fields that are common to
+/// all ICMP messages, as well as checksum calculation.
+macro_rules! icmp_header {
+    ($name:ident) => {
+        getters!($name
+            pub fn icmp_type(&self) -> IcmpType {
+                IcmpType(read_offset!(self.data(), 0, u8))
+            }
+
+            pub fn code(&self) -> u8 {
+                read_offset!(self.data(), 1, u8)
+            }
+
+            pub fn checksum(&self) -> u16 {
+                read_offset!(self.data(), 2, u16, from_be)
+            }
+
+            /// Computes the checksum of the message, as it should be written to the checksum
+            /// field. The current value of the checksum field is ignored. The message is assumed
+            /// to span the entire backing slice.
+            pub fn calculate_checksum(&self) -> u16 {
+                let data = self.data();
+                checksum::finish(checksum::sum(checksum::sum(0, &data[..2]), &data[4..]))
+            }
+
+            /// Returns true if the checksum field contains the correct checksum for the message.
+            pub fn verify_checksum(&self) -> bool {
+                checksum::checksum(self.data()) == 0
+            }
+        );
+
+        setters!($name
+            pub fn set_icmp_type(&mut self, icmp_type: IcmpType) {
+                write_offset!(self.data_mut(), 0, icmp_type.value(), u8);
+            }
+
+            pub fn set_code(&mut self, code: u8) {
+                write_offset!(self.data_mut(), 1, code, u8);
+            }
+
+            pub fn set_checksum(&mut self, checksum: u16) {
+                write_offset!(self.data_mut(), 2, checksum, u16, to_be);
+            }
+        );
+
+        impl<B: AsRef<[u8]> + AsMut<[u8]>> $name<B> {
+            /// Computes the checksum and writes it to the checksum field. Should be called after
+            /// all other fields and the payload have been written.
+            pub fn fill_checksum(&mut self) {
+                let checksum = self.calculate_checksum();
+                self.set_checksum(checksum);
+            }
+        }
+    }
+}
+
+/// Generates the accessor for the original IP packet embedded in ICMP error messages.
+macro_rules! icmp_error {
+    ($name:ident) => {
+        getters!($name
+            /// Returns the IPv4 header and leading payload bytes of the packet that caused this
+            /// error. Returns `None` if the message is too short to contain an IPv4 header.
+            pub fn original_packet(&self) -> Option<Ipv4Packet<&[u8]>> {
+                Ipv4Packet::new(&self.data()[8..])
+            }
+        );
+    }
+}
+
+packet!(IcmpPacket, MutIcmpPacket, 8);
+icmp_header!(IcmpPacket);
+
+getters!(IcmpPacket
+    /// Returns the four bytes after the checksum. Their meaning depend on the message type.
+    pub fn rest_of_header(&self) -> [u8; 4] {
+        read_offset!(self.data(), 4, [u8; 4])
+    }
+);
+
+impl<B: AsRef<[u8]>> IcmpPacket<B> {
+    /// Returns a typed view of this message based on its type.
+    pub fn message(&self) -> IcmpMessage<'_> {
+        let data = self.data();
+        match self.icmp_type() {
+            IcmpType::ECHO_REQUEST => IcmpMessage::EchoRequest(EchoPacket(data)),
+            IcmpType::ECHO_REPLY => IcmpMessage::EchoReply(EchoPacket(data)),
+            IcmpType::DESTINATION_UNREACHABLE => {
+                IcmpMessage::DestinationUnreachable(DestinationUnreachablePacket(data))
+            }
+            IcmpType::TIME_EXCEEDED => IcmpMessage::TimeExceeded(TimeExceededPacket(data)),
+            IcmpType::REDIRECT => IcmpMessage::Redirect(RedirectPacket(data)),
+            IcmpType::PARAMETER_PROBLEM => {
+                IcmpMessage::ParameterProblem(ParameterProblemPacket(data))
+            }
+            _ => IcmpMessage::Other(self.as_immutable()),
+        }
+    }
+}
+
+/// Writes a one line summary of the message, like `ICMP echo request id=1 seq=2`.
+impl<B: AsRef<[u8]>> fmt::Display for IcmpPacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ICMP {}", self.icmp_type())?;
+        match self.message() {
+            IcmpMessage::EchoRequest(echo) | IcmpMessage::EchoReply(echo) => {
+                write!(f, " id={} seq={}", echo.identifier(), echo.sequence_number())
+            }
+            _ => write!(f, " code={}", self.code()),
+        }
+    }
+}
+
+setters!(IcmpPacket
+    pub fn set_rest_of_header(&mut self, rest_of_header: [u8; 4]) {
+        write_offset!(self.data_mut(), 4, rest_of_header, [u8; 4]);
+    }
+);
+
+/// A typed view of an ICMP message. Created with `IcmpPacket::message()`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum IcmpMessage<'a> {
+    EchoRequest(EchoPacket<&'a [u8]>),
+    EchoReply(EchoPacket<&'a [u8]>),
+    DestinationUnreachable(DestinationUnreachablePacket<&'a [u8]>),
+    TimeExceeded(TimeExceededPacket<&'a [u8]>),
+    Redirect(RedirectPacket<&'a [u8]>),
+    ParameterProblem(ParameterProblemPacket<&'a [u8]>),
+    /// A message of a type without a typed view.
+    Other(IcmpPacket<&'a [u8]>),
+}
+
+
+packet!(EchoPacket, MutEchoPacket, 8);
+icmp_header!(EchoPacket);
+
+getters!(EchoPacket
+    pub fn identifier(&self) -> u16 {
+        read_offset!(self.data(), 4, u16, from_be)
+    }
+
+    pub fn sequence_number(&self) -> u16 {
+        read_offset!(self.data(), 6, u16, from_be)
+    }
+);
+
+setters!(EchoPacket
+    pub fn set_identifier(&mut self, identifier: u16) {
+        write_offset!(self.data_mut(), 4, identifier, u16, to_be);
+    }
+
+    pub fn set_sequence_number(&mut self, sequence_number: u16) {
+        write_offset!(self.data_mut(), 6, sequence_number, u16, to_be);
+    }
+);
+
+
+packet!(DestinationUnreachablePacket, MutDestinationUnreachablePacket, 8);
+icmp_header!(DestinationUnreachablePacket);
+icmp_error!(DestinationUnreachablePacket);
+
+impl DestinationUnreachablePacket<&[u8]> {
+    pub const NET_UNREACHABLE: u8 = 0;
+    pub const HOST_UNREACHABLE: u8 = 1;
+    pub const PROTOCOL_UNREACHABLE: u8 = 2;
+    pub const PORT_UNREACHABLE: u8 = 3;
+    pub const FRAGMENTATION_NEEDED: u8 = 4;
+    pub const SOURCE_ROUTE_FAILED: u8 = 5;
+    pub const DESTINATION_NETWORK_UNKNOWN: u8 = 6;
+    pub const DESTINATION_HOST_UNKNOWN: u8 = 7;
+    pub const SOURCE_HOST_ISOLATED: u8 = 8;
+    pub const NETWORK_ADMINISTRATIVELY_PROHIBITED: u8 = 9;
+    pub const HOST_ADMINISTRATIVELY_PROHIBITED: u8 = 10;
+    pub const NETWORK_UNREACHABLE_FOR_TOS: u8 = 11;
+    pub const HOST_UNREACHABLE_FOR_TOS: u8 = 12;
+    pub const COMMUNICATION_ADMINISTRATIVELY_PROHIBITED: u8 = 13;
+    pub const HOST_PRECEDENCE_VIOLATION: u8 = 14;
+    pub const PRECEDENCE_CUTOFF_IN_EFFECT: u8 = 15;
+}
+
+getters!(DestinationUnreachablePacket
+    /// Returns the MTU of the next-hop network as defined in [RFC 1191]. Only meaningful when
+    /// the code is `FRAGMENTATION_NEEDED`, see `fragmentation_needed_mtu()`.
+    ///
+    /// [RFC 1191]: https://tools.ietf.org/html/rfc1191
+    pub fn next_hop_mtu(&self) -> u16 {
+        read_offset!(self.data(), 6, u16, from_be)
+    }
+
+    /// Returns the next-hop MTU if this is a fragmentation needed message, otherwise `None`.
+    pub fn fragmentation_needed_mtu(&self) -> Option<u16> {
+        if self.code() == DestinationUnreachablePacket::FRAGMENTATION_NEEDED {
+            Some(self.next_hop_mtu())
+        } else {
+            None
+        }
+    }
+);
+
+setters!(DestinationUnreachablePacket
+    pub fn set_next_hop_mtu(&mut self, next_hop_mtu: u16) {
+        write_offset!(self.data_mut(), 6, next_hop_mtu, u16, to_be);
+    }
+);
+
+
+packet!(TimeExceededPacket, MutTimeExceededPacket, 8);
+icmp_header!(TimeExceededPacket);
+icmp_error!(TimeExceededPacket);
+
+impl TimeExceededPacket<&[u8]> {
+    pub const TTL_EXCEEDED: u8 = 0;
+    pub const FRAGMENT_REASSEMBLY_TIME_EXCEEDED: u8 = 1;
+}
+
+
+packet!(RedirectPacket, MutRedirectPacket, 8);
+icmp_header!(RedirectPacket);
+icmp_error!(RedirectPacket);
+
+impl RedirectPacket<&[u8]> {
+    pub const REDIRECT_FOR_NETWORK: u8 = 0;
+    pub const REDIRECT_FOR_HOST: u8 = 1;
+    pub const REDIRECT_FOR_TOS_AND_NETWORK: u8 = 2;
+    pub const REDIRECT_FOR_TOS_AND_HOST: u8 = 3;
+}
+
+getters!(RedirectPacket
+    pub fn gateway(&self) -> Ipv4Addr {
+        Ipv4Addr::from(read_offset!(self.data(), 4, [u8; 4]))
+    }
+);
+
+setters!(RedirectPacket
+    pub fn set_gateway(&mut self, gateway: Ipv4Addr) {
+        write_offset!(self.data_mut(), 4, gateway.octets(), [u8; 4]);
+    }
+);
+
+
+packet!(ParameterProblemPacket, MutParameterProblemPacket, 8);
+icmp_header!(ParameterProblemPacket);
+icmp_error!(ParameterProblemPacket);
+
+impl ParameterProblemPacket<&[u8]> {
+    pub const POINTER_INDICATES_ERROR: u8 = 0;
+    pub const MISSING_REQUIRED_OPTION: u8 = 1;
+    pub const BAD_LENGTH: u8 = 2;
+}
+
+getters!(ParameterProblemPacket
+    /// Returns the offset of the byte in the original packet where the error was detected.
+    pub fn pointer(&self) -> u8 {
+        read_offset!(self.data(), 4, u8)
+    }
+);
+
+setters!(ParameterProblemPacket
+    pub fn set_pointer(&mut self, pointer: u8) {
+        write_offset!(self.data_mut(), 4, pointer, u8);
+    }
+);
+
+
+/// Represents the eight bit type field of an ICMP message. See [IANA's list] for the defined
+/// values.
+///
+/// [IANA's list]: https://www.iana.org/assignments/icmp-parameters/icmp-parameters.xhtml
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct IcmpType(pub u8);
+
+impl IcmpType {
+    pub const ECHO_REPLY: IcmpType = IcmpType(0);
+    pub const DESTINATION_UNREACHABLE: IcmpType = IcmpType(3);
+    pub const SOURCE_QUENCH: IcmpType = IcmpType(4);
+    pub const REDIRECT: IcmpType = IcmpType(5);
+    pub const ECHO_REQUEST: IcmpType = IcmpType(8);
+    pub const ROUTER_ADVERTISEMENT: IcmpType = IcmpType(9);
+    pub const ROUTER_SOLICITATION: IcmpType = IcmpType(10);
+    pub const TIME_EXCEEDED: IcmpType = IcmpType(11);
+    pub const PARAMETER_PROBLEM: IcmpType = IcmpType(12);
+    pub const TIMESTAMP: IcmpType = IcmpType(13);
+    pub const TIMESTAMP_REPLY: IcmpType = IcmpType(14);
+
+    /// Returns the numeric representation of this ICMP type.
+    #[inline]
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns true if this is one of the error message types that carry the original packet.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            *self,
+            IcmpType::DESTINATION_UNREACHABLE |
+                IcmpType::SOURCE_QUENCH |
+                IcmpType::REDIRECT |
+                IcmpType::TIME_EXCEEDED |
+                IcmpType::PARAMETER_PROBLEM
+        )
+    }
+
+    /// Returns the name of this type if it is one of the associated constants.
+    pub fn name(&self) -> Option<&'static str> {
+        match *self {
+            IcmpType::ECHO_REPLY => Some("echo reply"),
+            IcmpType::DESTINATION_UNREACHABLE => Some("destination unreachable"),
+            IcmpType::SOURCE_QUENCH => Some("source quench"),
+            IcmpType::REDIRECT => Some("redirect"),
+            IcmpType::ECHO_REQUEST => Some("echo request"),
+            IcmpType::ROUTER_ADVERTISEMENT => Some("router advertisement"),
+            IcmpType::ROUTER_SOLICITATION => Some("router solicitation"),
+            IcmpType::TIME_EXCEEDED => Some("time exceeded"),
+            IcmpType::PARAMETER_PROBLEM => Some("parameter problem"),
+            IcmpType::TIMESTAMP => Some("timestamp"),
+            IcmpType::TIMESTAMP_REPLY => Some("timestamp reply"),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the name of the type, or `type N` for unnamed types.
+impl fmt::Display for IcmpType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "type {}", self.0),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ip::Protocol;
+    use ipv4::MutIpv4Packet;
+
+    macro_rules! icmp_setget_test {
+        ($name:ident, $set_name:ident, $value:expr, $offset:expr, $expected:expr) => {
+            setget_test!(MutIcmpPacket, $name, $set_name, $value, $offset, $expected);
+        }
+    }
+
+    icmp_setget_test!(icmp_type, set_icmp_type, IcmpType(0xff), 0, [0xff]);
+    icmp_setget_test!(code, set_code, 0xff, 1, [0xff]);
+    icmp_setget_test!(checksum, set_checksum, 0xfeff, 2, [0xfe, 0xff]);
+    icmp_setget_test!(
+        rest_of_header,
+        set_rest_of_header,
+        [1, 2, 3, 4],
+        4,
+        [1, 2, 3, 4]
+    );
+
+    mod echo {
+        use super::super::*;
+        setget_test!(MutEchoPacket, identifier, set_identifier, 0xabcd, 4, [0xab, 0xcd]);
+        setget_test!(
+            MutEchoPacket,
+            sequence_number,
+            set_sequence_number,
+            0x1234,
+            6,
+            [0x12, 0x34]
+        );
+    }
+
+    mod unreachable {
+        use super::super::*;
+        setget_test!(
+            MutDestinationUnreachablePacket,
+            next_hop_mtu,
+            set_next_hop_mtu,
+            1500,
+            6,
+            [0x05, 0xdc]
+        );
+    }
+
+    mod redirect {
+        use super::super::*;
+        setget_test!(
+            MutRedirectPacket,
+            gateway,
+            set_gateway,
+            Ipv4Addr::new(10, 0, 0, 1),
+            4,
+            [10, 0, 0, 1]
+        );
+    }
+
+    mod parameter_problem {
+        use super::super::*;
+        setget_test!(MutParameterProblemPacket, pointer, set_pointer, 0xff, 4, [0xff]);
+    }
+
+    #[test]
+    fn echo_request_checksum() {
+        // Echo request, id 1, sequence 7, payload "abcd"
+        let mut backing_data = [8, 0, 0, 0, 0, 1, 0, 7, 0x61, 0x62, 0x63, 0x64];
+        {
+            let mut testee = MutEchoPacket::new(&mut backing_data).unwrap();
+            testee.fill_checksum();
+            assert!(testee.as_immutable().verify_checksum());
+        }
+        // 0x0800 + 0x0001 + 0x0007 + 0x6162 + 0x6364 = 0xccce
+        assert_eq!([0x33, 0x31], backing_data[2..4]);
+        backing_data[8] = 0;
+        assert!(!EchoPacket::new(&backing_data).unwrap().verify_checksum());
+    }
+
+    #[test]
+    fn message_dispatch() {
+        let mut backing_data = [0; 8];
+        backing_data[0] = 8;
+        match IcmpPacket::new(&backing_data).unwrap().message() {
+            IcmpMessage::EchoRequest(_) => (),
+            message => panic!("Unexpected message {:?}", message),
+        }
+        backing_data[0] = 0;
+        match IcmpPacket::new(&backing_data).unwrap().message() {
+            IcmpMessage::EchoReply(_) => (),
+            message => panic!("Unexpected message {:?}", message),
+        }
+        backing_data[0] = 13;
+        match IcmpPacket::new(&backing_data).unwrap().message() {
+            IcmpMessage::Other(packet) => assert_eq!(IcmpType::TIMESTAMP, packet.icmp_type()),
+            message => panic!("Unexpected message {:?}", message),
+        }
+    }
+
+    #[test]
+    fn fragmentation_needed() {
+        let mut backing_data = [0; 36];
+        {
+            let mut testee = MutDestinationUnreachablePacket::new(&mut backing_data).unwrap();
+            testee.set_icmp_type(IcmpType::DESTINATION_UNREACHABLE);
+            testee.set_code(DestinationUnreachablePacket::FRAGMENTATION_NEEDED);
+            testee.set_next_hop_mtu(1400);
+            let mut original = MutIpv4Packet::new(testee.payload_mut()).unwrap();
+            original.set_version(4);
+            original.set_header_length(5);
+            original.set_total_length(1500);
+            original.set_protocol(Protocol::UDP);
+        }
+        match IcmpPacket::new(&backing_data).unwrap().message() {
+            IcmpMessage::DestinationUnreachable(testee) => {
+                assert_eq!(Some(1400), testee.fragmentation_needed_mtu());
+                let original = testee.original_packet().unwrap();
+                assert_eq!(Protocol::UDP, original.protocol());
+                assert_eq!(8, original.payload().len());
+            }
+            message => panic!("Unexpected message {:?}", message),
+        }
+    }
+
+    #[test]
+    fn not_fragmentation_needed() {
+        let backing_data = [3, 3, 0, 0, 0, 0, 0x05, 0xdc];
+        let testee = DestinationUnreachablePacket::new(&backing_data).unwrap();
+        assert_eq!(1500, testee.next_hop_mtu());
+        assert_eq!(None, testee.fragmentation_needed_mtu());
+        assert!(testee.original_packet().is_none());
+    }
+
+    #[test]
+    fn error_types() {
+        assert!(IcmpType::TIME_EXCEEDED.is_error());
+        assert!(!IcmpType::ECHO_REQUEST.is_error());
+    }
+
+    #[test]
+    fn display() {
+        let backing_data = [8, 0, 0, 0, 0x12, 0x34, 0, 1];
+        let testee = IcmpPacket::new(&backing_data).unwrap();
+        assert_eq!("ICMP echo request id=4660 seq=1", testee.to_string());
+        let backing_data = [3, 4, 0, 0, 0, 0, 0x05, 0xdc];
+        let testee = IcmpPacket::new(&backing_data).unwrap();
+        assert_eq!("ICMP destination unreachable code=4", testee.to_string());
+        assert_eq!("type 42", IcmpType(42).to_string());
+    }
+}
@@ -0,0 +1,436 @@
+use checksum;
+use core::fmt;
+use ip::{Protocol, PseudoHeader};
+use ipv6::Ipv6Packet;
+
+/// Generates the getters and setters for the type, code and checksum fields that are common to
+/// all ICMPv6 messages, as well as checksum calculation over the IPv6 pseudo-header.
+macro_rules! icmpv6_header {
+    ($name:ident) => {
+        getters!($name
+            pub fn icmp_type(&self) -> Icmpv6Type {
+                Icmpv6Type(read_offset!(self.data(), 0, u8))
+            }
+
+            pub fn code(&self) -> u8 {
+                read_offset!(self.data(), 1, u8)
+            }
+
+            pub fn checksum(&self) -> u16 {
+                read_offset!(self.data(), 2, u16, from_be)
+            }
+        );
+
+        impl<B: AsRef<[u8]>> $name<B> {
+            /// Computes the checksum of the message over the given IPv6 pseudo-header, as it
+            /// should be written to the checksum field. The current value of the checksum field
+            /// is ignored. The message is assumed to span the entire backing slice.
+            pub fn calculate_checksum<P: PseudoHeader>(&self, ip: &P) -> u16 {
+                let data = self.data();
+                let sum = ip.pseudo_header_sum(Protocol::IPV6_ICMP, data.len() as u32);
+                checksum::finish(checksum::sum(checksum::sum(sum, &data[..2]), &data[4..]))
+            }
+
+            /// Returns true if the checksum field contains the correct checksum for the message
+            /// and the given IPv6 pseudo-header.
+            pub fn verify_checksum<P: PseudoHeader>(&self, ip: &P) -> bool {
+                let sum = ip.pseudo_header_sum(Protocol::IPV6_ICMP, self.data().len() as u32);
+                checksum::finish(checksum::sum(sum, self.data())) == 0
+            }
+        }
+
+        setters!($name
+            pub fn set_icmp_type(&mut self, icmp_type: Icmpv6Type) {
+                write_offset!(self.data_mut(), 0, icmp_type.value(), u8);
+            }
+
+            pub fn set_code(&mut self, code: u8) {
+                write_offset!(self.data_mut(), 1, code, u8);
+            }
+
+            pub fn set_checksum(&mut self, checksum: u16) {
+                write_offset!(self.data_mut(), 2, checksum, u16, to_be);
+            }
+        );
+
+        impl<B: AsRef<[u8]> + AsMut<[u8]>> $name<B> {
+            /// Computes the checksum over the given IPv6 pseudo-header and writes it to the
+            /// checksum field. Should be called after all other fields and the payload have been
+            /// written.
+            pub fn fill_checksum<P: PseudoHeader>(&mut self, ip: &P) {
+                let checksum = self.calculate_checksum(ip);
+                self.set_checksum(checksum);
+            }
+        }
+    }
+}
+
+/// Generates the accessor for the original IPv6 packet embedded in ICMPv6 error messages.
+macro_rules! icmpv6_error {
+    ($name:ident) => {
+        getters!($name
+            /// Returns as much of the packet that caused this error as fit in the message.
+            /// Returns `None` if the message is too short to contain an IPv6 header.
+            pub fn original_packet(&self) -> Option<Ipv6Packet<&[u8]>> {
+                Ipv6Packet::new(&self.data()[8..])
+            }
+        );
+    }
+}
+
+mod ndp;
+pub use self::ndp::*;
+
+packet!(Icmpv6Packet, MutIcmpv6Packet, 8);
+icmpv6_header!(Icmpv6Packet);
+
+impl<B: AsRef<[u8]>> Icmpv6Packet<B> {
+    /// Returns a typed view of this message based on its type. Messages too short for their
+    /// type are returned as `Icmpv6Message::Other`.
+    pub fn message(&self) -> Icmpv6Message<'_> {
+        let data = self.data();
+        let message = match self.icmp_type() {
+            Icmpv6Type::ECHO_REQUEST => Some(Icmpv6Message::EchoRequest(EchoPacket(data))),
+            Icmpv6Type::ECHO_REPLY => Some(Icmpv6Message::EchoReply(EchoPacket(data))),
+            Icmpv6Type::DESTINATION_UNREACHABLE => Some(Icmpv6Message::DestinationUnreachable(
+                DestinationUnreachablePacket(data),
+            )),
+            Icmpv6Type::PACKET_TOO_BIG => {
+                Some(Icmpv6Message::PacketTooBig(PacketTooBigPacket(data)))
+            }
+            Icmpv6Type::TIME_EXCEEDED => {
+                Some(Icmpv6Message::TimeExceeded(TimeExceededPacket(data)))
+            }
+            Icmpv6Type::PARAMETER_PROBLEM => {
+                Some(Icmpv6Message::ParameterProblem(ParameterProblemPacket(data)))
+            }
+            Icmpv6Type::ROUTER_SOLICITATION => {
+                RouterSolicitationPacket::new(data).map(Icmpv6Message::RouterSolicitation)
+            }
+            Icmpv6Type::ROUTER_ADVERTISEMENT => {
+                RouterAdvertisementPacket::new(data).map(Icmpv6Message::RouterAdvertisement)
+            }
+            Icmpv6Type::NEIGHBOR_SOLICITATION => {
+                NeighborSolicitationPacket::new(data).map(Icmpv6Message::NeighborSolicitation)
+            }
+            Icmpv6Type::NEIGHBOR_ADVERTISEMENT => {
+                NeighborAdvertisementPacket::new(data).map(Icmpv6Message::NeighborAdvertisement)
+            }
+            Icmpv6Type::REDIRECT => RedirectPacket::new(data).map(Icmpv6Message::Redirect),
+            _ => None,
+        };
+        message.unwrap_or(Icmpv6Message::Other(self.as_immutable()))
+    }
+}
+
+/// Writes a one line summary of the message, like `ICMPv6 echo request id=1 seq=2` or
+/// `ICMPv6 neighbor solicitation who-has fe80::1`.
+impl<B: AsRef<[u8]>> fmt::Display for Icmpv6Packet<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ICMPv6 {}", self.icmp_type())?;
+        match self.message() {
+            Icmpv6Message::EchoRequest(echo) | Icmpv6Message::EchoReply(echo) => {
+                write!(f, " id={} seq={}", echo.identifier(), echo.sequence_number())
+            }
+            Icmpv6Message::PacketTooBig(packet) => write!(f, " mtu={}", packet.mtu()),
+            Icmpv6Message::NeighborSolicitation(packet) => {
+                write!(f, " who-has {}", packet.target_address())
+            }
+            Icmpv6Message::NeighborAdvertisement(packet) => {
+                write!(f, " tgt-is {}", packet.target_address())
+            }
+            Icmpv6Message::RouterSolicitation(_) | Icmpv6Message::RouterAdvertisement(_) => Ok(()),
+            _ => write!(f, " code={}", self.code()),
+        }
+    }
+}
+
+/// A typed view of an ICMPv6 message. Created with `Icmpv6Packet::message()`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Icmpv6Message<'a> {
+    DestinationUnreachable(DestinationUnreachablePacket<&'a [u8]>),
+    PacketTooBig(PacketTooBigPacket<&'a [u8]>),
+    TimeExceeded(TimeExceededPacket<&'a [u8]>),
+    ParameterProblem(ParameterProblemPacket<&'a [u8]>),
+    EchoRequest(EchoPacket<&'a [u8]>),
+    EchoReply(EchoPacket<&'a [u8]>),
+    RouterSolicitation(RouterSolicitationPacket<&'a [u8]>),
+    RouterAdvertisement(RouterAdvertisementPacket<&'a [u8]>),
+    NeighborSolicitation(NeighborSolicitationPacket<&'a [u8]>),
+    NeighborAdvertisement(NeighborAdvertisementPacket<&'a [u8]>),
+    Redirect(RedirectPacket<&'a [u8]>),
+    /// A message of a type without a typed view, or too short for its type.
+    Other(Icmpv6Packet<&'a [u8]>),
+}
+
+
+packet!(EchoPacket, MutEchoPacket, 8);
+icmpv6_header!(EchoPacket);
+
+getters!(EchoPacket
+    pub fn identifier(&self) -> u16 {
+        read_offset!(self.data(), 4, u16, from_be)
+    }
+
+    pub fn sequence_number(&self) -> u16 {
+        read_offset!(self.data(), 6, u16, from_be)
+    }
+);
+
+setters!(EchoPacket
+    pub fn set_identifier(&mut self, identifier: u16) {
+        write_offset!(self.data_mut(), 4, identifier, u16, to_be);
+    }
+
+    pub fn set_sequence_number(&mut self, sequence_number: u16) {
+        write_offset!(self.data_mut(), 6, sequence_number, u16, to_be);
+    }
+);
+
+
+packet!(DestinationUnreachablePacket, MutDestinationUnreachablePacket, 8);
+icmpv6_header!(DestinationUnreachablePacket);
+icmpv6_error!(DestinationUnreachablePacket);
+
+impl DestinationUnreachablePacket<&[u8]> {
+    pub const NO_ROUTE: u8 = 0;
+    pub const ADMINISTRATIVELY_PROHIBITED: u8 = 1;
+    pub const BEYOND_SCOPE_OF_SOURCE_ADDRESS: u8 = 2;
+    pub const ADDRESS_UNREACHABLE: u8 = 3;
+    pub const PORT_UNREACHABLE: u8 = 4;
+    pub const SOURCE_ADDRESS_FAILED_POLICY: u8 = 5;
+    pub const REJECT_ROUTE: u8 = 6;
+}
+
+
+packet!(PacketTooBigPacket, MutPacketTooBigPacket, 8);
+icmpv6_header!(PacketTooBigPacket);
+icmpv6_error!(PacketTooBigPacket);
+
+getters!(PacketTooBigPacket
+    pub fn mtu(&self) -> u32 {
+        read_offset!(self.data(), 4, u32, from_be)
+    }
+);
+
+setters!(PacketTooBigPacket
+    pub fn set_mtu(&mut self, mtu: u32) {
+        write_offset!(self.data_mut(), 4, mtu, u32, to_be);
+    }
+);
+
+
+packet!(TimeExceededPacket, MutTimeExceededPacket, 8);
+icmpv6_header!(TimeExceededPacket);
+icmpv6_error!(TimeExceededPacket);
+
+impl TimeExceededPacket<&[u8]> {
+    pub const HOP_LIMIT_EXCEEDED: u8 = 0;
+    pub const FRAGMENT_REASSEMBLY_TIME_EXCEEDED: u8 = 1;
+}
+
+
+packet!(ParameterProblemPacket, MutParameterProblemPacket, 8);
+icmpv6_header!(ParameterProblemPacket);
+icmpv6_error!(ParameterProblemPacket);
+
+impl ParameterProblemPacket<&[u8]> {
+    pub const ERRONEOUS_HEADER_FIELD: u8 = 0;
+    pub const UNRECOGNIZED_NEXT_HEADER: u8 = 1;
+    pub const UNRECOGNIZED_IPV6_OPTION: u8 = 2;
+}
+
+getters!(ParameterProblemPacket
+    /// Returns the offset of the byte in the original packet where the error was detected.
+    pub fn pointer(&self) -> u32 {
+        read_offset!(self.data(), 4, u32, from_be)
+    }
+);
+
+setters!(ParameterProblemPacket
+    pub fn set_pointer(&mut self, pointer: u32) {
+        write_offset!(self.data_mut(), 4, pointer, u32, to_be);
+    }
+);
+
+
+/// Represents the eight bit type field of an ICMPv6 message. See [IANA's list] for the defined
+/// values.
+///
+/// [IANA's list]: https://www.iana.org/assignments/icmpv6-parameters/icmpv6-parameters.xhtml
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Icmpv6Type(pub u8);
+
+impl Icmpv6Type {
+    pub const DESTINATION_UNREACHABLE: Icmpv6Type = Icmpv6Type(1);
+    pub const PACKET_TOO_BIG: Icmpv6Type = Icmpv6Type(2);
+    pub const TIME_EXCEEDED: Icmpv6Type = Icmpv6Type(3);
+    pub const PARAMETER_PROBLEM: Icmpv6Type = Icmpv6Type(4);
+    pub const ECHO_REQUEST: Icmpv6Type = Icmpv6Type(128);
+    pub const ECHO_REPLY: Icmpv6Type = Icmpv6Type(129);
+    pub const MULTICAST_LISTENER_QUERY: Icmpv6Type = Icmpv6Type(130);
+    pub const MULTICAST_LISTENER_REPORT: Icmpv6Type = Icmpv6Type(131);
+    pub const MULTICAST_LISTENER_DONE: Icmpv6Type = Icmpv6Type(132);
+    pub const ROUTER_SOLICITATION: Icmpv6Type = Icmpv6Type(133);
+    pub const ROUTER_ADVERTISEMENT: Icmpv6Type = Icmpv6Type(134);
+    pub const NEIGHBOR_SOLICITATION: Icmpv6Type = Icmpv6Type(135);
+    pub const NEIGHBOR_ADVERTISEMENT: Icmpv6Type = Icmpv6Type(136);
+    pub const REDIRECT: Icmpv6Type = Icmpv6Type(137);
+    pub const MULTICAST_LISTENER_REPORT_V2: Icmpv6Type = Icmpv6Type(143);
+
+    /// Returns the numeric representation of this ICMPv6 type.
+    #[inline]
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns true if this is an error message type. Error messages have types below 128.
+    pub fn is_error(&self) -> bool {
+        self.0 < 128
+    }
+
+    /// Returns the name of this type if it is one of the associated constants.
+    pub fn name(&self) -> Option<&'static str> {
+        match *self {
+            Icmpv6Type::DESTINATION_UNREACHABLE => Some("destination unreachable"),
+            Icmpv6Type::PACKET_TOO_BIG => Some("packet too big"),
+            Icmpv6Type::TIME_EXCEEDED => Some("time exceeded"),
+            Icmpv6Type::PARAMETER_PROBLEM => Some("parameter problem"),
+            Icmpv6Type::ECHO_REQUEST => Some("echo request"),
+            Icmpv6Type::ECHO_REPLY => Some("echo reply"),
+            Icmpv6Type::MULTICAST_LISTENER_QUERY => Some("multicast listener query"),
+            Icmpv6Type::MULTICAST_LISTENER_REPORT => Some("multicast listener report"),
+            Icmpv6Type::MULTICAST_LISTENER_DONE => Some("multicast listener done"),
+            Icmpv6Type::ROUTER_SOLICITATION => Some("router solicitation"),
+            Icmpv6Type::ROUTER_ADVERTISEMENT => Some("router advertisement"),
+            Icmpv6Type::NEIGHBOR_SOLICITATION => Some("neighbor solicitation"),
+            Icmpv6Type::NEIGHBOR_ADVERTISEMENT => Some("neighbor advertisement"),
+            Icmpv6Type::REDIRECT => Some("redirect"),
+            Icmpv6Type::MULTICAST_LISTENER_REPORT_V2 => Some("multicast listener report v2"),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the name of the type, or `type N` for unnamed types.
+impl fmt::Display for Icmpv6Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "type {}", self.0),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv6Addr;
+
+    macro_rules! icmpv6_setget_test {
+        ($name:ident, $set_name:ident, $value:expr, $offset:expr, $expected:expr) => {
+            setget_test!(MutIcmpv6Packet, $name, $set_name, $value, $offset, $expected);
+        }
+    }
+
+    icmpv6_setget_test!(icmp_type, set_icmp_type, Icmpv6Type(0xff), 0, [0xff]);
+    icmpv6_setget_test!(code, set_code, 0xff, 1, [0xff]);
+    icmpv6_setget_test!(checksum, set_checksum, 0xfeff, 2, [0xfe, 0xff]);
+
+    mod echo {
+        use super::super::*;
+        setget_test!(MutEchoPacket, identifier, set_identifier, 0xabcd, 4, [0xab, 0xcd]);
+        setget_test!(
+            MutEchoPacket,
+            sequence_number,
+            set_sequence_number,
+            0x1234,
+            6,
+            [0x12, 0x34]
+        );
+    }
+
+    mod packet_too_big {
+        use super::super::*;
+        setget_test!(MutPacketTooBigPacket, mtu, set_mtu, 1280, 4, [0, 0, 0x05, 0]);
+    }
+
+    mod parameter_problem {
+        use super::super::*;
+        setget_test!(
+            MutParameterProblemPacket,
+            pointer,
+            set_pointer,
+            0x0102_0304,
+            4,
+            [1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn echo_request_checksum() {
+        let addrs = (Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1), Ipv6Addr::LOCALHOST);
+        let mut backing_data = [0; 16];
+        {
+            let mut testee = MutEchoPacket::new(&mut backing_data[..10]).unwrap();
+            testee.set_icmp_type(Icmpv6Type::ECHO_REQUEST);
+            testee.set_identifier(1);
+            testee.set_sequence_number(2);
+            testee.payload_mut().copy_from_slice(&[0xab, 0xcd]);
+            testee.fill_checksum(&addrs);
+            assert!(testee.as_immutable().verify_checksum(&addrs));
+        }
+        let mut pseudo_header = Vec::new();
+        pseudo_header.extend_from_slice(&addrs.0.octets());
+        pseudo_header.extend_from_slice(&addrs.1.octets());
+        pseudo_header.extend_from_slice(&[0, 0, 0, 10, 0, 0, 0, 58]);
+        let sum = checksum::sum(0, &pseudo_header);
+        assert_eq!(0, checksum::finish(checksum::sum(sum, &backing_data[..10])));
+    }
+
+    #[test]
+    fn packet_too_big_original_packet() {
+        let mut backing_data = [0; 48];
+        backing_data[0] = 2;
+        backing_data[8] = 0x60;
+        match Icmpv6Packet::new(&backing_data).unwrap().message() {
+            Icmpv6Message::PacketTooBig(testee) => {
+                assert_eq!(6, testee.original_packet().unwrap().version());
+            }
+            message => panic!("Unexpected message {:?}", message),
+        }
+        assert!(PacketTooBigPacket::new(&backing_data[..47])
+            .unwrap()
+            .original_packet()
+            .is_none());
+    }
+
+    #[test]
+    fn short_ndp_message_is_other() {
+        let backing_data = [135, 0, 0, 0, 0, 0, 0, 0];
+        match Icmpv6Packet::new(&backing_data).unwrap().message() {
+            Icmpv6Message::Other(_) => (),
+            message => panic!("Unexpected message {:?}", message),
+        }
+    }
+
+    #[test]
+    fn error_types() {
+        assert!(Icmpv6Type::PACKET_TOO_BIG.is_error());
+        assert!(!Icmpv6Type::NEIGHBOR_SOLICITATION.is_error());
+    }
+
+    #[test]
+    fn display() {
+        let backing_data = [129, 0, 0, 0, 0x12, 0x34, 0, 1];
+        let testee = Icmpv6Packet::new(&backing_data).unwrap();
+        assert_eq!("ICMPv6 echo reply id=4660 seq=1", testee.to_string());
+        let mut backing_data = [0; 24];
+        backing_data[0] = 135;
+        backing_data[8..10].copy_from_slice(&[0xfe, 0x80]);
+        backing_data[23] = 1;
+        let testee = Icmpv6Packet::new(&backing_data).unwrap();
+        assert_eq!("ICMPv6 neighbor solicitation who-has fe80::1", testee.to_string());
+    }
+}
@@ -0,0 +1,544 @@
+//! Neighbor Discovery Protocol messages and options, as defined in [RFC 4861].
+//!
+//! [RFC 4861]: https://tools.ietf.org/html/rfc4861
+
+use super::Icmpv6Type;
+use checksum;
+use ethernet::MacAddr;
+use ip::{Protocol, PseudoHeader};
+use core::net::Ipv6Addr;
+use core::slice::Chunks;
+
+packet!(RouterSolicitationPacket, MutRouterSolicitationPacket, 8);
+icmpv6_header!(RouterSolicitationPacket);
+
+getters!(RouterSolicitationPacket
+    /// Returns an iterator over the NDP options following the message.
+    pub fn options(&self) -> NdpOptions<'_> {
+        NdpOptions::new(self.payload())
+    }
+);
+
+
+packet!(RouterAdvertisementPacket, MutRouterAdvertisementPacket, 16);
+icmpv6_header!(RouterAdvertisementPacket);
+
+getters!(RouterAdvertisementPacket
+    pub fn current_hop_limit(&self) -> u8 {
+        read_offset!(self.data(), 4, u8)
+    }
+
+    pub fn flags(&self) -> RouterAdvertisementFlags {
+        RouterAdvertisementFlags::from_bits_truncate(read_offset!(self.data(), 5, u8))
+    }
+
+    /// Returns the lifetime of the router as a default router, in seconds.
+    pub fn router_lifetime(&self) -> u16 {
+        read_offset!(self.data(), 6, u16, from_be)
+    }
+
+    /// Returns the reachable time, in milliseconds.
+    pub fn reachable_time(&self) -> u32 {
+        read_offset!(self.data(), 8, u32, from_be)
+    }
+
+    /// Returns the retransmission timer, in milliseconds.
+    pub fn retrans_timer(&self) -> u32 {
+        read_offset!(self.data(), 12, u32, from_be)
+    }
+
+    /// Returns an iterator over the NDP options following the message.
+    pub fn options(&self) -> NdpOptions<'_> {
+        NdpOptions::new(self.payload())
+    }
+);
+
+setters!(RouterAdvertisementPacket
+    pub fn set_current_hop_limit(&mut self, current_hop_limit: u8) {
+        write_offset!(self.data_mut(), 4, current_hop_limit, u8);
+    }
+
+    pub fn set_flags(&mut self, flags: RouterAdvertisementFlags) {
+        write_offset!(self.data_mut(), 5, flags.bits(), u8);
+    }
+
+    pub fn set_router_lifetime(&mut self, router_lifetime: u16) {
+        write_offset!(self.data_mut(), 6, router_lifetime, u16, to_be);
+    }
+
+    pub fn set_reachable_time(&mut self, reachable_time: u32) {
+        write_offset!(self.data_mut(), 8, reachable_time, u32, to_be);
+    }
+
+    pub fn set_retrans_timer(&mut self, retrans_timer: u32) {
+        write_offset!(self.data_mut(), 12, retrans_timer, u32, to_be);
+    }
+);
+
+bitflags! {
+    /// Bitmasks for the flags byte in a Router Advertisement.
+    pub struct RouterAdvertisementFlags: u8 {
+        /// Managed address configuration, addresses are available via DHCPv6.
+        const MANAGED = 0b1000_0000;
+        /// Other configuration is available via DHCPv6.
+        const OTHER = 0b0100_0000;
+    }
+}
+
+
+packet!(NeighborSolicitationPacket, MutNeighborSolicitationPacket, 24);
+icmpv6_header!(NeighborSolicitationPacket);
+
+getters!(NeighborSolicitationPacket
+    pub fn target_address(&self) -> Ipv6Addr {
+        Ipv6Addr::from(read_offset!(self.data(), 8, [u8; 16]))
+    }
+
+    /// Returns an iterator over the NDP options following the message.
+    pub fn options(&self) -> NdpOptions<'_> {
+        NdpOptions::new(self.payload())
+    }
+);
+
+setters!(NeighborSolicitationPacket
+    pub fn set_target_address(&mut self, target_address: Ipv6Addr) {
+        write_offset!(self.data_mut(), 8, target_address.octets(), [u8; 16]);
+    }
+);
+
+
+packet!(NeighborAdvertisementPacket, MutNeighborAdvertisementPacket, 24);
+icmpv6_header!(NeighborAdvertisementPacket);
+
+getters!(NeighborAdvertisementPacket
+    pub fn flags(&self) -> NeighborAdvertisementFlags {
+        NeighborAdvertisementFlags::from_bits_truncate(read_offset!(self.data(), 4, u8))
+    }
+
+    pub fn target_address(&self) -> Ipv6Addr {
+        Ipv6Addr::from(read_offset!(self.data(), 8, [u8; 16]))
+    }
+
+    /// Returns an iterator over the NDP options following the message.
+    pub fn options(&self) -> NdpOptions<'_> {
+        NdpOptions::new(self.payload())
+    }
+);
+
+setters!(NeighborAdvertisementPacket
+    pub fn set_flags(&mut self, flags: NeighborAdvertisementFlags) {
+        write_offset!(self.data_mut(), 4, flags.bits(), u8);
+    }
+
+    pub fn set_target_address(&mut self, target_address: Ipv6Addr) {
+        write_offset!(self.data_mut(), 8, target_address.octets(), [u8; 16]);
+    }
+);
+
+bitflags! {
+    /// Bitmasks for the flags in a Neighbor Advertisement.
+    pub struct NeighborAdvertisementFlags: u8 {
+        /// The sender is a router.
+        const ROUTER = 0b1000_0000;
+        /// The advertisement was sent in response to a Neighbor Solicitation.
+        const SOLICITED = 0b0100_0000;
+        /// The advertisement should override an existing cache entry.
+        const OVERRIDE = 0b0010_0000;
+    }
+}
+
+
+packet!(RedirectPacket, MutRedirectPacket, 40);
+icmpv6_header!(RedirectPacket);
+
+getters!(RedirectPacket
+    /// Returns the address that is a better first hop for the destination.
+    pub fn target_address(&self) -> Ipv6Addr {
+        Ipv6Addr::from(read_offset!(self.data(), 8, [u8; 16]))
+    }
+
+    pub fn destination_address(&self) -> Ipv6Addr {
+        Ipv6Addr::from(read_offset!(self.data(), 24, [u8; 16]))
+    }
+
+    /// Returns an iterator over the NDP options following the message.
+    pub fn options(&self) -> NdpOptions<'_> {
+        NdpOptions::new(self.payload())
+    }
+);
+
+setters!(RedirectPacket
+    pub fn set_target_address(&mut self, target_address: Ipv6Addr) {
+        write_offset!(self.data_mut(), 8, target_address.octets(), [u8; 16]);
+    }
+
+    pub fn set_destination_address(&mut self, destination_address: Ipv6Addr) {
+        write_offset!(self.data_mut(), 24, destination_address.octets(), [u8; 16]);
+    }
+);
+
+
+/// Represents the type byte of an NDP option. See [IANA's list] for the defined values.
+///
+/// [IANA's list]: https://www.iana.org/assignments/icmpv6-parameters/icmpv6-parameters.xhtml
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct NdpOptionType(pub u8);
+
+impl NdpOptionType {
+    pub const SOURCE_LINK_LAYER_ADDRESS: NdpOptionType = NdpOptionType(1);
+    pub const TARGET_LINK_LAYER_ADDRESS: NdpOptionType = NdpOptionType(2);
+    pub const PREFIX_INFORMATION: NdpOptionType = NdpOptionType(3);
+    pub const REDIRECTED_HEADER: NdpOptionType = NdpOptionType(4);
+    pub const MTU: NdpOptionType = NdpOptionType(5);
+    pub const RECURSIVE_DNS_SERVER: NdpOptionType = NdpOptionType(25);
+
+    /// Returns the numeric representation of this option type.
+    #[inline]
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+/// A single NDP option, as yielded by the [`NdpOptions`] iterator.
+///
+/// [`NdpOptions`]: struct.NdpOptions.html
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum NdpOption<'a> {
+    SourceLinkLayerAddress(MacAddr),
+    TargetLinkLayerAddress(MacAddr),
+    PrefixInformation(PrefixInformation<'a>),
+    /// Contains the original packet, possibly truncated, of a Redirected Header option.
+    RedirectedHeader(&'a [u8]),
+    Mtu(u32),
+    /// Recursive DNS Server option as defined in [RFC 8106].
+    ///
+    /// [RFC 8106]: https://tools.ietf.org/html/rfc8106
+    RecursiveDnsServer(RecursiveDnsServer<'a>),
+    /// An option of a type this crate does not know how to parse, or a link-layer address option
+    /// that is not six bytes long. Contains the option type and the option data, not including
+    /// the type and length bytes.
+    Unknown(NdpOptionType, &'a [u8]),
+    /// The option data could not be parsed, typically because of an invalid length byte.
+    /// Contains the remaining option bytes. Iteration ends after this item.
+    Malformed(&'a [u8]),
+}
+
+/// Iterator over the options in an NDP message. Created with the `options()` method on the NDP
+/// message types.
+#[derive(Debug, Clone)]
+pub struct NdpOptions<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> NdpOptions<'a> {
+    /// Creates an iterator over the options encoded in `data`.
+    pub fn new(data: &'a [u8]) -> NdpOptions<'a> {
+        NdpOptions { data }
+    }
+
+    fn parse(option_type: NdpOptionType, option: &'a [u8]) -> Option<NdpOption<'a>> {
+        let length = option.len();
+        match option_type {
+            NdpOptionType::SOURCE_LINK_LAYER_ADDRESS if length == 8 => Some(
+                NdpOption::SourceLinkLayerAddress(MacAddr::from_slice(&option[2..8])),
+            ),
+            NdpOptionType::TARGET_LINK_LAYER_ADDRESS if length == 8 => Some(
+                NdpOption::TargetLinkLayerAddress(MacAddr::from_slice(&option[2..8])),
+            ),
+            NdpOptionType::PREFIX_INFORMATION if length == 32 => {
+                Some(NdpOption::PrefixInformation(PrefixInformation(option)))
+            }
+            NdpOptionType::REDIRECTED_HEADER => Some(NdpOption::RedirectedHeader(&option[8..])),
+            NdpOptionType::MTU if length == 8 => Some(NdpOption::Mtu(u32::from_be_bytes(
+                read_offset!(option, 4, [u8; 4]),
+            ))),
+            NdpOptionType::RECURSIVE_DNS_SERVER if length >= 24 => {
+                Some(NdpOption::RecursiveDnsServer(RecursiveDnsServer(option)))
+            }
+            NdpOptionType::PREFIX_INFORMATION |
+            NdpOptionType::MTU |
+            NdpOptionType::RECURSIVE_DNS_SERVER => None,
+            _ => Some(NdpOption::Unknown(option_type, &option[2..])),
+        }
+    }
+}
+
+impl<'a> Iterator for NdpOptions<'a> {
+    type Item = NdpOption<'a>;
+
+    fn next(&mut self) -> Option<NdpOption<'a>> {
+        let data = self.data;
+        let option_type = NdpOptionType(*data.first()?);
+        // The length is in units of eight bytes and zero is invalid
+        let length = data.get(1).map(|&length| length as usize * 8).unwrap_or(0);
+        let option = if length > 0 && length <= data.len() {
+            Self::parse(option_type, &data[..length])
+        } else {
+            None
+        };
+        match option {
+            Some(option) => {
+                self.data = &data[length..];
+                Some(option)
+            }
+            None => {
+                self.data = &[];
+                Some(NdpOption::Malformed(data))
+            }
+        }
+    }
+}
+
+/// View of the Prefix Information option.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PrefixInformation<'a>(&'a [u8]);
+
+impl<'a> PrefixInformation<'a> {
+    /// Returns the raw bytes of this option, including the type and length bytes.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn prefix_length(&self) -> u8 {
+        read_offset!(self.0, 2, u8)
+    }
+
+    /// Returns true if the prefix can be used for on-link determination.
+    pub fn on_link(&self) -> bool {
+        read_offset!(self.0, 3, u8) & 0x80 != 0
+    }
+
+    /// Returns true if the prefix can be used for stateless address autoconfiguration.
+    pub fn autonomous(&self) -> bool {
+        read_offset!(self.0, 3, u8) & 0x40 != 0
+    }
+
+    /// Returns the valid lifetime of the prefix, in seconds.
+    pub fn valid_lifetime(&self) -> u32 {
+        read_offset!(self.0, 4, u32, from_be)
+    }
+
+    /// Returns the preferred lifetime of the prefix, in seconds.
+    pub fn preferred_lifetime(&self) -> u32 {
+        read_offset!(self.0, 8, u32, from_be)
+    }
+
+    pub fn prefix(&self) -> Ipv6Addr {
+        Ipv6Addr::from(read_offset!(self.0, 16, [u8; 16]))
+    }
+}
+
+/// View of the Recursive DNS Server option.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RecursiveDnsServer<'a>(&'a [u8]);
+
+impl<'a> RecursiveDnsServer<'a> {
+    /// Returns the raw bytes of this option, including the type and length bytes.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Returns the time the servers may be used, in seconds.
+    pub fn lifetime(&self) -> u32 {
+        read_offset!(self.0, 4, u32, from_be)
+    }
+
+    /// Returns an iterator over the addresses of the DNS servers.
+    pub fn addresses(&self) -> Ipv6Addresses<'a> {
+        Ipv6Addresses(self.0[8..].chunks(16))
+    }
+}
+
+/// Iterator over IPv6 addresses stored back to back in an option.
+#[derive(Debug, Clone)]
+pub struct Ipv6Addresses<'a>(Chunks<'a, u8>);
+
+impl<'a> Iterator for Ipv6Addresses<'a> {
+    type Item = Ipv6Addr;
+
+    fn next(&mut self) -> Option<Ipv6Addr> {
+        self.0
+            .next()
+            .filter(|chunk| chunk.len() == 16)
+            .map(|chunk| Ipv6Addr::from(read_offset!(chunk, 0, [u8; 16])))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icmpv6::{Icmpv6Message, Icmpv6Packet};
+
+    mod router_advertisement {
+        use super::super::*;
+        setget_test!(
+            MutRouterAdvertisementPacket,
+            current_hop_limit,
+            set_current_hop_limit,
+            64,
+            4,
+            [64]
+        );
+        setget_test!(
+            MutRouterAdvertisementPacket,
+            flags,
+            set_flags,
+            RouterAdvertisementFlags::all(),
+            5,
+            [0xc0]
+        );
+        setget_test!(
+            MutRouterAdvertisementPacket,
+            router_lifetime,
+            set_router_lifetime,
+            1800,
+            6,
+            [0x07, 0x08]
+        );
+        setget_test!(
+            MutRouterAdvertisementPacket,
+            reachable_time,
+            set_reachable_time,
+            0x0102_0304,
+            8,
+            [1, 2, 3, 4]
+        );
+        setget_test!(
+            MutRouterAdvertisementPacket,
+            retrans_timer,
+            set_retrans_timer,
+            0x0506_0708,
+            12,
+            [5, 6, 7, 8]
+        );
+    }
+
+    mod neighbor_advertisement {
+        use super::super::*;
+        setget_test!(
+            MutNeighborAdvertisementPacket,
+            flags,
+            set_flags,
+            NeighborAdvertisementFlags::all(),
+            4,
+            [0xe0]
+        );
+        setget_test!(
+            MutNeighborAdvertisementPacket,
+            target_address,
+            set_target_address,
+            Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+            8,
+            [0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+    }
+
+    mod redirect {
+        use super::super::*;
+        setget_test!(
+            MutRedirectPacket,
+            destination_address,
+            set_destination_address,
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+            24,
+            [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn neighbor_solicitation() {
+        let mut backing_data = [0; 32];
+        backing_data[0] = 135;
+        backing_data[8..10].copy_from_slice(&[0xfe, 0x80]);
+        backing_data[23] = 2;
+        backing_data[24..32].copy_from_slice(&[1, 1, 0x02, 0x00, 0x5e, 0x10, 0x20, 0x30]);
+        match Icmpv6Packet::new(&backing_data).unwrap().message() {
+            Icmpv6Message::NeighborSolicitation(testee) => {
+                assert_eq!(
+                    Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 2),
+                    testee.target_address()
+                );
+                let options: Vec<_> = testee.options().collect();
+                assert_eq!(
+                    vec![
+                        NdpOption::SourceLinkLayerAddress(MacAddr([
+                            0x02, 0x00, 0x5e, 0x10, 0x20, 0x30,
+                        ])),
+                    ],
+                    options
+                );
+            }
+            message => panic!("Unexpected message {:?}", message),
+        }
+    }
+
+    #[test]
+    fn router_advertisement_options() {
+        let mut options = vec![
+            // MTU
+            5, 1, 0, 0, 0, 0, 0x05, 0xdc,
+            // Prefix information, on-link and autonomous
+            3, 4, 64, 0xc0, 0, 0, 0x0e, 0x10, 0, 0, 0x07, 0x08, 0, 0, 0, 0,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            // RDNSS with one server
+            25, 3, 0, 0, 0, 0, 0x02, 0x58,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x53,
+            // Target link-layer address
+            2, 1, 1, 2, 3, 4, 5, 6,
+        ];
+        let mut backing_data = vec![134, 0, 0, 0, 64, 0x80, 0, 30, 0, 0, 0, 0, 0, 0, 0, 0];
+        backing_data.append(&mut options);
+        let testee = RouterAdvertisementPacket::new(&backing_data).unwrap();
+        assert_eq!(64, testee.current_hop_limit());
+        assert_eq!(RouterAdvertisementFlags::MANAGED, testee.flags());
+        assert_eq!(30, testee.router_lifetime());
+
+        let mut options = testee.options();
+        assert_eq!(Some(NdpOption::Mtu(1500)), options.next());
+        match options.next() {
+            Some(NdpOption::PrefixInformation(prefix)) => {
+                assert_eq!(64, prefix.prefix_length());
+                assert!(prefix.on_link());
+                assert!(prefix.autonomous());
+                assert_eq!(3600, prefix.valid_lifetime());
+                assert_eq!(1800, prefix.preferred_lifetime());
+                assert_eq!(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), prefix.prefix());
+            }
+            option => panic!("Unexpected option {:?}", option),
+        }
+        match options.next() {
+            Some(NdpOption::RecursiveDnsServer(rdnss)) => {
+                assert_eq!(600, rdnss.lifetime());
+                let addresses: Vec<_> = rdnss.addresses().collect();
+                assert_eq!(
+                    vec![Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53)],
+                    addresses
+                );
+            }
+            option => panic!("Unexpected option {:?}", option),
+        }
+        assert_eq!(
+            Some(NdpOption::TargetLinkLayerAddress(MacAddr([1, 2, 3, 4, 5, 6]))),
+            options.next()
+        );
+        assert_eq!(None, options.next());
+    }
+
+    #[test]
+    fn zero_length_option() {
+        let data = [1, 0, 1, 2, 3, 4, 5, 6];
+        let options: Vec<_> = NdpOptions::new(&data).collect();
+        assert_eq!(vec![NdpOption::Malformed(&data)], options);
+    }
+
+    #[test]
+    fn non_ethernet_link_layer_address() {
+        let data = [1, 2, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14];
+        let options: Vec<_> = NdpOptions::new(&data).collect();
+        assert_eq!(
+            vec![NdpOption::Unknown(NdpOptionType::SOURCE_LINK_LAYER_ADDRESS, &data[2..])],
+            options
+        );
+    }
+}
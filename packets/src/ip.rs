@@ -1,27 +1,458 @@
+use checksum;
+use ipv4::Ipv4Packet;
+use ipv6::Ipv6Packet;
+use core::fmt;
+use core::net::{Ipv4Addr, Ipv6Addr};
+
 /// Represents the eight bit header field in IPv4/IPv6 that defines what protocol the payload has.
-/// See [this list] for the full definition.
 ///
-/// [this list]: https://en.wikipedia.org/wiki/List_of_IP_protocol_numbers
+/// All assigned protocol numbers of [IANA's list] are attached as associated constants, named
+/// after their IANA keywords. `name()` returns the keyword, and `FromStr` parses keywords as well
+/// as numbers.
+///
+/// [IANA's list]: https://www.iana.org/assignments/protocol-numbers/protocol-numbers.xhtml
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Protocol(pub u8);
 
-impl Protocol {
-    pub const ICMP: Protocol = Protocol(1);
-    pub const TCP: Protocol = Protocol(6);
-    pub const UDP: Protocol = Protocol(17);
-    pub const RESERVED: Protocol = Protocol(255);
+named_values!(Protocol, u8, ProtocolParseError, "Unknown IP protocol", {
+    /// IPv6 Hop-by-Hop Option.
+    HOPOPT = 0, "HOPOPT";
+    /// Internet Control Message.
+    ICMP = 1, "ICMP";
+    /// Internet Group Management.
+    IGMP = 2, "IGMP";
+    /// Gateway-to-Gateway.
+    GGP = 3, "GGP";
+    /// IPv4 encapsulation.
+    IPV4 = 4, "IPv4";
+    /// Stream.
+    ST = 5, "ST";
+    /// Transmission Control.
+    TCP = 6, "TCP";
+    CBT = 7, "CBT";
+    /// Exterior Gateway Protocol.
+    EGP = 8, "EGP";
+    /// Any private interior gateway, used by Cisco for their IGRP.
+    IGP = 9, "IGP";
+    /// BBN RCC Monitoring.
+    BBN_RCC_MON = 10, "BBN-RCC-MON";
+    /// Network Voice Protocol.
+    NVP_II = 11, "NVP-II";
+    PUP = 12, "PUP";
+    /// ARGUS, deprecated.
+    ARGUS = 13, "ARGUS";
+    EMCON = 14, "EMCON";
+    /// Cross Net Debugger.
+    XNET = 15, "XNET";
+    CHAOS = 16, "CHAOS";
+    /// User Datagram.
+    UDP = 17, "UDP";
+    /// Multiplexing.
+    MUX = 18, "MUX";
+    /// DCN Measurement Subsystems.
+    DCN_MEAS = 19, "DCN-MEAS";
+    /// Host Monitoring.
+    HMP = 20, "HMP";
+    /// Packet Radio Measurement.
+    PRM = 21, "PRM";
+    /// XEROX NS IDP.
+    XNS_IDP = 22, "XNS-IDP";
+    TRUNK_1 = 23, "TRUNK-1";
+    TRUNK_2 = 24, "TRUNK-2";
+    LEAF_1 = 25, "LEAF-1";
+    LEAF_2 = 26, "LEAF-2";
+    /// Reliable Data Protocol.
+    RDP = 27, "RDP";
+    /// Internet Reliable Transaction.
+    IRTP = 28, "IRTP";
+    /// ISO Transport Protocol Class 4.
+    ISO_TP4 = 29, "ISO-TP4";
+    /// Bulk Data Transfer Protocol.
+    NETBLT = 30, "NETBLT";
+    /// MFE Network Services Protocol.
+    MFE_NSP = 31, "MFE-NSP";
+    /// MERIT Internodal Protocol.
+    MERIT_INP = 32, "MERIT-INP";
+    /// Datagram Congestion Control Protocol.
+    DCCP = 33, "DCCP";
+    /// Third Party Connect Protocol.
+    THREE_PC = 34, "3PC";
+    /// Inter-Domain Policy Routing Protocol.
+    IDPR = 35, "IDPR";
+    XTP = 36, "XTP";
+    /// Datagram Delivery Protocol.
+    DDP = 37, "DDP";
+    /// IDPR Control Message Transport Proto.
+    IDPR_CMTP = 38, "IDPR-CMTP";
+    /// TP++ Transport Protocol.
+    TP_PLUS_PLUS = 39, "TP++";
+    /// IL Transport Protocol.
+    IL = 40, "IL";
+    /// IPv6 encapsulation.
+    IPV6 = 41, "IPv6";
+    /// Source Demand Routing Protocol.
+    SDRP = 42, "SDRP";
+    /// Routing Header for IPv6.
+    IPV6_ROUTE = 43, "IPv6-Route";
+    /// Fragment Header for IPv6.
+    IPV6_FRAG = 44, "IPv6-Frag";
+    /// Inter-Domain Routing Protocol.
+    IDRP = 45, "IDRP";
+    /// Reservation Protocol.
+    RSVP = 46, "RSVP";
+    /// Generic Routing Encapsulation.
+    GRE = 47, "GRE";
+    /// Dynamic Source Routing Protocol.
+    DSR = 48, "DSR";
+    BNA = 49, "BNA";
+    /// Encap Security Payload.
+    ESP = 50, "ESP";
+    /// Authentication Header.
+    AH = 51, "AH";
+    /// Integrated Net Layer Security TUBA.
+    I_NLSP = 52, "I-NLSP";
+    /// IP with Encryption, deprecated.
+    SWIPE = 53, "SWIPE";
+    /// NBMA Address Resolution Protocol.
+    NARP = 54, "NARP";
+    /// Minimal IPv4 Encapsulation.
+    MIN_IPV4 = 55, "Min-IPv4";
+    /// Transport Layer Security Protocol using Kryptonet key management.
+    TLSP = 56, "TLSP";
+    SKIP = 57, "SKIP";
+    /// ICMP for IPv6.
+    IPV6_ICMP = 58, "IPv6-ICMP";
+    /// No Next Header for IPv6.
+    IPV6_NONXT = 59, "IPv6-NoNxt";
+    /// Destination Options for IPv6.
+    IPV6_OPTS = 60, "IPv6-Opts";
+    CFTP = 62, "CFTP";
+    /// SATNET and Backroom EXPAK.
+    SAT_EXPAK = 64, "SAT-EXPAK";
+    KRYPTOLAN = 65, "KRYPTOLAN";
+    /// MIT Remote Virtual Disk Protocol.
+    RVD = 66, "RVD";
+    /// Internet Pluribus Packet Core.
+    IPPC = 67, "IPPC";
+    /// SATNET Monitoring.
+    SAT_MON = 69, "SAT-MON";
+    /// VISA Protocol.
+    VISA = 70, "VISA";
+    /// Internet Packet Core Utility.
+    IPCV = 71, "IPCV";
+    /// Computer Protocol Network Executive.
+    CPNX = 72, "CPNX";
+    /// Computer Protocol Heart Beat.
+    CPHB = 73, "CPHB";
+    /// Wang Span Network.
+    WSN = 74, "WSN";
+    /// Packet Video Protocol.
+    PVP = 75, "PVP";
+    /// Backroom SATNET Monitoring.
+    BR_SAT_MON = 76, "BR-SAT-MON";
+    /// SUN ND PROTOCOL-Temporary.
+    SUN_ND = 77, "SUN-ND";
+    /// WIDEBAND Monitoring.
+    WB_MON = 78, "WB-MON";
+    /// WIDEBAND EXPAK.
+    WB_EXPAK = 79, "WB-EXPAK";
+    /// ISO Internet Protocol.
+    ISO_IP = 80, "ISO-IP";
+    VMTP = 81, "VMTP";
+    SECURE_VMTP = 82, "SECURE-VMTP";
+    VINES = 83, "VINES";
+    /// Transaction Transport Protocol, shares its number with IPTM.
+    TTP = 84, "TTP";
+    NSFNET_IGP = 85, "NSFNET-IGP";
+    /// Dissimilar Gateway Protocol.
+    DGP = 86, "DGP";
+    TCF = 87, "TCF";
+    EIGRP = 88, "EIGRP";
+    /// OSPF.
+    OSPFIGP = 89, "OSPFIGP";
+    /// Sprite RPC Protocol.
+    SPRITE_RPC = 90, "Sprite-RPC";
+    /// Locus Address Resolution Protocol.
+    LARP = 91, "LARP";
+    /// Multicast Transport Protocol.
+    MTP = 92, "MTP";
+    /// AX.25 Frames.
+    AX25 = 93, "AX.25";
+    /// IP-within-IP Encapsulation Protocol.
+    IPIP = 94, "IPIP";
+    /// Mobile Internetworking Control Pro., deprecated.
+    MICP = 95, "MICP";
+    /// Semaphore Communications Sec. Pro..
+    SCC_SP = 96, "SCC-SP";
+    /// Ethernet-within-IP Encapsulation.
+    ETHERIP = 97, "ETHERIP";
+    /// Encapsulation Header.
+    ENCAP = 98, "ENCAP";
+    GMTP = 100, "GMTP";
+    /// Ipsilon Flow Management Protocol.
+    IFMP = 101, "IFMP";
+    /// PNNI over IP.
+    PNNI = 102, "PNNI";
+    /// Protocol Independent Multicast.
+    PIM = 103, "PIM";
+    ARIS = 104, "ARIS";
+    SCPS = 105, "SCPS";
+    QNX = 106, "QNX";
+    /// Active Networks.
+    A_N = 107, "A/N";
+    /// IP Payload Compression Protocol.
+    IPCOMP = 108, "IPComp";
+    /// Sitara Networks Protocol.
+    SNP = 109, "SNP";
+    /// Compaq Peer Protocol.
+    COMPAQ_PEER = 110, "Compaq-Peer";
+    /// IPX in IP.
+    IPX_IN_IP = 111, "IPX-in-IP";
+    /// Virtual Router Redundancy Protocol.
+    VRRP = 112, "VRRP";
+    /// PGM Reliable Transport Protocol.
+    PGM = 113, "PGM";
+    /// Layer Two Tunneling Protocol.
+    L2TP = 115, "L2TP";
+    /// D-II Data Exchange.
+    DDX = 116, "DDX";
+    /// Interactive Agent Transfer Protocol.
+    IATP = 117, "IATP";
+    /// Schedule Transfer Protocol.
+    STP = 118, "STP";
+    /// SpectraLink Radio Protocol.
+    SRP = 119, "SRP";
+    UTI = 120, "UTI";
+    /// Simple Message Protocol.
+    SMP = 121, "SMP";
+    /// Simple Multicast Protocol, deprecated.
+    SM = 122, "SM";
+    /// Performance Transparency Protocol.
+    PTP = 123, "PTP";
+    /// IS-IS over IPv4.
+    ISIS_OVER_IPV4 = 124, "ISIS over IPv4";
+    FIRE = 125, "FIRE";
+    /// Combat Radio Transport Protocol.
+    CRTP = 126, "CRTP";
+    /// Combat Radio User Datagram.
+    CRUDP = 127, "CRUDP";
+    SSCOPMCE = 128, "SSCOPMCE";
+    IPLT = 129, "IPLT";
+    /// Secure Packet Shield.
+    SPS = 130, "SPS";
+    /// Private IP Encapsulation within IP.
+    PIPE = 131, "PIPE";
+    /// Stream Control Transmission Protocol.
+    SCTP = 132, "SCTP";
+    /// Fibre Channel.
+    FC = 133, "FC";
+    RSVP_E2E_IGNORE = 134, "RSVP-E2E-IGNORE";
+    /// Mobility Extension Header for IPv6.
+    MOBILITY_HEADER = 135, "Mobility Header";
+    /// Lightweight User Datagram Protocol.
+    UDPLITE = 136, "UDPLite";
+    /// MPLS in IP.
+    MPLS_IN_IP = 137, "MPLS-in-IP";
+    /// MANET Protocols.
+    MANET = 138, "manet";
+    /// Host Identity Protocol.
+    HIP = 139, "HIP";
+    /// Shim6 Protocol.
+    SHIM6 = 140, "Shim6";
+    /// Wrapped Encapsulating Security Payload.
+    WESP = 141, "WESP";
+    /// Robust Header Compression.
+    ROHC = 142, "ROHC";
+    /// Ethernet, used by Segment Routing over IPv6.
+    ETHERNET = 143, "Ethernet";
+    /// AGGFRAG encapsulation payload for ESP.
+    AGGFRAG = 144, "AGGFRAG";
+    /// Network Service Header.
+    NSH = 145, "NSH";
+    RESERVED = 255, "Reserved";
+});
 
+impl Protocol {
     /// Returns the numeric representation of this protocol.
     #[inline]
     pub fn value(&self) -> u8 {
         self.0
     }
 
+    /// Returns true if IANA has not assigned this protocol number, 146 to 252.
     pub fn is_unassigned(&self) -> bool {
-        self.0 >= 143 && self.0 <= 252
+        self.0 >= 146 && self.0 <= 252
     }
 
+    /// Returns true if this is one of the numbers reserved for experimentation and testing, 253
+    /// and 254.
     pub fn is_experimental(&self) -> bool {
         self.0 >= 253 && self.0 <= 254
     }
+
+    /// Returns true if this protocol number identifies an IPv6 extension header, as listed in
+    /// RFC 7045 and the IANA registry of extension headers. ESP is included even though the
+    /// headers after it are encrypted.
+    pub fn is_ipv6_extension_header(&self) -> bool {
+        matches!(
+            *self,
+            Protocol::HOPOPT |
+                Protocol::IPV6_ROUTE |
+                Protocol::IPV6_FRAG |
+                Protocol::ESP |
+                Protocol::AH |
+                Protocol::IPV6_OPTS |
+                Protocol::MOBILITY_HEADER |
+                Protocol::HIP |
+                Protocol::SHIM6
+        )
+    }
+
+    /// Returns true if this protocol carries another IP packet directly, like `IPV4`, `IPV6` and
+    /// `IPIP`. Tunnels with a header of their own, such as GRE, are not included.
+    pub fn is_ip_in_ip(&self) -> bool {
+        matches!(*self, Protocol::IPV4 | Protocol::IPV6 | Protocol::IPIP)
+    }
+}
+
+/// Writes the keyword and number of the protocol, like `UDP (17)`, or only the number for
+/// unnamed protocols. The alternate form, `{:#}`, leaves out the number of named protocols and
+/// is used in the packet summaries.
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) if f.alternate() => write!(f, "{}", name),
+            Some(name) => write!(f, "{} ({})", name, self.0),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+newtype_layout_value!(Protocol, u8, Display);
+
+
+/// Source of the IP pseudo-header that upper-layer protocols such as TCP and UDP include in their
+/// checksums. Implemented for `Ipv4Packet` and `Ipv6Packet`, and for `(source, destination)`
+/// address tuples for when the IP header is not available as a separate packet, e.g. while its
+/// payload is borrowed mutably.
+pub trait PseudoHeader {
+    /// The IP version of the pseudo-header, 4 or 6.
+    const VERSION: u8;
+
+    /// Returns the one's complement sum of the pseudo-header for an upper-layer packet of the
+    /// given protocol and length. Can be passed as the initial sum to `checksum::sum`.
+    fn pseudo_header_sum(&self, protocol: Protocol, length: u32) -> u32;
+}
+
+impl PseudoHeader for (Ipv4Addr, Ipv4Addr) {
+    const VERSION: u8 = 4;
+
+    fn pseudo_header_sum(&self, protocol: Protocol, length: u32) -> u32 {
+        let sum = checksum::sum(checksum::sum(0, &self.0.octets()), &self.1.octets());
+        checksum::sum(
+            sum,
+            &[0, protocol.value(), (length >> 8) as u8, length as u8],
+        )
+    }
+}
+
+impl PseudoHeader for (Ipv6Addr, Ipv6Addr) {
+    const VERSION: u8 = 6;
+
+    fn pseudo_header_sum(&self, protocol: Protocol, length: u32) -> u32 {
+        let sum = checksum::sum(checksum::sum(0, &self.0.octets()), &self.1.octets());
+        let sum = checksum::sum(sum, &length.to_be_bytes());
+        checksum::sum(sum, &[0, 0, 0, protocol.value()])
+    }
+}
+
+impl<B: AsRef<[u8]>> PseudoHeader for Ipv4Packet<B> {
+    const VERSION: u8 = 4;
+
+    fn pseudo_header_sum(&self, protocol: Protocol, length: u32) -> u32 {
+        (self.source(), self.destination()).pseudo_header_sum(protocol, length)
+    }
+}
+
+impl<B: AsRef<[u8]>> PseudoHeader for Ipv6Packet<B> {
+    const VERSION: u8 = 6;
+
+    fn pseudo_header_sum(&self, protocol: Protocol, length: u32) -> u32 {
+        (self.source(), self.destination()).pseudo_header_sum(protocol, length)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipv4_pseudo_header() {
+        let addrs = (Ipv4Addr::new(192, 168, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let expected = checksum::sum(
+            0,
+            &[192, 168, 0, 1, 10, 0, 0, 2, 0, 17, 0x01, 0x02],
+        );
+        assert_eq!(expected, addrs.pseudo_header_sum(Protocol::UDP, 0x0102));
+    }
+
+    #[test]
+    fn ipv6_pseudo_header() {
+        let source = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+        let destination = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 2);
+        let mut data = Vec::new();
+        data.extend_from_slice(&source.octets());
+        data.extend_from_slice(&destination.octets());
+        data.extend_from_slice(&[0, 1, 0, 2, 0, 0, 0, 6]);
+        assert_eq!(
+            checksum::sum(0, &data),
+            (source, destination).pseudo_header_sum(Protocol::TCP, 0x10002)
+        );
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Some("IPv6-ICMP"), Protocol::IPV6_ICMP.name());
+        assert_eq!(Some("3PC"), Protocol(34).name());
+        assert_eq!(None, Protocol(61).name());
+        assert_eq!(Some(Protocol::SCTP), Protocol::from_name("sctp"));
+        assert_eq!(Some(Protocol::ISIS_OVER_IPV4), Protocol::from_name("ISIS over IPv4"));
+        for value in 0..=255 {
+            let protocol = Protocol(value);
+            if let Some(name) = protocol.name() {
+                assert_eq!(Some(protocol), Protocol::from_name(name));
+            }
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("UDP (17)", Protocol::UDP.to_string());
+        assert_eq!("IPv6-ICMP (58)", Protocol::IPV6_ICMP.to_string());
+        assert_eq!("UDP", format!("{:#}", Protocol::UDP));
+        assert_eq!("200", Protocol(200).to_string());
+        assert_eq!("200", format!("{:#}", Protocol(200)));
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(Ok(Protocol::TCP), "TCP".parse());
+        assert_eq!(Ok(Protocol::IPV6_NONXT), "ipv6-nonxt".parse());
+        assert_eq!(Ok(Protocol(200)), "200".parse());
+        assert_eq!(Ok(Protocol::GRE), "0x2f".parse());
+        assert_eq!(Err(ProtocolParseError), "256".parse::<Protocol>());
+        assert_eq!(Err(ProtocolParseError), "TCP (6)".parse::<Protocol>());
+        assert_eq!(Err(ProtocolParseError), "".parse::<Protocol>());
+        assert_eq!("Unknown IP protocol", ProtocolParseError.to_string());
+    }
+
+    #[test]
+    fn classification() {
+        assert!(Protocol(146).is_unassigned() && !Protocol::NSH.is_unassigned());
+        assert!(Protocol(253).is_experimental() && !Protocol::RESERVED.is_experimental());
+        assert!(Protocol::MOBILITY_HEADER.is_ipv6_extension_header());
+        assert!(!Protocol::IPV6_ICMP.is_ipv6_extension_header());
+        assert!(Protocol::IPV6.is_ip_in_ip() && !Protocol::GRE.is_ip_in_ip());
+    }
 }
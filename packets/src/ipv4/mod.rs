@@ -0,0 +1,716 @@
+use checksum;
+use core::error::Error;
+use core::fmt;
+use core::net::Ipv4Addr;
+use ip::Protocol;
+use types::*;
+
+mod options;
+pub use self::options::*;
+
+packet_layout! {
+    packet: Ipv4Packet, MutIpv4Packet;
+    header_len: |data: &[u8]| (read_offset!(data, 0, u8) & 0x0f) as usize * 4;
+    packet_len: |data: &[u8]| read_offset!(data, 2, u16, from_be) as usize;
+    tests: ipv4_layout_tests;
+    fields: {
+        version / set_version: u4 = 0, 4, be;
+        header_length / set_header_length: u4 = 4, 4, be;
+        dscp / set_dscp: u6 = 8, 6, be;
+        ecn / set_ecn: u2 = 14, 2, be;
+        /// Returns the length of the packet in bytes, header included. The payload ends here,
+        /// anything after it in the buffer, such as Ethernet padding, is in `trailer()`.
+        total_length / set_total_length: u16 = 16, 16, be;
+        identification / set_identification: u16 = 32, 16, be;
+        flags / set_flags: Flags = 48, 3, be;
+        fragment_offset / set_fragment_offset: u13 = 51, 13, be;
+        ttl / set_ttl: u8 = 64, 8, be;
+        protocol / set_protocol: Protocol = 72, 8, be;
+        header_checksum / set_header_checksum: u16 = 80, 16, be;
+        source / set_source: Ipv4Addr = 96, 32, be;
+        destination / set_destination: Ipv4Addr = 128, 32, be;
+    }
+}
+
+getters!(Ipv4Packet
+    pub fn dont_fragment(&self) -> bool {
+        self.flags().contains(Flags::DF)
+    }
+
+    pub fn more_fragments(&self) -> bool {
+        self.flags().contains(Flags::MF)
+    }
+
+    /// Returns the raw option bytes of the header. This is everything between the fixed 20 byte
+    /// header and the end of the header as given by `header_length()`.
+    pub fn options_data(&self) -> &[u8] {
+        &self.data()[Ipv4Packet::MIN_LEN..self.header_len()]
+    }
+
+    /// Returns an iterator over the options in the header.
+    pub fn options(&self) -> Ipv4Options<'_> {
+        Ipv4Options::new(self.options_data())
+    }
+
+    /// Computes the checksum of the header, as it should be written to the header checksum
+    /// field. The current value of the header checksum field is ignored.
+    pub fn calculate_checksum(&self) -> u16 {
+        let header = self.header();
+        let sum = checksum::sum(checksum::sum(0, &header[..10]), &header[12..]);
+        checksum::finish(sum)
+    }
+
+    /// Returns true if the header checksum field contains the correct checksum for the header.
+    pub fn verify_checksum(&self) -> bool {
+        checksum::checksum(self.header()) == 0
+    }
+);
+
+impl<B: AsRef<[u8]>> Ipv4Packet<B> {
+    /// Checks that the version is 4, that the header length field covers at least the fixed
+    /// header and fits in the packet, and that the total length field covers the header and fits
+    /// in the packet. The header checksum is not verified, see `verify_checksum()`.
+    pub fn validate(&self) -> Result<(), Ipv4Error> {
+        if self.version() != 4 {
+            return Err(Ipv4Error::Version);
+        }
+        let data_len = self.data().len();
+        let header_len = usize::from(self.header_length()) * 4;
+        if header_len < Ipv4Packet::MIN_LEN || header_len > data_len {
+            return Err(Ipv4Error::HeaderLength);
+        }
+        let total_length = usize::from(self.total_length());
+        if total_length < header_len || total_length > data_len {
+            return Err(Ipv4Error::TotalLength);
+        }
+        Ok(())
+    }
+}
+
+new_checked!(Ipv4Packet, Ipv4Error);
+
+/// Writes a one line summary of the header, like `IPv4 192.168.0.1 -> 10.0.0.1 ttl=64 proto=UDP
+/// len=84 DF`. Fragments also get their fragment offset in bytes written, as `offset=1480`.
+impl<B: AsRef<[u8]>> fmt::Display for Ipv4Packet<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "IPv4 {} -> {} ttl={} proto={:#} len={}",
+            self.source(),
+            self.destination(),
+            self.ttl(),
+            self.protocol(),
+            self.total_length()
+        )?;
+        if self.dont_fragment() {
+            write!(f, " DF")?;
+        }
+        if self.more_fragments() {
+            write!(f, " MF")?;
+        }
+        if self.fragment_offset() != 0 {
+            write!(f, " offset={}", u32::from(self.fragment_offset()) * 8)?;
+        }
+        Ok(())
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> Ipv4Packet<B> {
+    /// Writes the given raw option bytes directly after the fixed part of the header and updates
+    /// the header length field to include them. The options are padded with zeros (End of Option
+    /// List) to a multiple of four bytes. Since the payload starts after the options, options
+    /// should be set before the payload is written.
+    pub fn set_options(&mut self, options: &[u8]) -> Result<(), SetOptionsError> {
+        if options.len() > MAX_OPTIONS_LEN {
+            return Err(SetOptionsError::TooLong);
+        }
+        let padded_len = (options.len() + 3) & !3;
+        let header_len = Ipv4Packet::MIN_LEN + padded_len;
+        if self.data().len() < header_len {
+            return Err(SetOptionsError::BufferTooShort);
+        }
+        let options_end = Ipv4Packet::MIN_LEN + options.len();
+        self.data_mut()[Ipv4Packet::MIN_LEN..options_end].copy_from_slice(options);
+        for byte in &mut self.data_mut()[options_end..header_len] {
+            *byte = 0;
+        }
+        self.set_header_length((header_len / 4) as u4);
+        Ok(())
+    }
+
+    /// Computes the header checksum and writes it to the header checksum field. Should be called
+    /// after all other header fields have been set.
+    pub fn fill_checksum(&mut self) {
+        let checksum = self.calculate_checksum();
+        self.set_header_checksum(checksum);
+    }
+
+    /// Sets the TTL and incrementally updates the header checksum according to [RFC 1624],
+    /// without summing the entire header again. The header checksum must be correct before the
+    /// call for it to be correct after. The same goes for all the other `*_incremental` setters.
+    ///
+    /// [RFC 1624]: https://tools.ietf.org/html/rfc1624
+    pub fn set_ttl_incremental(&mut self, ttl: u8) {
+        self.update_checksum(8, 2, |packet| packet.set_ttl(ttl));
+    }
+
+    /// Decrements the TTL by one and incrementally updates the header checksum, the way a router
+    /// does when forwarding a packet. Returns the new TTL, or `None` without modifying the packet
+    /// if the TTL is already zero.
+    pub fn decrement_ttl(&mut self) -> Option<u8> {
+        let ttl = self.ttl().checked_sub(1)?;
+        self.set_ttl_incremental(ttl);
+        Some(ttl)
+    }
+
+    pub fn set_dscp_incremental(&mut self, dscp: u6) {
+        self.update_checksum(0, 2, |packet| packet.set_dscp(dscp));
+    }
+
+    pub fn set_ecn_incremental(&mut self, ecn: u2) {
+        self.update_checksum(0, 2, |packet| packet.set_ecn(ecn));
+    }
+
+    pub fn set_total_length_incremental(&mut self, total_length: u16) {
+        self.update_checksum(2, 2, |packet| packet.set_total_length(total_length));
+    }
+
+    pub fn set_identification_incremental(&mut self, identification: u16) {
+        self.update_checksum(4, 2, |packet| packet.set_identification(identification));
+    }
+
+    pub fn set_flags_incremental(&mut self, flags: Flags) {
+        self.update_checksum(6, 2, |packet| packet.set_flags(flags));
+    }
+
+    pub fn set_fragment_offset_incremental(&mut self, fragment_offset: u13) {
+        self.update_checksum(6, 2, |packet| packet.set_fragment_offset(fragment_offset));
+    }
+
+    pub fn set_protocol_incremental(&mut self, protocol: Protocol) {
+        self.update_checksum(8, 2, |packet| packet.set_protocol(protocol));
+    }
+
+    pub fn set_source_incremental(&mut self, source: Ipv4Addr) {
+        self.update_checksum(12, 4, |packet| packet.set_source(source));
+    }
+
+    pub fn set_destination_incremental(&mut self, destination: Ipv4Addr) {
+        self.update_checksum(16, 4, |packet| packet.set_destination(destination));
+    }
+
+    /// Runs `set`, which may only modify the `len` header bytes starting at the even `offset`,
+    /// and updates the header checksum with the difference.
+    fn update_checksum<F>(&mut self, offset: usize, len: usize, set: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let mut old = [0; 4];
+        old[..len].copy_from_slice(&self.data()[offset..offset + len]);
+        set(self);
+        let checksum = checksum::update_slice(
+            self.header_checksum(),
+            &old[..len],
+            &self.data()[offset..offset + len],
+        );
+        self.set_header_checksum(checksum);
+    }
+}
+
+bitflags! {
+    /// Bitmasks for the three bit flags field in IPv4
+    pub struct Flags: u3 {
+        /// A bitmask with a one in the "Reserved" position.
+        const RESERVED = 0b100;
+        /// A bitmask with a one in the "Don't fragment" position.
+        const DF = 0b010;
+        /// A bitmask with a one in the "More fragments" position.
+        const MF = 0b001;
+    }
+}
+
+impl ::layout::LayoutValue for Flags {
+    #[inline(always)]
+    fn from_raw(raw: u64) -> Self {
+        Flags::from_bits_truncate(raw as u3)
+    }
+
+    #[inline(always)]
+    fn into_raw(self) -> u64 {
+        u64::from(self.bits())
+    }
+
+    fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+
+/// An owned representation of an IPv4 header. Options are not part of the representation, they
+/// are skipped when parsing and never emitted.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Ipv4Repr {
+    pub dscp: u6,
+    pub ecn: u2,
+    pub identification: u16,
+    pub flags: Flags,
+    pub fragment_offset: u13,
+    pub ttl: u8,
+    pub protocol: Protocol,
+    pub source: Ipv4Addr,
+    pub destination: Ipv4Addr,
+    /// The length of the payload, the total length of the packet minus the header length.
+    pub payload_len: usize,
+}
+
+impl Ipv4Repr {
+    /// Reads the header of `packet` into an `Ipv4Repr`. Fails if the version is not 4, the header
+    /// or total length fields do not fit the packet or the header checksum is not correct.
+    pub fn parse<B: AsRef<[u8]>>(packet: &Ipv4Packet<B>) -> Result<Self, Ipv4Error> {
+        if packet.data().len() < Ipv4Packet::MIN_LEN {
+            return Err(Ipv4Error::Truncated);
+        }
+        packet.validate()?;
+        if !packet.verify_checksum() {
+            return Err(Ipv4Error::Checksum);
+        }
+        Ok(Ipv4Repr {
+            dscp: packet.dscp(),
+            ecn: packet.ecn(),
+            identification: packet.identification(),
+            flags: packet.flags(),
+            fragment_offset: packet.fragment_offset(),
+            ttl: packet.ttl(),
+            protocol: packet.protocol(),
+            source: packet.source(),
+            destination: packet.destination(),
+            payload_len: usize::from(packet.total_length()) - packet.header_len(),
+        })
+    }
+
+    /// Returns the number of bytes needed to emit this header. The payload is not included.
+    pub fn buffer_len(&self) -> usize {
+        Ipv4Packet::MIN_LEN
+    }
+
+    /// Writes this header without options to `packet` and fills in the header checksum. The
+    /// payload is left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the header and `payload_len` together do not fit in the total length field.
+    pub fn emit<B: AsRef<[u8]> + AsMut<[u8]>>(&self, packet: &mut Ipv4Packet<B>) {
+        let total_length = self.buffer_len() + self.payload_len;
+        assert!(total_length <= usize::from(u16::MAX), "IPv4 payload too long");
+        packet.set_version(4);
+        packet.set_header_length((self.buffer_len() / 4) as u4);
+        packet.set_dscp(self.dscp);
+        packet.set_ecn(self.ecn);
+        packet.set_total_length(total_length as u16);
+        packet.set_identification(self.identification);
+        packet.set_flags(self.flags);
+        packet.set_fragment_offset(self.fragment_offset);
+        packet.set_ttl(self.ttl);
+        packet.set_protocol(self.protocol);
+        packet.set_source(self.source);
+        packet.set_destination(self.destination);
+        packet.fill_checksum();
+    }
+}
+
+/// Error returned when an IPv4 packet is not valid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Ipv4Error {
+    /// The packet is too short to hold an IPv4 header.
+    Truncated,
+    /// The version field is not 4.
+    Version,
+    /// The header length field is less than five words or longer than the packet.
+    HeaderLength,
+    /// The total length field is shorter than the header or longer than the packet.
+    TotalLength,
+    /// The header checksum is not correct.
+    Checksum,
+}
+
+impl fmt::Display for Ipv4Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Ipv4Error::Truncated => write!(f, "IPv4 packet truncated"),
+            Ipv4Error::Version => write!(f, "IPv4 version field is not 4"),
+            Ipv4Error::HeaderLength => write!(f, "Invalid IPv4 header length"),
+            Ipv4Error::TotalLength => write!(f, "Invalid IPv4 total length"),
+            Ipv4Error::Checksum => write!(f, "Invalid IPv4 header checksum"),
+        }
+    }
+}
+
+impl Error for Ipv4Error {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_length() {
+        assert_eq!(Ipv4Packet::MIN_LEN, 20);
+    }
+
+    #[test]
+    fn too_short_slice() {
+        assert!(Ipv4Packet::new(&[0; 19]).is_none());
+    }
+
+    #[test]
+    fn exactly_20_bytes_slice() {
+        let packet = Ipv4Packet::new(&[1; 20]).expect("Ipv4Packet to accept 20 bytes");
+        assert_eq!(packet.data(), &[1; 20]);
+        assert_eq!(packet.header(), &[1; 20]);
+        assert!(packet.payload().is_empty());
+    }
+
+    #[test]
+    fn payload_after_options() {
+        let mut data = [0; 30];
+        data[0] = 0x46;
+        data[3] = 30;
+        data[20..24].copy_from_slice(&[148, 4, 0, 0]);
+        let packet = Ipv4Packet::new(&data[..]).unwrap();
+        assert_eq!(24, packet.header_len());
+        assert_eq!(&data[..24], packet.header());
+        assert_eq!(&data[24..], packet.payload());
+        assert_eq!(&[148, 4, 0, 0], packet.options_data());
+        assert_eq!(vec![Ipv4Option::RouterAlert(0)], packet.options().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn header_length_larger_than_buffer() {
+        let mut data = [0; 22];
+        data[0] = 0x4f;
+        let packet = Ipv4Packet::new(&data[..]).unwrap();
+        assert_eq!(22, packet.header_len());
+        assert_eq!(&[0, 0], packet.options_data());
+        assert!(packet.payload().is_empty());
+    }
+
+    #[test]
+    fn header_length_too_small() {
+        let mut data = [0; 21];
+        data[0] = 0x42;
+        data[3] = 21;
+        let packet = Ipv4Packet::new(&data[..]).unwrap();
+        assert_eq!(20, packet.header_len());
+        assert!(packet.options_data().is_empty());
+        assert_eq!(&[0], packet.payload());
+    }
+
+    #[test]
+    fn set_options() {
+        let mut data = [0xff; 30];
+        {
+            let mut packet = MutIpv4Packet::new(&mut data[..]).unwrap();
+            packet.set_options(&[7, 7, 4, 0, 0, 0, 0]).unwrap();
+            assert_eq!(28, packet.header_len());
+            assert_eq!(2, packet.payload().len());
+        }
+        assert_eq!(0xf7, data[0]);
+        assert_eq!([7, 7, 4, 0, 0, 0, 0, 0], data[20..28]);
+        assert_eq!([0xff, 0xff], data[28..]);
+    }
+
+    #[test]
+    fn set_no_options() {
+        let mut data = [0; 24];
+        data[0] = 0x46;
+        let mut packet = MutIpv4Packet::new(&mut data[..]).unwrap();
+        packet.set_options(&[]).unwrap();
+        assert_eq!(5, packet.as_immutable().header_length());
+    }
+
+    #[test]
+    fn set_options_errors() {
+        let mut data = [0; 64];
+        let mut packet = MutIpv4Packet::new(&mut data[..23]).unwrap();
+        assert_eq!(Err(SetOptionsError::TooLong), packet.set_options(&[1; 41]));
+        assert_eq!(Err(SetOptionsError::BufferTooShort), packet.set_options(&[1; 3]));
+    }
+
+    static HEADER: [u8; 20] = [
+        0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8, 0x00,
+        0x01, 0xc0, 0xa8, 0x00, 0xc7,
+    ];
+
+    #[test]
+    fn verify_checksum() {
+        let mut data = HEADER;
+        assert!(Ipv4Packet::new(&data).unwrap().verify_checksum());
+        data[11] = 0x62;
+        assert!(!Ipv4Packet::new(&data).unwrap().verify_checksum());
+    }
+
+    #[test]
+    fn calculate_checksum_ignores_checksum_field() {
+        let mut data = HEADER;
+        data[10] = 0x12;
+        assert_eq!(0xb861, Ipv4Packet::new(&data).unwrap().calculate_checksum());
+    }
+
+    #[test]
+    fn fill_checksum() {
+        let mut data = HEADER;
+        data[10] = 0;
+        data[11] = 0;
+        MutIpv4Packet::new(&mut data).unwrap().fill_checksum();
+        assert_eq!(HEADER, data);
+    }
+
+    #[test]
+    fn checksum_covers_options_not_payload() {
+        let mut data = [0; 32];
+        {
+            let mut packet = MutIpv4Packet::new(&mut data).unwrap();
+            packet.set_version(4);
+            packet.set_options(&[148, 4, 0, 0]).unwrap();
+            packet.set_total_length(32);
+            packet.payload_mut()[0] = 0xff;
+            packet.fill_checksum();
+            assert!(packet.as_immutable().verify_checksum());
+        }
+        data[24] = 0x12;
+        assert!(Ipv4Packet::new(&data).unwrap().verify_checksum());
+        data[22] = 0x12;
+        assert!(!Ipv4Packet::new(&data).unwrap().verify_checksum());
+    }
+
+    #[test]
+    fn incremental_setters() {
+        let mut data = HEADER;
+        let mut packet = MutIpv4Packet::new(&mut data).unwrap();
+        packet.set_ttl_incremental(12);
+        packet.set_dscp_incremental(0x2e);
+        packet.set_ecn_incremental(1);
+        packet.set_total_length_incremental(1500);
+        packet.set_identification_incremental(0xabcd);
+        packet.set_flags_incremental(Flags::MF);
+        packet.set_fragment_offset_incremental(0x123);
+        packet.set_protocol_incremental(Protocol::TCP);
+        packet.set_source_incremental(Ipv4Addr::new(10, 0, 0, 1));
+        packet.set_destination_incremental(Ipv4Addr::new(172, 16, 254, 3));
+        let checksum = packet.as_immutable().header_checksum();
+        assert!(packet.as_immutable().verify_checksum());
+        packet.fill_checksum();
+        assert_eq!(checksum, packet.as_immutable().header_checksum());
+    }
+
+    #[test]
+    fn decrement_ttl() {
+        let mut data = HEADER;
+        data[8] = 1;
+        MutIpv4Packet::new(&mut data).unwrap().fill_checksum();
+        let mut packet = MutIpv4Packet::new(&mut data).unwrap();
+        assert_eq!(Some(0), packet.decrement_ttl());
+        assert!(packet.as_immutable().verify_checksum());
+        assert_eq!(None, packet.decrement_ttl());
+        assert_eq!(0, packet.as_immutable().ttl());
+    }
+
+    #[test]
+    fn correct_payload() {
+        let mut data = vec![2; 19];
+        data.push(3);
+        data.push(4);
+        let packet = Ipv4Packet::new(&data[..]).expect("Ipv4Packet to accept 21 bytes");
+        assert_eq!(packet.data(), &data[..]);
+        assert_eq!(packet.header(), &data[..20]);
+        assert_eq!(packet.payload(), &[4]);
+    }
+
+    macro_rules! ipv4_setget_test {
+        ($name:ident, $set_name:ident, $value:expr, $offset:expr, $expected:expr) => {
+            setget_test!(MutIpv4Packet, $name, $set_name, $value, $offset, $expected);
+        }
+    }
+
+    ipv4_setget_test!(version, set_version, 0xf, 0, [0xf0]);
+    ipv4_setget_test!(header_length, set_header_length, 0xf, 0, [0x0f]);
+    ipv4_setget_test!(dscp, set_dscp, 0x3f, 1, [0xfc]);
+    ipv4_setget_test!(ecn, set_ecn, 0x3, 1, [0x3]);
+    ipv4_setget_test!(total_length, set_total_length, 0xffbf, 2, [0xff, 0xbf]);
+    ipv4_setget_test!(identification, set_identification, 0xffaf, 4, [0xff, 0xaf]);
+    ipv4_setget_test!(flags, set_flags, Flags::all(), 6, [0xe0]);
+    ipv4_setget_test!(
+        fragment_offset,
+        set_fragment_offset,
+        0x1faf,
+        6,
+        [0x1f, 0xaf]
+    );
+    ipv4_setget_test!(ttl, set_ttl, 0xff, 8, [0xff]);
+    ipv4_setget_test!(protocol, set_protocol, Protocol(0xff), 9, [0xff]);
+    ipv4_setget_test!(
+        header_checksum,
+        set_header_checksum,
+        0xfeff,
+        10,
+        [0xfe, 0xff]
+    );
+    ipv4_setget_test!(
+        source,
+        set_source,
+        Ipv4Addr::new(192, 168, 15, 1),
+        12,
+        [192, 168, 15, 1]
+    );
+    ipv4_setget_test!(
+        destination,
+        set_destination,
+        Ipv4Addr::new(168, 254, 99, 88),
+        16,
+        [168, 254, 99, 88]
+    );
+
+    #[test]
+    fn getters_alternating_bits() {
+        let backing_data = [0b1010_1010; 20];
+        let testee = Ipv4Packet::new(&backing_data).unwrap();
+        assert_eq!(0b1010, testee.version());
+        assert_eq!(0b1010, testee.header_length());
+        assert_eq!(0b101010, testee.dscp());
+        assert_eq!(0b10, testee.ecn());
+        assert_eq!(0b1010_1010_1010_1010, testee.total_length());
+        assert_eq!(0b1010_1010_1010_1010, testee.identification());
+        assert_eq!(Flags::RESERVED | Flags::MF, testee.flags());
+        assert!(!testee.dont_fragment());
+        assert!(testee.more_fragments());
+        assert_eq!(0b0_1010_1010_1010, testee.fragment_offset());
+    }
+
+    fn repr() -> Ipv4Repr {
+        Ipv4Repr {
+            dscp: 10,
+            ecn: 1,
+            identification: 0xabcd,
+            flags: Flags::DF,
+            fragment_offset: 0,
+            ttl: 64,
+            protocol: Protocol::UDP,
+            source: Ipv4Addr::new(192, 168, 0, 1),
+            destination: Ipv4Addr::new(192, 168, 0, 199),
+            payload_len: 4,
+        }
+    }
+
+    #[test]
+    fn repr_round_trip() {
+        let mut backing_data = [0; 24];
+        let mut packet = Ipv4Packet::new(&mut backing_data[..]).unwrap();
+        repr().emit(&mut packet);
+        assert_eq!(24, packet.total_length());
+        assert!(packet.verify_checksum());
+        assert_eq!(Ok(repr()), Ipv4Repr::parse(&packet));
+    }
+
+    #[test]
+    fn repr_skips_options() {
+        let mut backing_data = [0; 28];
+        let mut packet = Ipv4Packet::new(&mut backing_data[..]).unwrap();
+        repr().emit(&mut packet);
+        packet.set_options(&[1, 1, 1, 0]).unwrap();
+        packet.set_total_length(28);
+        packet.fill_checksum();
+        assert_eq!(Ok(repr()), Ipv4Repr::parse(&packet));
+    }
+
+    #[test]
+    fn repr_invalid() {
+        let mut backing_data = [0; 24];
+        let mut packet = Ipv4Packet::new(&mut backing_data[..]).unwrap();
+        repr().emit(&mut packet);
+        packet.set_ttl(1);
+        assert_eq!(Err(Ipv4Error::Checksum), Ipv4Repr::parse(&packet));
+        packet.set_total_length(25);
+        assert_eq!(Err(Ipv4Error::TotalLength), Ipv4Repr::parse(&packet));
+        packet.set_header_length(4);
+        assert_eq!(Err(Ipv4Error::HeaderLength), Ipv4Repr::parse(&packet));
+        packet.set_version(6);
+        assert_eq!(Err(Ipv4Error::Version), Ipv4Repr::parse(&packet));
+    }
+
+    #[test]
+    fn validate() {
+        let mut backing_data = [0; 24];
+        let mut packet = Ipv4Packet::new(&mut backing_data[..]).unwrap();
+        repr().emit(&mut packet);
+        packet.set_ttl(1);
+        assert_eq!(Ok(()), packet.validate());
+        packet.set_total_length(25);
+        assert_eq!(Err(Ipv4Error::TotalLength), packet.validate());
+        packet.set_total_length(24);
+        packet.set_header_length(6);
+        assert_eq!(Ok(()), packet.validate());
+        packet.set_header_length(7);
+        assert_eq!(Err(Ipv4Error::HeaderLength), packet.validate());
+        assert_eq!(Err(Ipv4Error::Truncated), Ipv4Packet::new_checked(&backing_data[..19]));
+    }
+
+    #[test]
+    fn padded_packet() {
+        let mut data = [0xaa; 46];
+        data[0] = 0x45;
+        data[2..4].copy_from_slice(&[0, 28]);
+        let packet = Ipv4Packet::new(&data[..]).unwrap();
+        assert_eq!(28, packet.packet_len());
+        assert_eq!(&data[20..28], packet.payload());
+        assert_eq!(&data[28..], packet.trailer());
+    }
+
+    #[test]
+    fn total_length_larger_than_buffer() {
+        let mut data = [0; 30];
+        data[0] = 0x45;
+        data[2..4].copy_from_slice(&[0x05, 0xdc]);
+        let packet = Ipv4Packet::new(&data[..]).unwrap();
+        assert_eq!(10, packet.payload().len());
+        assert!(packet.trailer().is_empty());
+    }
+
+    #[test]
+    fn display() {
+        let mut backing_data = [0; 84];
+        let mut packet = MutIpv4Packet::new(&mut backing_data[..]).unwrap();
+        packet.set_version(4);
+        packet.set_header_length(5);
+        packet.set_total_length(84);
+        packet.set_flags(Flags::DF);
+        packet.set_ttl(64);
+        packet.set_protocol(Protocol::UDP);
+        packet.set_source(Ipv4Addr::new(192, 168, 0, 1));
+        packet.set_destination(Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(
+            "IPv4 192.168.0.1 -> 10.0.0.1 ttl=64 proto=UDP len=84 DF",
+            packet.to_string()
+        );
+        packet.set_flags(Flags::MF);
+        packet.set_fragment_offset(185);
+        assert_eq!(
+            "IPv4 192.168.0.1 -> 10.0.0.1 ttl=64 proto=UDP len=84 MF offset=1480",
+            packet.to_string()
+        );
+    }
+
+    #[test]
+    fn alternate_debug() {
+        let mut backing_data = [0; 21];
+        backing_data[0] = 0x45;
+        backing_data[2..4].copy_from_slice(&[0, 21]);
+        backing_data[8] = 64;
+        let packet = Ipv4Packet::new(&backing_data[..]).unwrap();
+        assert!(format!("{:?}", packet).starts_with("Ipv4Packet([69, 0, 0, 21"));
+        let debug = format!("{:#?}", packet);
+        assert!(debug.starts_with("Ipv4Packet {\n    version: 4,\n    header_length: 5,\n"));
+        assert!(debug.contains("    ttl: 64,\n"));
+        assert!(debug.contains("    flags: (empty),\n"));
+        assert!(debug.ends_with("    payload: [\n        0,\n    ],\n}"));
+    }
+}
@@ -0,0 +1,498 @@
+use core::error::Error;
+use core::fmt;
+use core::net::Ipv4Addr;
+use core::slice::Chunks;
+use types::*;
+
+/// The maximum number of option bytes that fit in an IPv4 header.
+pub const MAX_OPTIONS_LEN: usize = 40;
+
+/// Represents the type byte of an IPv4 option. Contains the copied flag, the option class and the
+/// option number. See [RFC 791] and [IANA's list] for the defined values.
+///
+/// [RFC 791]: https://tools.ietf.org/html/rfc791#section-3.1
+/// [IANA's list]: https://www.iana.org/assignments/ip-parameters/ip-parameters.xhtml
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OptionType(pub u8);
+
+impl OptionType {
+    pub const END_OF_LIST: OptionType = OptionType(0);
+    pub const NOP: OptionType = OptionType(1);
+    pub const RECORD_ROUTE: OptionType = OptionType(7);
+    pub const TIMESTAMP: OptionType = OptionType(68);
+    pub const SECURITY: OptionType = OptionType(130);
+    pub const LOOSE_SOURCE_ROUTE: OptionType = OptionType(131);
+    pub const STRICT_SOURCE_ROUTE: OptionType = OptionType(137);
+    pub const ROUTER_ALERT: OptionType = OptionType(148);
+
+    /// Returns the numeric representation of this option type.
+    #[inline]
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns true if this option should be copied into all fragments on fragmentation.
+    pub fn copied(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+
+    pub fn class(&self) -> u2 {
+        (self.0 >> 5) & 0x03
+    }
+
+    pub fn number(&self) -> u5 {
+        self.0 & 0x1f
+    }
+}
+
+/// A single IPv4 header option, as yielded by the [`Ipv4Options`] iterator.
+///
+/// [`Ipv4Options`]: struct.Ipv4Options.html
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Ipv4Option<'a> {
+    /// Marks the end of the option list. Nothing after this option is parsed.
+    EndOfList,
+    Nop,
+    RecordRoute(RouteOption<'a>),
+    LooseSourceRoute(RouteOption<'a>),
+    StrictSourceRoute(RouteOption<'a>),
+    Timestamp(TimestampOption<'a>),
+    Security(SecurityOption<'a>),
+    /// Router Alert option as defined in [RFC 2113]. Contains the 16 bit value of the option.
+    ///
+    /// [RFC 2113]: https://tools.ietf.org/html/rfc2113
+    RouterAlert(u16),
+    /// An option of a type this crate does not know how to parse. Contains the option type and
+    /// the option data, not including the type and length bytes.
+    Unknown(OptionType, &'a [u8]),
+    /// The option data could not be parsed, typically because of an invalid length byte.
+    /// Contains the remaining option bytes. Iteration ends after this item.
+    Malformed(&'a [u8]),
+}
+
+impl<'a> Ipv4Option<'a> {
+    /// Returns the type of this option, or `None` for a malformed option.
+    pub fn option_type(&self) -> Option<OptionType> {
+        match *self {
+            Ipv4Option::EndOfList => Some(OptionType::END_OF_LIST),
+            Ipv4Option::Nop => Some(OptionType::NOP),
+            Ipv4Option::RecordRoute(_) => Some(OptionType::RECORD_ROUTE),
+            Ipv4Option::LooseSourceRoute(_) => Some(OptionType::LOOSE_SOURCE_ROUTE),
+            Ipv4Option::StrictSourceRoute(_) => Some(OptionType::STRICT_SOURCE_ROUTE),
+            Ipv4Option::Timestamp(_) => Some(OptionType::TIMESTAMP),
+            Ipv4Option::Security(_) => Some(OptionType::SECURITY),
+            Ipv4Option::RouterAlert(_) => Some(OptionType::ROUTER_ALERT),
+            Ipv4Option::Unknown(option_type, _) => Some(option_type),
+            Ipv4Option::Malformed(_) => None,
+        }
+    }
+}
+
+/// Iterator over the options in an IPv4 header. Created with `Ipv4Packet::options()`.
+#[derive(Debug, Clone)]
+pub struct Ipv4Options<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Ipv4Options<'a> {
+    /// Creates an iterator over the options encoded in `data`.
+    pub fn new(data: &'a [u8]) -> Ipv4Options<'a> {
+        Ipv4Options { data }
+    }
+
+    fn parse(option_type: OptionType, option: &'a [u8]) -> Option<Ipv4Option<'a>> {
+        let length = option.len();
+        match option_type {
+            OptionType::RECORD_ROUTE if length >= 3 => {
+                Some(Ipv4Option::RecordRoute(RouteOption(option)))
+            }
+            OptionType::LOOSE_SOURCE_ROUTE if length >= 3 => {
+                Some(Ipv4Option::LooseSourceRoute(RouteOption(option)))
+            }
+            OptionType::STRICT_SOURCE_ROUTE if length >= 3 => {
+                Some(Ipv4Option::StrictSourceRoute(RouteOption(option)))
+            }
+            OptionType::TIMESTAMP if length >= 4 => {
+                Some(Ipv4Option::Timestamp(TimestampOption(option)))
+            }
+            OptionType::SECURITY if length == 11 => {
+                Some(Ipv4Option::Security(SecurityOption(option)))
+            }
+            OptionType::ROUTER_ALERT if length == 4 => {
+                let value = read_offset!(option, 2, u16, from_be);
+                Some(Ipv4Option::RouterAlert(value))
+            }
+            OptionType::RECORD_ROUTE |
+            OptionType::LOOSE_SOURCE_ROUTE |
+            OptionType::STRICT_SOURCE_ROUTE |
+            OptionType::TIMESTAMP |
+            OptionType::SECURITY |
+            OptionType::ROUTER_ALERT => None,
+            _ => Some(Ipv4Option::Unknown(option_type, &option[2..])),
+        }
+    }
+}
+
+impl<'a> Iterator for Ipv4Options<'a> {
+    type Item = Ipv4Option<'a>;
+
+    fn next(&mut self) -> Option<Ipv4Option<'a>> {
+        let data = self.data;
+        let option_type = OptionType(*data.first()?);
+        match option_type {
+            OptionType::END_OF_LIST => {
+                self.data = &[];
+                Some(Ipv4Option::EndOfList)
+            }
+            OptionType::NOP => {
+                self.data = &data[1..];
+                Some(Ipv4Option::Nop)
+            }
+            _ => {
+                let length = data.get(1).map(|&length| length as usize).unwrap_or(0);
+                let option = if length >= 2 && length <= data.len() {
+                    Self::parse(option_type, &data[..length])
+                } else {
+                    None
+                };
+                match option {
+                    Some(option) => {
+                        self.data = &data[length..];
+                        Some(option)
+                    }
+                    None => {
+                        self.data = &[];
+                        Some(Ipv4Option::Malformed(data))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// View of the Record Route, Loose Source Route and Strict Source Route options. They all share
+/// the same layout, a pointer followed by a list of IPv4 addresses.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RouteOption<'a>(&'a [u8]);
+
+impl<'a> RouteOption<'a> {
+    /// Returns the raw bytes of this option, including the type and length bytes.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn length(&self) -> u8 {
+        read_offset!(self.0, 1, u8)
+    }
+
+    /// Returns the pointer field. It is the one based offset, from the start of the option, of
+    /// the next address slot to use.
+    pub fn pointer(&self) -> u8 {
+        read_offset!(self.0, 2, u8)
+    }
+
+    /// Returns an iterator over all address slots in the option, used or not.
+    pub fn addresses(&self) -> Addresses<'a> {
+        Addresses(self.0[3..].chunks(4))
+    }
+
+    /// Returns an iterator over the addresses before the pointer. For Record Route these are the
+    /// addresses recorded so far.
+    pub fn recorded_addresses(&self) -> Addresses<'a> {
+        let pointer = (self.pointer() as usize).saturating_sub(1);
+        let end = ::core::cmp::max(3, ::core::cmp::min(pointer, self.0.len()));
+        Addresses(self.0[3..end].chunks(4))
+    }
+}
+
+/// Iterator over IPv4 addresses stored back to back in an option.
+#[derive(Debug, Clone)]
+pub struct Addresses<'a>(Chunks<'a, u8>);
+
+impl<'a> Iterator for Addresses<'a> {
+    type Item = Ipv4Addr;
+
+    fn next(&mut self) -> Option<Ipv4Addr> {
+        self.0
+            .next()
+            .filter(|chunk| chunk.len() == 4)
+            .map(|chunk| Ipv4Addr::new(chunk[0], chunk[1], chunk[2], chunk[3]))
+    }
+}
+
+/// View of the Internet Timestamp option.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TimestampOption<'a>(&'a [u8]);
+
+impl<'a> TimestampOption<'a> {
+    /// Returns the raw bytes of this option, including the type and length bytes.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn length(&self) -> u8 {
+        read_offset!(self.0, 1, u8)
+    }
+
+    pub fn pointer(&self) -> u8 {
+        read_offset!(self.0, 2, u8)
+    }
+
+    /// Returns the number of hosts that could not register a timestamp due to lack of space.
+    pub fn overflow(&self) -> u4 {
+        read_offset!(self.0, 3, u8) >> 4
+    }
+
+    pub fn flag(&self) -> TimestampFlag {
+        TimestampFlag(read_offset!(self.0, 3, u8) & 0x0f)
+    }
+
+    /// Returns an iterator over the timestamp entries in the option. Entries carry an address
+    /// if the flag says addresses are present.
+    pub fn entries(&self) -> TimestampEntries<'a> {
+        let with_address = self.flag() != TimestampFlag::TIMESTAMPS_ONLY;
+        let entry_len = if with_address { 8 } else { 4 };
+        TimestampEntries {
+            chunks: self.0[4..].chunks(entry_len),
+            with_address,
+        }
+    }
+}
+
+/// Represents the four bit flag field of the Internet Timestamp option.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TimestampFlag(pub u4);
+
+impl TimestampFlag {
+    pub const TIMESTAMPS_ONLY: TimestampFlag = TimestampFlag(0);
+    pub const WITH_ADDRESSES: TimestampFlag = TimestampFlag(1);
+    pub const PRESPECIFIED_ADDRESSES: TimestampFlag = TimestampFlag(3);
+
+    #[inline]
+    pub fn value(&self) -> u4 {
+        self.0
+    }
+}
+
+/// A single entry in a timestamp option.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TimestampEntry {
+    pub address: Option<Ipv4Addr>,
+    pub timestamp: u32,
+}
+
+/// Iterator over the entries in a timestamp option.
+#[derive(Debug, Clone)]
+pub struct TimestampEntries<'a> {
+    chunks: Chunks<'a, u8>,
+    with_address: bool,
+}
+
+impl<'a> Iterator for TimestampEntries<'a> {
+    type Item = TimestampEntry;
+
+    fn next(&mut self) -> Option<TimestampEntry> {
+        let chunk = self.chunks.next()?;
+        if self.with_address && chunk.len() == 8 {
+            Some(TimestampEntry {
+                address: Some(Ipv4Addr::from(read_offset!(chunk, 0, [u8; 4]))),
+                timestamp: read_offset!(chunk, 4, u32, from_be),
+            })
+        } else if !self.with_address && chunk.len() == 4 {
+            Some(TimestampEntry {
+                address: None,
+                timestamp: read_offset!(chunk, 0, u32, from_be),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// View of the Security option as defined in [RFC 791].
+///
+/// [RFC 791]: https://tools.ietf.org/html/rfc791#section-3.1
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SecurityOption<'a>(&'a [u8]);
+
+impl<'a> SecurityOption<'a> {
+    /// Returns the raw bytes of this option, including the type and length bytes.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn security(&self) -> u16 {
+        read_offset!(self.0, 2, u16, from_be)
+    }
+
+    pub fn compartments(&self) -> u16 {
+        read_offset!(self.0, 4, u16, from_be)
+    }
+
+    pub fn handling_restrictions(&self) -> u16 {
+        read_offset!(self.0, 6, u16, from_be)
+    }
+
+    pub fn transmission_control_code(&self) -> u24 {
+        let bytes = read_offset!(self.0, 8, [u8; 3]);
+        u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]])
+    }
+}
+
+/// Error returned when setting the options of an IPv4 header fails.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SetOptionsError {
+    /// The options are longer than the 40 bytes that fit in the header.
+    TooLong,
+    /// The backing buffer is not large enough to hold the header with the options.
+    BufferTooShort,
+}
+
+impl fmt::Display for SetOptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SetOptionsError::TooLong => {
+                write!(f, "IPv4 options longer than {} bytes", MAX_OPTIONS_LEN)
+            }
+            SetOptionsError::BufferTooShort => write!(f, "Buffer too short for IPv4 options"),
+        }
+    }
+}
+
+impl Error for SetOptionsError {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn option_type_parts() {
+        let option_type = OptionType::LOOSE_SOURCE_ROUTE;
+        assert!(option_type.copied());
+        assert_eq!(0, option_type.class());
+        assert_eq!(3, option_type.number());
+        assert!(!OptionType::TIMESTAMP.copied());
+        assert_eq!(2, OptionType::TIMESTAMP.class());
+        assert_eq!(4, OptionType::TIMESTAMP.number());
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(None, Ipv4Options::new(&[]).next());
+    }
+
+    #[test]
+    fn nop_and_end_of_list() {
+        let data = [1, 1, 0, 7, 0xff];
+        let options: Vec<_> = Ipv4Options::new(&data).collect();
+        assert_eq!(
+            vec![Ipv4Option::Nop, Ipv4Option::Nop, Ipv4Option::EndOfList],
+            options
+        );
+    }
+
+    #[test]
+    fn router_alert() {
+        let data = [148, 4, 0x12, 0x34];
+        let options: Vec<_> = Ipv4Options::new(&data).collect();
+        assert_eq!(vec![Ipv4Option::RouterAlert(0x1234)], options);
+    }
+
+    #[test]
+    fn record_route() {
+        let data = [7, 11, 8, 10, 0, 0, 1, 0, 0, 0, 0, 1, 0];
+        let mut options = Ipv4Options::new(&data);
+        match options.next() {
+            Some(Ipv4Option::RecordRoute(route)) => {
+                assert_eq!(11, route.length());
+                assert_eq!(8, route.pointer());
+                let addrs: Vec<_> = route.addresses().collect();
+                assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(0, 0, 0, 0)], addrs);
+                let recorded: Vec<_> = route.recorded_addresses().collect();
+                assert_eq!(vec![Ipv4Addr::new(10, 0, 0, 1)], recorded);
+            }
+            option => panic!("Unexpected option {:?}", option),
+        }
+        assert_eq!(Some(Ipv4Option::Nop), options.next());
+        assert_eq!(Some(Ipv4Option::EndOfList), options.next());
+        assert_eq!(None, options.next());
+    }
+
+    #[test]
+    fn timestamp_with_addresses() {
+        let data = [68, 12, 13, 0x21, 192, 168, 0, 1, 0, 0, 0x01, 0x02];
+        match Ipv4Options::new(&data).next() {
+            Some(Ipv4Option::Timestamp(timestamp)) => {
+                assert_eq!(13, timestamp.pointer());
+                assert_eq!(2, timestamp.overflow());
+                assert_eq!(TimestampFlag::WITH_ADDRESSES, timestamp.flag());
+                let entries: Vec<_> = timestamp.entries().collect();
+                assert_eq!(
+                    vec![
+                        TimestampEntry {
+                            address: Some(Ipv4Addr::new(192, 168, 0, 1)),
+                            timestamp: 0x0102,
+                        },
+                    ],
+                    entries
+                );
+            }
+            option => panic!("Unexpected option {:?}", option),
+        }
+    }
+
+    #[test]
+    fn timestamp_only() {
+        let data = [68, 12, 5, 0x00, 0, 0, 0, 1, 0, 0, 0, 2];
+        match Ipv4Options::new(&data).next() {
+            Some(Ipv4Option::Timestamp(timestamp)) => {
+                let entries: Vec<_> = timestamp.entries().map(|e| e.timestamp).collect();
+                assert_eq!(vec![1, 2], entries);
+            }
+            option => panic!("Unexpected option {:?}", option),
+        }
+    }
+
+    #[test]
+    fn security() {
+        let data = [130, 11, 0xf1, 0x35, 0, 1, 0, 2, 0xab, 0xcd, 0xef];
+        match Ipv4Options::new(&data).next() {
+            Some(Ipv4Option::Security(security)) => {
+                assert_eq!(0xf135, security.security());
+                assert_eq!(1, security.compartments());
+                assert_eq!(2, security.handling_restrictions());
+                assert_eq!(0xabcdef, security.transmission_control_code());
+            }
+            option => panic!("Unexpected option {:?}", option),
+        }
+    }
+
+    #[test]
+    fn unknown() {
+        let data = [25, 4, 1, 2];
+        let options: Vec<_> = Ipv4Options::new(&data).collect();
+        assert_eq!(vec![Ipv4Option::Unknown(OptionType(25), &[1, 2])], options);
+    }
+
+    #[test]
+    fn too_long_length() {
+        let data = [1, 7, 10, 4];
+        let options: Vec<_> = Ipv4Options::new(&data).collect();
+        assert_eq!(vec![Ipv4Option::Nop, Ipv4Option::Malformed(&[7, 10, 4])], options);
+    }
+
+    #[test]
+    fn invalid_router_alert_length() {
+        let data = [148, 3, 0, 1];
+        let options: Vec<_> = Ipv4Options::new(&data).collect();
+        assert_eq!(vec![Ipv4Option::Malformed(&data)], options);
+    }
+
+    #[test]
+    fn missing_length() {
+        let data = [7];
+        let options: Vec<_> = Ipv4Options::new(&data).collect();
+        assert_eq!(vec![Ipv4Option::Malformed(&data)], options);
+    }
+}
@@ -0,0 +1,516 @@
+use ip::Protocol;
+use types::*;
+
+/// Iterator over the extension header chain of an IPv6 packet. Created with
+/// `Ipv6Packet::extension_headers()`.
+///
+/// Yields one item per extension header and always ends with either
+/// [`ExtensionHeader::UpperLayer`], [`ExtensionHeader::NoNextHeader`],
+/// [`ExtensionHeader::FragmentPayload`] or [`ExtensionHeader::Truncated`]. The chain of a
+/// fragment other than the first ends directly after the Fragment header, since the rest of it
+/// continues the payload of an earlier fragment.
+///
+/// [`ExtensionHeader::UpperLayer`]: enum.ExtensionHeader.html#variant.UpperLayer
+/// [`ExtensionHeader::NoNextHeader`]: enum.ExtensionHeader.html#variant.NoNextHeader
+/// [`ExtensionHeader::FragmentPayload`]: enum.ExtensionHeader.html#variant.FragmentPayload
+/// [`ExtensionHeader::Truncated`]: enum.ExtensionHeader.html#variant.Truncated
+#[derive(Debug, Clone)]
+pub struct ExtensionHeaders<'a> {
+    next_header: Option<Protocol>,
+    data: &'a [u8],
+    /// Set after the Fragment header of a fragment other than the first
+    later_fragment: bool,
+}
+
+impl<'a> ExtensionHeaders<'a> {
+    /// Creates an iterator over the header chain in `data`, where the first header is of type
+    /// `next_header`.
+    pub fn new(next_header: Protocol, data: &'a [u8]) -> ExtensionHeaders<'a> {
+        ExtensionHeaders {
+            next_header: Some(next_header),
+            data,
+            later_fragment: false,
+        }
+    }
+
+    /// Walks the rest of the chain and returns the upper-layer protocol and its payload. Returns
+    /// `None` if the chain ends with No Next Header, is truncated or belongs to a fragment other
+    /// than the first.
+    pub fn upper_layer(self) -> Option<(Protocol, &'a [u8])> {
+        match self.last() {
+            Some(ExtensionHeader::UpperLayer(protocol, payload)) => Some((protocol, payload)),
+            _ => None,
+        }
+    }
+
+    fn header_len(protocol: Protocol, data: &[u8]) -> Option<usize> {
+        let length = *data.get(1)? as usize;
+        match protocol {
+            Protocol::IPV6_FRAG => Some(FragmentHeader::LEN),
+            Protocol::AH if length >= 1 => Some((length + 2) * 4),
+            Protocol::AH => None,
+            _ => Some((length + 1) * 8),
+        }
+    }
+}
+
+impl<'a> Iterator for ExtensionHeaders<'a> {
+    type Item = ExtensionHeader<'a>;
+
+    fn next(&mut self) -> Option<ExtensionHeader<'a>> {
+        let protocol = self.next_header.take()?;
+        let data = self.data;
+        if self.later_fragment {
+            return Some(ExtensionHeader::FragmentPayload(protocol, data));
+        }
+        if !is_extension_header(protocol) {
+            return Some(if protocol == Protocol::IPV6_NONXT {
+                ExtensionHeader::NoNextHeader
+            } else {
+                ExtensionHeader::UpperLayer(protocol, data)
+            });
+        }
+        let header_len = match Self::header_len(protocol, data) {
+            Some(header_len) if header_len <= data.len() => header_len,
+            _ => return Some(ExtensionHeader::Truncated(protocol, data)),
+        };
+        let (header, rest) = data.split_at(header_len);
+        self.next_header = Some(Protocol(header[0]));
+        self.data = rest;
+        Some(match protocol {
+            Protocol::HOPOPT => ExtensionHeader::HopByHop(OptionsHeader(header)),
+            Protocol::IPV6_ROUTE => ExtensionHeader::Routing(RoutingHeader(header)),
+            Protocol::IPV6_FRAG => {
+                let header = FragmentHeader(header);
+                self.later_fragment = header.fragment_offset() != 0;
+                ExtensionHeader::Fragment(header)
+            }
+            Protocol::IPV6_OPTS => ExtensionHeader::DestinationOptions(OptionsHeader(header)),
+            _ => ExtensionHeader::Authentication(AuthenticationHeader(header)),
+        })
+    }
+}
+
+/// Returns true if `protocol` is an IPv6 extension header that `ExtensionHeaders` walks past.
+fn is_extension_header(protocol: Protocol) -> bool {
+    matches!(
+        protocol,
+        Protocol::HOPOPT |
+            Protocol::IPV6_ROUTE |
+            Protocol::IPV6_FRAG |
+            Protocol::IPV6_OPTS |
+            Protocol::AH
+    )
+}
+
+/// A single header in the IPv6 header chain.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ExtensionHeader<'a> {
+    HopByHop(OptionsHeader<'a>),
+    Routing(RoutingHeader<'a>),
+    Fragment(FragmentHeader<'a>),
+    DestinationOptions(OptionsHeader<'a>),
+    Authentication(AuthenticationHeader<'a>),
+    /// The chain ended with the No Next Header value. Anything after it should be ignored.
+    NoNextHeader,
+    /// The end of the chain. Contains the upper-layer protocol and its payload.
+    UpperLayer(Protocol, &'a [u8]),
+    /// The end of the chain of a fragment other than the first. Contains the next header value
+    /// of the Fragment header and the fragment data, which is not a header of that type but
+    /// the continuation of an earlier fragment.
+    FragmentPayload(Protocol, &'a [u8]),
+    /// A header of the given type did not fit in the remaining data, or had an invalid length.
+    /// Contains the remaining data.
+    Truncated(Protocol, &'a [u8]),
+}
+
+/// View of the Hop-by-Hop Options and Destination Options headers, which share the same layout.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OptionsHeader<'a>(&'a [u8]);
+
+impl<'a> OptionsHeader<'a> {
+    /// Returns the raw bytes of this header.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn next_header(&self) -> Protocol {
+        Protocol(read_offset!(self.0, 0, u8))
+    }
+
+    /// Returns the length of this header in eight byte units, not counting the first eight
+    /// bytes.
+    pub fn header_ext_length(&self) -> u8 {
+        read_offset!(self.0, 1, u8)
+    }
+
+    /// Returns an iterator over the options in this header.
+    pub fn options(&self) -> Ipv6Options<'a> {
+        Ipv6Options::new(&self.0[2..])
+    }
+}
+
+/// View of the Routing header. The type specific data is available with `type_data()`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RoutingHeader<'a>(&'a [u8]);
+
+impl<'a> RoutingHeader<'a> {
+    /// Returns the raw bytes of this header.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn next_header(&self) -> Protocol {
+        Protocol(read_offset!(self.0, 0, u8))
+    }
+
+    /// Returns the length of this header in eight byte units, not counting the first eight
+    /// bytes.
+    pub fn header_ext_length(&self) -> u8 {
+        read_offset!(self.0, 1, u8)
+    }
+
+    pub fn routing_type(&self) -> u8 {
+        read_offset!(self.0, 2, u8)
+    }
+
+    pub fn segments_left(&self) -> u8 {
+        read_offset!(self.0, 3, u8)
+    }
+
+    /// Returns the routing type specific data following the fixed four bytes.
+    pub fn type_data(&self) -> &'a [u8] {
+        &self.0[4..]
+    }
+}
+
+/// View of the Fragment header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FragmentHeader<'a>(&'a [u8]);
+
+impl<'a> FragmentHeader<'a> {
+    /// The length of the fragment header, in bytes.
+    pub const LEN: usize = 8;
+
+    /// Returns the raw bytes of this header.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn next_header(&self) -> Protocol {
+        Protocol(read_offset!(self.0, 0, u8))
+    }
+
+    /// Returns the offset of the fragment data, in eight byte units.
+    pub fn fragment_offset(&self) -> u13 {
+        read_offset!(self.0, 2, u16, from_be) >> 3
+    }
+
+    pub fn more_fragments(&self) -> bool {
+        read_offset!(self.0, 3, u8) & 0x01 != 0
+    }
+
+    pub fn identification(&self) -> u32 {
+        read_offset!(self.0, 4, u32, from_be)
+    }
+}
+
+/// View of the Authentication Header as defined in [RFC 4302].
+///
+/// [RFC 4302]: https://tools.ietf.org/html/rfc4302
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct AuthenticationHeader<'a>(&'a [u8]);
+
+impl<'a> AuthenticationHeader<'a> {
+    /// Returns the raw bytes of this header.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    pub fn next_header(&self) -> Protocol {
+        Protocol(read_offset!(self.0, 0, u8))
+    }
+
+    /// Returns the length of this header in four byte units, minus two.
+    pub fn payload_length(&self) -> u8 {
+        read_offset!(self.0, 1, u8)
+    }
+
+    pub fn spi(&self) -> u32 {
+        read_offset!(self.0, 4, u32, from_be)
+    }
+
+    pub fn sequence_number(&self) -> u32 {
+        read_offset!(self.0, 8, u32, from_be)
+    }
+
+    /// Returns the Integrity Check Value. Returns an empty slice if the header is too short to
+    /// contain one.
+    pub fn icv(&self) -> &'a [u8] {
+        self.0.get(12..).unwrap_or(&[])
+    }
+}
+
+/// Represents the type byte of an option in a Hop-by-Hop or Destination Options header. See
+/// [IANA's list] for the defined values.
+///
+/// [IANA's list]: https://www.iana.org/assignments/ipv6-parameters/ipv6-parameters.xhtml
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OptionType(pub u8);
+
+impl OptionType {
+    pub const PAD1: OptionType = OptionType(0x00);
+    pub const PADN: OptionType = OptionType(0x01);
+    pub const ROUTER_ALERT: OptionType = OptionType(0x05);
+    pub const JUMBO_PAYLOAD: OptionType = OptionType(0xc2);
+
+    /// Returns the numeric representation of this option type.
+    #[inline]
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns the two high order bits, telling what a node that does not recognize the option
+    /// should do with the packet.
+    pub fn action(&self) -> u2 {
+        self.0 >> 6
+    }
+
+    /// Returns true if the option data may change en route to the destination.
+    pub fn may_change(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+}
+
+/// A single option in a Hop-by-Hop or Destination Options header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Ipv6Option<'a> {
+    Pad1,
+    /// Padding of the given number of bytes, including the type and length bytes.
+    PadN(usize),
+    /// Router Alert option as defined in [RFC 2711]. Contains the 16 bit value of the option.
+    ///
+    /// [RFC 2711]: https://tools.ietf.org/html/rfc2711
+    RouterAlert(u16),
+    /// Jumbo Payload option as defined in [RFC 2675]. Contains the payload length.
+    ///
+    /// [RFC 2675]: https://tools.ietf.org/html/rfc2675
+    JumboPayload(u32),
+    /// An option of a type this crate does not know how to parse. Contains the option type and
+    /// the option data, not including the type and length bytes.
+    Unknown(OptionType, &'a [u8]),
+    /// The option data could not be parsed, typically because of an invalid length byte.
+    /// Contains the remaining option bytes. Iteration ends after this item.
+    Malformed(&'a [u8]),
+}
+
+/// Iterator over the options in a Hop-by-Hop or Destination Options header.
+#[derive(Debug, Clone)]
+pub struct Ipv6Options<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Ipv6Options<'a> {
+    /// Creates an iterator over the options encoded in `data`.
+    pub fn new(data: &'a [u8]) -> Ipv6Options<'a> {
+        Ipv6Options { data }
+    }
+
+    fn parse(option_type: OptionType, option: &'a [u8]) -> Option<Ipv6Option<'a>> {
+        let value = &option[2..];
+        match option_type {
+            OptionType::PADN => Some(Ipv6Option::PadN(option.len())),
+            OptionType::ROUTER_ALERT if value.len() == 2 => Some(Ipv6Option::RouterAlert(
+                read_offset!(value, 0, u16, from_be),
+            )),
+            OptionType::JUMBO_PAYLOAD if value.len() == 4 => Some(Ipv6Option::JumboPayload(
+                read_offset!(value, 0, u32, from_be),
+            )),
+            OptionType::ROUTER_ALERT | OptionType::JUMBO_PAYLOAD => None,
+            _ => Some(Ipv6Option::Unknown(option_type, value)),
+        }
+    }
+}
+
+impl<'a> Iterator for Ipv6Options<'a> {
+    type Item = Ipv6Option<'a>;
+
+    fn next(&mut self) -> Option<Ipv6Option<'a>> {
+        let data = self.data;
+        let option_type = OptionType(*data.first()?);
+        if option_type == OptionType::PAD1 {
+            self.data = &data[1..];
+            return Some(Ipv6Option::Pad1);
+        }
+        let length = data.get(1).map(|&length| length as usize + 2).unwrap_or(0);
+        let option = if length >= 2 && length <= data.len() {
+            Self::parse(option_type, &data[..length])
+        } else {
+            None
+        };
+        match option {
+            Some(option) => {
+                self.data = &data[length..];
+                Some(option)
+            }
+            None => {
+                self.data = &[];
+                Some(Ipv6Option::Malformed(data))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_extension_headers() {
+        let data = [1, 2, 3];
+        let headers: Vec<_> = ExtensionHeaders::new(Protocol::UDP, &data).collect();
+        assert_eq!(vec![ExtensionHeader::UpperLayer(Protocol::UDP, &data[..])], headers);
+    }
+
+    #[test]
+    fn no_next_header() {
+        let data = [1, 2, 3];
+        let mut headers = ExtensionHeaders::new(Protocol::IPV6_NONXT, &data);
+        assert_eq!(Some(ExtensionHeader::NoNextHeader), headers.next());
+        assert_eq!(None, headers.next());
+        assert_eq!(None, ExtensionHeaders::new(Protocol::IPV6_NONXT, &data).upper_layer());
+    }
+
+    #[test]
+    fn header_chain() {
+        let data = [
+            // Hop-by-Hop: next Routing, one extra 8 byte unit, Router Alert, PadN
+            43, 1, 5, 2, 0, 0, 1, 8, 0, 0, 0, 0, 0, 0, 0, 0,
+            // Routing: next Fragment, type 2, 1 segment left
+            44, 0, 2, 1, 0xaa, 0xbb, 0xcc, 0xdd,
+            // Fragment: next AH, first fragment, more fragments, identification
+            51, 0, 0, 0x01, 0xde, 0xad, 0xbe, 0xef,
+            // AH: next Destination Options, 16 bytes, SPI, sequence number
+            60, 2, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0xff, 0xff, 0xff, 0xff,
+            // Destination Options: next TCP, Pad1 and a PadN
+            6, 0, 0, 1, 3, 0, 0, 0,
+            // TCP payload
+            0x12, 0x34,
+        ];
+        let mut headers = ExtensionHeaders::new(Protocol::HOPOPT, &data);
+        match headers.next() {
+            Some(ExtensionHeader::HopByHop(header)) => {
+                assert_eq!(Protocol::IPV6_ROUTE, header.next_header());
+                assert_eq!(16, header.data().len());
+                let options: Vec<_> = header.options().collect();
+                assert_eq!(
+                    vec![Ipv6Option::RouterAlert(0), Ipv6Option::PadN(10)],
+                    options
+                );
+            }
+            header => panic!("Unexpected header {:?}", header),
+        }
+        match headers.next() {
+            Some(ExtensionHeader::Routing(header)) => {
+                assert_eq!(Protocol::IPV6_FRAG, header.next_header());
+                assert_eq!(2, header.routing_type());
+                assert_eq!(1, header.segments_left());
+                assert_eq!(&[0xaa, 0xbb, 0xcc, 0xdd], header.type_data());
+            }
+            header => panic!("Unexpected header {:?}", header),
+        }
+        match headers.next() {
+            Some(ExtensionHeader::Fragment(header)) => {
+                assert_eq!(Protocol::AH, header.next_header());
+                assert_eq!(0, header.fragment_offset());
+                assert!(header.more_fragments());
+                assert_eq!(0xdeadbeef, header.identification());
+            }
+            header => panic!("Unexpected header {:?}", header),
+        }
+        match headers.next() {
+            Some(ExtensionHeader::Authentication(header)) => {
+                assert_eq!(Protocol::IPV6_OPTS, header.next_header());
+                assert_eq!(1, header.spi());
+                assert_eq!(2, header.sequence_number());
+                assert_eq!(&[0xff; 4], header.icv());
+            }
+            header => panic!("Unexpected header {:?}", header),
+        }
+        match headers.next() {
+            Some(ExtensionHeader::DestinationOptions(header)) => {
+                let options: Vec<_> = header.options().collect();
+                assert_eq!(vec![Ipv6Option::Pad1, Ipv6Option::PadN(5)], options);
+            }
+            header => panic!("Unexpected header {:?}", header),
+        }
+        assert_eq!(
+            Some(ExtensionHeader::UpperLayer(Protocol::TCP, &[0x12, 0x34][..])),
+            headers.next()
+        );
+        assert_eq!(None, headers.next());
+    }
+
+    #[test]
+    fn later_fragment() {
+        let data = [
+            // Fragment: next Destination Options, offset 0x123
+            60, 0, 0x09, 0x18, 0xde, 0xad, 0xbe, 0xef,
+            // Fragment data that happens to look like a Destination Options header
+            17, 0, 1, 4, 0, 0, 0, 0, 0x12, 0x34,
+        ];
+        let headers: Vec<_> = ExtensionHeaders::new(Protocol::IPV6_FRAG, &data).collect();
+        assert_eq!(2, headers.len());
+        match headers[0] {
+            ExtensionHeader::Fragment(header) => assert_eq!(0x123, header.fragment_offset()),
+            header => panic!("Unexpected header {:?}", header),
+        }
+        assert_eq!(
+            ExtensionHeader::FragmentPayload(Protocol::IPV6_OPTS, &data[8..]),
+            headers[1]
+        );
+        assert_eq!(None, ExtensionHeaders::new(Protocol::IPV6_FRAG, &data).upper_layer());
+
+        // The first fragment is walked like an unfragmented packet
+        let mut first = data;
+        first[2..4].copy_from_slice(&[0, 1]);
+        assert_eq!(
+            Some((Protocol::UDP, &data[16..])),
+            ExtensionHeaders::new(Protocol::IPV6_FRAG, &first).upper_layer()
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let data = [6, 1, 0, 0, 0, 0, 0, 0, 0];
+        let headers: Vec<_> = ExtensionHeaders::new(Protocol::IPV6_OPTS, &data).collect();
+        assert_eq!(
+            vec![ExtensionHeader::Truncated(Protocol::IPV6_OPTS, &data[..])],
+            headers
+        );
+        assert_eq!(None, ExtensionHeaders::new(Protocol::IPV6_OPTS, &data).upper_layer());
+    }
+
+    #[test]
+    fn upper_layer() {
+        let data = [17, 0, 0, 0, 0, 0, 0, 0, 9];
+        assert_eq!(
+            Some((Protocol::UDP, &[9][..])),
+            ExtensionHeaders::new(Protocol::HOPOPT, &data).upper_layer()
+        );
+    }
+
+    #[test]
+    fn jumbo_payload_option() {
+        let data = [0xc2, 4, 0, 1, 0, 0];
+        let options: Vec<_> = Ipv6Options::new(&data).collect();
+        assert_eq!(vec![Ipv6Option::JumboPayload(0x10000)], options);
+    }
+
+    #[test]
+    fn malformed_option() {
+        let data = [1, 4, 0];
+        let options: Vec<_> = Ipv6Options::new(&data).collect();
+        assert_eq!(vec![Ipv6Option::Malformed(&data)], options);
+    }
+}
@@ -0,0 +1,368 @@
+use core::error::Error;
+use core::fmt;
+use core::net::Ipv6Addr;
+use ip::Protocol;
+use types::*;
+
+mod extension;
+pub use self::extension::*;
+
+packet!(Ipv6Packet, MutIpv6Packet, 40, |_: &[u8]| 40, |data: &[u8]| {
+    40 + read_offset!(data, 4, u16, from_be) as usize
+});
+
+getters!(Ipv6Packet
+    pub fn version(&self) -> u4 {
+        read_offset!(self.data(), 0, u8) >> 4
+    }
+
+    pub fn traffic_class(&self) -> u8 {
+        (read_offset!(self.data(), 0, u8) << 4) | (read_offset!(self.data(), 1, u8) >> 4)
+    }
+
+    pub fn dscp(&self) -> u6 {
+        self.traffic_class() >> 2
+    }
+
+    pub fn ecn(&self) -> u2 {
+        self.traffic_class() & 0x03
+    }
+
+    pub fn flow_label(&self) -> u20 {
+        read_offset!(self.data(), 0, u32, from_be) & 0x000f_ffff
+    }
+
+    /// Returns the length of the payload in bytes, extension headers included. The payload ends
+    /// here, anything after it in the buffer, such as Ethernet padding, is in `trailer()`.
+    /// Jumbograms, where this field is zero, are not supported.
+    pub fn payload_length(&self) -> u16 {
+        read_offset!(self.data(), 4, u16, from_be)
+    }
+
+    pub fn next_header(&self) -> Protocol {
+        Protocol(read_offset!(self.data(), 6, u8))
+    }
+
+    pub fn hop_limit(&self) -> u8 {
+        read_offset!(self.data(), 7, u8)
+    }
+
+    pub fn source(&self) -> Ipv6Addr {
+        Ipv6Addr::from(read_offset!(self.data(), 8, [u8; 16]))
+    }
+
+    pub fn destination(&self) -> Ipv6Addr {
+        Ipv6Addr::from(read_offset!(self.data(), 24, [u8; 16]))
+    }
+
+    /// Returns an iterator over the extension header chain, starting with the header given by
+    /// `next_header()` and ending with the upper-layer protocol and its payload.
+    pub fn extension_headers(&self) -> ExtensionHeaders<'_> {
+        ExtensionHeaders::new(self.next_header(), self.payload())
+    }
+
+    /// Skips all extension headers and returns the upper-layer protocol and its payload. Returns
+    /// `None` if the chain ends with No Next Header, is truncated or this is a fragment other
+    /// than the first.
+    pub fn upper_layer(&self) -> Option<(Protocol, &[u8])> {
+        self.extension_headers().upper_layer()
+    }
+);
+
+impl<B: AsRef<[u8]>> Ipv6Packet<B> {
+    /// Checks that the version is 6 and that the payload length field fits in the packet.
+    /// Extension headers are not checked.
+    pub fn validate(&self) -> Result<(), Ipv6Error> {
+        if self.version() != 6 {
+            return Err(Ipv6Error::Version);
+        }
+        if Ipv6Packet::MIN_LEN + usize::from(self.payload_length()) > self.data().len() {
+            return Err(Ipv6Error::PayloadLength);
+        }
+        Ok(())
+    }
+}
+
+new_checked!(Ipv6Packet, Ipv6Error);
+
+/// Writes a one line summary of the header, like `IPv6 fe80::1 -> ff02::1 hlim=255
+/// next=IPv6-ICMP len=32`, where `len` is the payload length.
+impl<B: AsRef<[u8]>> fmt::Display for Ipv6Packet<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "IPv6 {} -> {} hlim={} next={:#} len={}",
+            self.source(),
+            self.destination(),
+            self.hop_limit(),
+            self.next_header(),
+            self.payload_length()
+        )
+    }
+}
+
+setters!(Ipv6Packet
+    pub fn set_version(&mut self, version: u4) {
+        let new_byte = (version << 4) | (read_offset!(self.data(), 0, u8) & 0x0f);
+        write_offset!(self.data_mut(), 0, new_byte, u8);
+    }
+
+    pub fn set_traffic_class(&mut self, traffic_class: u8) {
+        let byte0 = (read_offset!(self.data(), 0, u8) & 0xf0) | (traffic_class >> 4);
+        let byte1 = (traffic_class << 4) | (read_offset!(self.data(), 1, u8) & 0x0f);
+        write_offset!(self.data_mut(), 0, byte0, u8);
+        write_offset!(self.data_mut(), 1, byte1, u8);
+    }
+
+    pub fn set_dscp(&mut self, dscp: u6) {
+        let traffic_class = (dscp << 2) | (self.traffic_class() & 0x03);
+        self.set_traffic_class(traffic_class);
+    }
+
+    pub fn set_ecn(&mut self, ecn: u2) {
+        let traffic_class = (self.traffic_class() & 0xfc) | (ecn & 0x03);
+        self.set_traffic_class(traffic_class);
+    }
+
+    pub fn set_flow_label(&mut self, flow_label: u20) {
+        let word = read_offset!(self.data(), 0, u32, from_be);
+        let new_word = (word & 0xfff0_0000) | (flow_label & 0x000f_ffff);
+        write_offset!(self.data_mut(), 0, new_word, u32, to_be);
+    }
+
+    pub fn set_payload_length(&mut self, payload_length: u16) {
+        write_offset!(self.data_mut(), 4, payload_length, u16, to_be);
+    }
+
+    pub fn set_next_header(&mut self, protocol: Protocol) {
+        write_offset!(self.data_mut(), 6, protocol.value(), u8);
+    }
+
+    pub fn set_hop_limit(&mut self, hop_limit: u8) {
+        write_offset!(self.data_mut(), 7, hop_limit, u8);
+    }
+
+    pub fn set_source(&mut self, source: Ipv6Addr) {
+        write_offset!(self.data_mut(), 8, source.octets(), [u8; 16]);
+    }
+
+    pub fn set_destination(&mut self, destination: Ipv6Addr) {
+        write_offset!(self.data_mut(), 24, destination.octets(), [u8; 16]);
+    }
+);
+
+
+/// An owned representation of the fixed IPv6 header. Extension headers are part of the payload.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Ipv6Repr {
+    pub traffic_class: u8,
+    pub flow_label: u20,
+    pub next_header: Protocol,
+    pub hop_limit: u8,
+    pub source: Ipv6Addr,
+    pub destination: Ipv6Addr,
+    /// The length of the payload, including any extension headers.
+    pub payload_len: usize,
+}
+
+impl Ipv6Repr {
+    /// Reads the header of `packet` into an `Ipv6Repr`. Fails if the version is not 6 or the
+    /// payload length field is longer than the packet.
+    pub fn parse<B: AsRef<[u8]>>(packet: &Ipv6Packet<B>) -> Result<Self, Ipv6Error> {
+        if packet.data().len() < Ipv6Packet::MIN_LEN {
+            return Err(Ipv6Error::Truncated);
+        }
+        packet.validate()?;
+        Ok(Ipv6Repr {
+            traffic_class: packet.traffic_class(),
+            flow_label: packet.flow_label(),
+            next_header: packet.next_header(),
+            hop_limit: packet.hop_limit(),
+            source: packet.source(),
+            destination: packet.destination(),
+            payload_len: usize::from(packet.payload_length()),
+        })
+    }
+
+    /// Returns the number of bytes needed to emit this header. The payload is not included.
+    pub fn buffer_len(&self) -> usize {
+        Ipv6Packet::MIN_LEN
+    }
+
+    /// Writes this header to `packet`. The payload is left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `payload_len` does not fit in the payload length field. Jumbograms are not
+    /// supported.
+    pub fn emit<B: AsRef<[u8]> + AsMut<[u8]>>(&self, packet: &mut Ipv6Packet<B>) {
+        assert!(self.payload_len <= usize::from(u16::MAX), "IPv6 payload too long");
+        packet.set_version(6);
+        packet.set_traffic_class(self.traffic_class);
+        packet.set_flow_label(self.flow_label);
+        packet.set_payload_length(self.payload_len as u16);
+        packet.set_next_header(self.next_header);
+        packet.set_hop_limit(self.hop_limit);
+        packet.set_source(self.source);
+        packet.set_destination(self.destination);
+    }
+}
+
+/// Error returned when an IPv6 packet is not valid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Ipv6Error {
+    /// The packet is too short to hold an IPv6 header.
+    Truncated,
+    /// The version field is not 6.
+    Version,
+    /// The payload length field is longer than the packet.
+    PayloadLength,
+}
+
+impl fmt::Display for Ipv6Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Ipv6Error::Truncated => write!(f, "IPv6 packet truncated"),
+            Ipv6Error::Version => write!(f, "IPv6 version field is not 6"),
+            Ipv6Error::PayloadLength => write!(f, "Invalid IPv6 payload length"),
+        }
+    }
+}
+
+impl Error for Ipv6Error {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! ipv6_setget_test {
+        ($name:ident, $set_name:ident, $value:expr, $offset:expr, $expected:expr) => {
+            setget_test!(MutIpv6Packet, $name, $set_name, $value, $offset, $expected);
+        }
+    }
+
+    ipv6_setget_test!(version, set_version, 0xf, 0, [0xf0]);
+    ipv6_setget_test!(traffic_class, set_traffic_class, 0xff, 0, [0x0f, 0xf0]);
+    ipv6_setget_test!(dscp, set_dscp, 0x3f, 0, [0x0f, 0xc0]);
+    ipv6_setget_test!(ecn, set_ecn, 0x3, 1, [0x30]);
+    ipv6_setget_test!(flow_label, set_flow_label, 0xfffff, 1, [0x0f, 0xff, 0xff]);
+    ipv6_setget_test!(payload_length, set_payload_length, 0xabcd, 4, [0xab, 0xcd]);
+    ipv6_setget_test!(next_header, set_next_header, Protocol(123), 6, [123]);
+    ipv6_setget_test!(hop_limit, set_hop_limit, 0x65, 7, [0x65]);
+    ipv6_setget_test!(
+        source,
+        set_source,
+        Ipv6Addr::new(0x2001, 1, 2, 3, 4, 5, 6, 0xabcd),
+        8,
+        [0x20, 0x01, 0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0xab, 0xcd]
+    );
+    ipv6_setget_test!(
+        destination,
+        set_destination,
+        Ipv6Addr::new(0x2001, 1, 2, 3, 4, 5, 6, 0x1234),
+        24,
+        [0x20, 0x01, 0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0x12, 0x34]
+    );
+
+    #[test]
+    fn getters_alternating_bits() {
+        let backing_data = [0b1010_1010; 40];
+        let testee = Ipv6Packet::new(&backing_data).unwrap();
+        assert_eq!(0b1010, testee.version());
+        assert_eq!(0b1010_1010, testee.traffic_class());
+        assert_eq!(0b101010, testee.dscp());
+        assert_eq!(0b10, testee.ecn());
+        assert_eq!(0b1010_1010_1010_1010_1010, testee.flow_label());
+    }
+
+    #[test]
+    fn setters_keep_neighbouring_bits() {
+        let mut backing_data = [0xff; 40];
+        {
+            let mut testee = MutIpv6Packet::new(&mut backing_data).unwrap();
+            testee.set_traffic_class(0);
+            testee.set_flow_label(0);
+        }
+        assert_eq!([0xf0, 0, 0, 0, 0xff], backing_data[..5]);
+    }
+
+    #[test]
+    fn upper_layer_after_extension_headers() {
+        let mut backing_data = [0; 50];
+        {
+            let mut testee = MutIpv6Packet::new(&mut backing_data).unwrap();
+            testee.set_next_header(Protocol::IPV6_FRAG);
+            testee.set_payload_length(10);
+            testee.payload_mut()[0] = Protocol::UDP.value();
+        }
+        let testee = Ipv6Packet::new(&backing_data).unwrap();
+        assert_eq!(10, testee.payload().len());
+        assert_eq!(2, testee.extension_headers().count());
+        assert_eq!(Some((Protocol::UDP, &[0; 2][..])), testee.upper_layer());
+    }
+
+    #[test]
+    fn repr_round_trip() {
+        let repr = Ipv6Repr {
+            traffic_class: 0xb8,
+            flow_label: 0x12345,
+            next_header: Protocol::TCP,
+            hop_limit: 255,
+            source: Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+            destination: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2),
+            payload_len: 2,
+        };
+        let mut backing_data = [0; 42];
+        let mut packet = Ipv6Packet::new(&mut backing_data[..]).unwrap();
+        repr.emit(&mut packet);
+        assert_eq!(40, repr.buffer_len());
+        assert_eq!(Ok(repr), Ipv6Repr::parse(&packet));
+        packet.set_payload_length(3);
+        assert_eq!(Err(Ipv6Error::PayloadLength), Ipv6Repr::parse(&packet));
+        packet.set_version(4);
+        assert_eq!(Err(Ipv6Error::Version), Ipv6Repr::parse(&packet));
+    }
+
+    #[test]
+    fn validate() {
+        let mut backing_data = [0; 42];
+        backing_data[0] = 0x60;
+        backing_data[5] = 2;
+        assert!(Ipv6Packet::new_checked(&backing_data[..]).is_ok());
+        assert_eq!(Err(Ipv6Error::PayloadLength), Ipv6Packet::new_checked(&backing_data[..41]));
+        backing_data[0] = 0x40;
+        assert_eq!(Err(Ipv6Error::Version), Ipv6Packet::new_checked(&backing_data[..]));
+    }
+
+    #[test]
+    fn padded_packet() {
+        let mut backing_data = [0xaa; 64];
+        {
+            let mut testee = MutIpv6Packet::new(&mut backing_data).unwrap();
+            testee.set_payload_length(8);
+            testee.trailer_mut().copy_from_slice(&[0; 16]);
+        }
+        let testee = Ipv6Packet::new(&backing_data).unwrap();
+        assert_eq!([0xaa; 8], testee.payload());
+        assert_eq!([0; 16], testee.trailer());
+    }
+
+    #[test]
+    fn display() {
+        let mut backing_data = [0; 72];
+        {
+            let mut testee = MutIpv6Packet::new(&mut backing_data).unwrap();
+            testee.set_version(6);
+            testee.set_payload_length(32);
+            testee.set_next_header(Protocol::IPV6_ICMP);
+            testee.set_hop_limit(255);
+            testee.set_source(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1));
+            testee.set_destination(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1));
+        }
+        assert_eq!(
+            "IPv6 fe80::1 -> ff02::1 hlim=255 next=IPv6-ICMP len=32",
+            Ipv6Packet::new(&backing_data).unwrap().to_string()
+        );
+    }
+}
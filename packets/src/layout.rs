@@ -0,0 +1,299 @@
+//! Support for packet types defined with the `packet_layout!` macro. The macro takes a table of
+//! header fields, each given as a bit offset, a bit width and a byte order, and generates the
+//! getters, setters, `MIN_LEN`, a field listing and tests for the packet. The listing is exposed
+//! as `FooPacket::FIELDS` for introspection, e.g. to label the bytes of a hexdump.
+
+use core::fmt;
+use core::net::Ipv4Addr;
+use ethernet::MacAddr;
+
+/// The byte order of a header field spanning more than one byte.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Endianness {
+    /// Most significant byte first, network byte order. Also used for fields that are not byte
+    /// aligned, where the most significant bit comes first.
+    Big,
+    /// Least significant byte first. Only valid for byte aligned fields.
+    Little,
+}
+
+/// Describes one header field of a packet type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct FieldInfo {
+    /// The name of the getter for the field.
+    pub name: &'static str,
+    /// The offset of the most significant bit of the field from the start of the packet.
+    pub bit_offset: usize,
+    /// The number of bits in the field.
+    pub bit_width: usize,
+    pub endianness: Endianness,
+}
+
+impl FieldInfo {
+    /// Returns the offset of the first byte containing any bit of this field.
+    pub fn byte_offset(&self) -> usize {
+        self.bit_offset / 8
+    }
+
+    /// Returns the number of bytes containing any bit of this field.
+    pub fn byte_len(&self) -> usize {
+        (self.bit_offset % 8 + self.bit_width).div_ceil(8)
+    }
+}
+
+/// Returns the number of bytes needed to hold all of the given fields.
+pub const fn min_len(fields: &[FieldInfo]) -> usize {
+    let mut bits = 0;
+    let mut i = 0;
+    while i < fields.len() {
+        let end = fields[i].bit_offset + fields[i].bit_width;
+        if end > bits {
+            bits = end;
+        }
+        i += 1;
+    }
+    bits.div_ceil(8)
+}
+
+/// A mask with the lowest `width` bits set.
+#[inline(always)]
+pub fn mask(width: usize) -> u64 {
+    if width >= 64 {
+        !0
+    } else {
+        (1 << width) - 1
+    }
+}
+
+/// Reads a field of `width` bits starting at `bit_offset` from `data`. The field may span at
+/// most eight bytes. Called with constant offsets and widths this optimizes down to a plain load
+/// and, for fields that are not byte aligned, a shift and mask.
+#[inline(always)]
+pub fn read_bits(data: &[u8], bit_offset: usize, width: usize, endianness: Endianness) -> u64 {
+    let shift = bit_offset % 8;
+    let len = (shift + width).div_ceil(8);
+    let bytes = &data[bit_offset / 8..bit_offset / 8 + len];
+    match endianness {
+        Endianness::Big => {
+            let word = bytes.iter().fold(0u64, |word, &byte| (word << 8) | u64::from(byte));
+            (word >> (len * 8 - shift - width)) & mask(width)
+        }
+        Endianness::Little => {
+            debug_assert!(shift == 0 && width.is_multiple_of(8));
+            bytes.iter().rev().fold(0u64, |word, &byte| (word << 8) | u64::from(byte))
+        }
+    }
+}
+
+/// Writes the lowest `width` bits of `value` to the field starting at `bit_offset` in `data`,
+/// leaving all other bits untouched. See `read_bits()`.
+#[inline(always)]
+pub fn write_bits(
+    data: &mut [u8],
+    bit_offset: usize,
+    width: usize,
+    endianness: Endianness,
+    value: u64,
+) {
+    let shift = bit_offset % 8;
+    let len = (shift + width).div_ceil(8);
+    let bytes = &mut data[bit_offset / 8..bit_offset / 8 + len];
+    match endianness {
+        Endianness::Big => {
+            let low = len * 8 - shift - width;
+            let field_mask = mask(width) << low;
+            let word = bytes.iter().fold(0u64, |word, &byte| (word << 8) | u64::from(byte));
+            let word = (word & !field_mask) | ((value << low) & field_mask);
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = (word >> ((len - 1 - i) * 8)) as u8;
+            }
+        }
+        Endianness::Little => {
+            debug_assert!(shift == 0 && width.is_multiple_of(8));
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = (value >> (i * 8)) as u8;
+            }
+        }
+    }
+}
+
+/// A type that can be stored in a `packet_layout!` field. Converts to and from the raw bits of
+/// the field, given as the lowest bits of a `u64`.
+pub trait LayoutValue: Sized {
+    fn from_raw(raw: u64) -> Self;
+
+    fn into_raw(self) -> u64;
+
+    /// Writes the value as shown in field listings such as the annotated hexdump. Uses the
+    /// `Display` implementation of types that have one.
+    fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+macro_rules! impl_integer_layout_value {
+    ($($type:ident),*) => {$(
+        impl LayoutValue for $type {
+            #[inline(always)]
+            fn from_raw(raw: u64) -> Self {
+                raw as $type
+            }
+
+            #[inline(always)]
+            fn into_raw(self) -> u64 {
+                self as u64
+            }
+
+            fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(self, f)
+            }
+        }
+    )*}
+}
+
+impl_integer_layout_value!(u8, u16, u32, u64);
+
+impl LayoutValue for bool {
+    #[inline(always)]
+    fn from_raw(raw: u64) -> Self {
+        raw != 0
+    }
+
+    #[inline(always)]
+    fn into_raw(self) -> u64 {
+        self as u64
+    }
+
+    fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl LayoutValue for Ipv4Addr {
+    #[inline(always)]
+    fn from_raw(raw: u64) -> Self {
+        Ipv4Addr::from(raw as u32)
+    }
+
+    #[inline(always)]
+    fn into_raw(self) -> u64 {
+        u64::from(u32::from(self))
+    }
+
+    fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl LayoutValue for MacAddr {
+    #[inline(always)]
+    fn from_raw(raw: u64) -> Self {
+        MacAddr::from_slice(&raw.to_be_bytes()[2..])
+    }
+
+    #[inline(always)]
+    fn into_raw(self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[2..].copy_from_slice(&self.0);
+        u64::from_be_bytes(bytes)
+    }
+
+    fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Asserts that the fields are within eight bytes each, are listed in order and do not overlap.
+/// Called from the tests generated by `packet_layout!`.
+#[cfg(test)]
+pub fn assert_valid_fields(fields: &[FieldInfo]) {
+    let mut end = 0;
+    for field in fields {
+        assert!(field.bit_width > 0, "{} is empty", field.name);
+        assert!(field.byte_len() <= 8, "{} is too wide", field.name);
+        assert!(field.bit_offset >= end, "{} overlaps the previous field", field.name);
+        if field.endianness == Endianness::Little {
+            assert!(field.bit_offset % 8 == 0 && field.bit_width % 8 == 0, "{}", field.name);
+        }
+        end = field.bit_offset + field.bit_width;
+    }
+}
+
+/// Asserts that exactly the bits of `field` are set in `data` and no other bits. Called from the
+/// tests generated by `packet_layout!`.
+#[cfg(test)]
+pub fn assert_only_field_set(data: &[u8], field: &FieldInfo) {
+    for (i, &byte) in data.iter().enumerate() {
+        for bit in 0..8 {
+            let bit_offset = i * 8 + bit;
+            let in_field =
+                bit_offset >= field.bit_offset && bit_offset < field.bit_offset + field.bit_width;
+            let set = byte & (0x80 >> bit) != 0;
+            assert_eq!(in_field, set, "{}: wrong value of bit {}", field.name, bit_offset);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_aligned() {
+        let data = [0x12, 0x34, 0x56, 0x78];
+        assert_eq!(0x12, read_bits(&data, 0, 8, Endianness::Big));
+        assert_eq!(0x3456, read_bits(&data, 8, 16, Endianness::Big));
+        assert_eq!(0x5634, read_bits(&data, 8, 16, Endianness::Little));
+        assert_eq!(0x1234_5678, read_bits(&data, 0, 32, Endianness::Big));
+    }
+
+    #[test]
+    fn read_unaligned() {
+        let data = [0x12, 0x34, 0x56];
+        assert_eq!(0x1, read_bits(&data, 0, 4, Endianness::Big));
+        assert_eq!(0x2, read_bits(&data, 4, 4, Endianness::Big));
+        assert_eq!(0x234, read_bits(&data, 4, 12, Endianness::Big));
+        assert_eq!(0xa2b, read_bits(&data, 11, 12, Endianness::Big));
+    }
+
+    #[test]
+    fn read_full_width() {
+        let data = [0xff; 8];
+        assert_eq!(!0, read_bits(&data, 0, 64, Endianness::Big));
+    }
+
+    #[test]
+    fn write_unaligned() {
+        let mut data = [0xff; 3];
+        write_bits(&mut data, 4, 12, Endianness::Big, 0);
+        assert_eq!([0xf0, 0x00, 0xff], data);
+        write_bits(&mut data, 4, 12, Endianness::Big, 0xabcd);
+        assert_eq!([0xfb, 0xcd, 0xff], data);
+    }
+
+    #[test]
+    fn write_little_endian() {
+        let mut data = [0; 4];
+        write_bits(&mut data, 8, 16, Endianness::Little, 0x1234);
+        assert_eq!([0, 0x34, 0x12, 0], data);
+    }
+
+    #[test]
+    fn min_len_of_fields() {
+        let field = |bit_offset, bit_width| FieldInfo {
+            name: "field",
+            bit_offset,
+            bit_width,
+            endianness: Endianness::Big,
+        };
+        assert_eq!(0, min_len(&[]));
+        assert_eq!(3, min_len(&[field(0, 4), field(4, 16)]));
+        assert_eq!(4, min_len(&[field(16, 16), field(0, 4)]));
+    }
+
+    #[test]
+    fn mac_addr_value() {
+        let mac = MacAddr([1, 2, 3, 4, 5, 6]);
+        assert_eq!(0x0102_0304_0506, mac.into_raw());
+        assert_eq!(mac, MacAddr::from_raw(0x0102_0304_0506));
+    }
+}
@@ -4,20 +4,33 @@
 //! slices (`&[u8]`) in a zero allocation, zero copy, zero-cost way and provide
 //! getter and setter methods for the fields in the corresponding protocols.
 //!
-//! All packet types in this crate are represented by two structs, one for immutable data (used to
-//! read header fields) and one for mutable data (used to set header fields). These structs are
-//! defined like this:
+//! All packet types in this crate are generic over the buffer backing them. Getters are available
+//! for any buffer that implements `AsRef<[u8]>` and setters for buffers that also implement
+//! `AsMut<[u8]>`. The types are defined like this:
 //!
 //! ```rust
-//! pub struct FooPacket<'a>(&'a [u8]);
-//! pub struct MutFooPacket<'a>(&'a mut [u8]);
+//! pub struct FooPacket<B>(B);
+//! pub type MutFooPacket<'a> = FooPacket<&'a mut [u8]>;
 //! ```
 //!
-//! Creating an immutable packet is done with `FooPacket::new(a_slice_of_u8)` and a mutable one with
-//! `MutFooPacket::new(a_mut_slice_of_u8)`. This returns a new packet instance after making sure the
-//! given slice is at least as long as the header of a "Foo packet". The packet types has getters
-//! and setters for each header field. A getter/setter only bitshifts, masks out and optionally do
-//! endianess conversion of the bytes in the backing buffer, making the operations very cheap.
+//! Creating a packet is done with `FooPacket::new(buffer)`. This returns a new packet instance
+//! after making sure the given buffer is at least as long as the header of a "Foo packet". The
+//! buffer can be a borrowed slice, or an owned buffer such as a `Vec<u8>` or `Box<[u8]>` when the
+//! packet needs to outlive the scope it was created in. The packet types has getters and setters
+//! for each header field. A getter/setter only bitshifts, masks out and optionally do endianess
+//! conversion of the bytes in the backing buffer, making the operations very cheap.
+//!
+//! `new` only checks the length of the buffer. For traffic that can be malformed, most packet
+//! types also have `validate()`, which checks that the header fields are consistent with each
+//! other and with the buffer, and `new_checked(buffer)`, which does both. They return an error
+//! type per protocol, such as `Ipv4Error`, describing what is wrong.
+//!
+//! The packet types implement `Display` as a one line summary of the header, such as
+//! `IPv4 192.168.0.1 -> 10.0.0.1 ttl=64 proto=UDP len=84 DF`. See the `dissect` module for
+//! summaries and annotated hexdumps of whole frames.
+//!
+//! To create a whole packet without sizing buffers and filling in lengths and checksums by hand,
+//! stack its layers with the `builder` module.
 //!
 //! # Usage
 //!
@@ -45,8 +58,7 @@
 //!     }
 //!
 //!     // Create an immutable representation of the ethernet frame based on the same
-//!     // buffer. Where a mutable `MutEthernetPacket` has setters `EthernetPacket` has the
-//!     // corresponding getters.
+//!     // buffer. A packet over an immutable buffer only has the getters.
 //!     let packet = EthernetPacket::new(&buffer[..]).expect("Too short buffer");
 //!
 //!     println!("Destination MAC: {}", packet.destination());
@@ -56,6 +68,13 @@
 //! }
 //! ```
 //!
+//! # `no_std` support
+//!
+//! The crate depends on the standard library through the `std` feature, which is enabled by
+//! default. Disable default features to build it on `core` only. Packets, addresses and errors
+//! never allocate, so all functionality except what is explicitly documented as requiring `std`
+//! is available without it.
+//!
 //! # Prior art and comparison
 //!
 //! This crate is heavily inspired by `pnet_packet` from
@@ -69,9 +88,20 @@
 //! more manual implementations is that it is easy to hand optimize single
 //! getters or setters if needed.
 //!
+//! Packets with simple fixed headers, such as Ethernet, ARP and IPv4, are declared as a table of
+//! fields with the `packet_layout!` macro, which generates the getters, setters and tests for
+//! each field. It is a plain `macro_rules!` macro, and single fields can be marked `[manual]` and
+//! implemented by hand. The field tables are available as `FooPacket::FIELDS`, see the `layout`
+//! module.
+//!
 //! Compiling `rips-packets` takes under a second on a modern computer, whereas `pnet_packet` take
 //! well over a minute on the same hardware.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(any(feature = "std", test))]
+extern crate core;
+
 #[macro_use]
 extern crate bitflags;
 
@@ -82,10 +112,30 @@ mod macros;
 pub mod ethernet;
 
 pub mod arp;
+pub mod builder;
+pub mod checksum;
+pub mod dissect;
+pub mod icmp;
+pub mod icmpv6;
 pub mod ip;
 pub mod ipv4;
 pub mod ipv6;
+pub mod layout;
+pub mod pcap;
+pub mod pcapng;
+pub mod tcp;
+pub mod udp;
 
 
 /// Bit field type aliases.
 pub mod types;
+
+/// Implemented by every packet type for any backing buffer. `FooPacket::MIN_LEN` is only
+/// defined for `FooPacket<&[u8]>`, so with this trait in scope the constant is also available
+/// as `MutFooPacket::MIN_LEN`, `FooPacket::<Vec<u8>>::MIN_LEN` and `Self::MIN_LEN` in generic
+/// code.
+pub trait MinLen {
+    /// The minimum number of bytes in this type of packet. Usually equal to the header size of
+    /// the protocol.
+    const MIN_LEN: usize;
+}
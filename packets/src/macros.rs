@@ -1,108 +1,443 @@
 macro_rules! packet {
+    // Defines the packet type without implementing `Debug`, for types that implement it
+    // themselves, like the ones defined with `packet_layout!`.
+    (@without_debug $name:ident, $mut_name:ident, $min_len:expr) => {
+        packet!(@without_debug $name, $mut_name, $min_len, |_: &[u8]| $min_len);
+    };
+    (@without_debug $name:ident, $mut_name:ident, $min_len:expr, $header_len:expr) => {
+        packet!(@without_debug $name, $mut_name, $min_len, $header_len, |data: &[u8]| data.len());
+    };
+    (@without_debug $name:ident, $mut_name:ident, $min_len:expr, $header_len:expr,
+        $packet_len:expr) => {
+        #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+        pub struct $name<B>(B);
+
+        packet!(@impl $name, $mut_name, $min_len, $header_len, $packet_len);
+    };
     ($name:ident, $mut_name:ident, $min_len:expr) => {
+        packet!($name, $mut_name, $min_len, |_: &[u8]| $min_len);
+    };
+    ($name:ident, $mut_name:ident, $min_len:expr, $header_len:expr) => {
+        packet!($name, $mut_name, $min_len, $header_len, |data: &[u8]| data.len());
+    };
+    ($name:ident, $mut_name:ident, $min_len:expr, $header_len:expr, $packet_len:expr) => {
         #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-        pub struct $name<'a>(&'a [u8]);
-        pub struct $mut_name<'a>(&'a mut [u8]);
+        pub struct $name<B>(B);
+
+        packet!(@impl $name, $mut_name, $min_len, $header_len, $packet_len);
+    };
+    (@impl $name:ident, $mut_name:ident, $min_len:expr, $header_len:expr, $packet_len:expr) => {
+        /// A packet backed by a mutable byte slice. Short for the packet type with a
+        /// `&mut [u8]` buffer, which has both getters and setters.
+        pub type $mut_name<'a> = $name<&'a mut [u8]>;
 
-        impl<'a> $name<'a> {
+        impl<B> ::MinLen for $name<B> {
+            const MIN_LEN: usize = $min_len;
+        }
+
+        // The inherent constant is defined for a concrete buffer type, so it can be used as
+        // `FooPacket::MIN_LEN` without naming a buffer type. Other buffers get it from `MinLen`.
+        impl $name<&[u8]> {
             /// The minimum number of bytes in this type of packet. Usually equal to the
             /// header size of the protocol.
             pub const MIN_LEN: usize = $min_len;
+        }
 
-            /// Creates a new immutable packet based on the given backing slice. Returns `None` if
-            /// the buffer is shorter than the minimum length of this packet.
+        impl<B> $name<B> {
+            /// Creates a new packet based on the given backing buffer without checking its length
+            /// first. If the buffer is too short, a subsequent read from or write to a field will
+            /// panic.
             #[inline]
-            pub fn new(data: &'a [u8]) -> Option<$name<'a>>
-            {
-                if data.len() >= $min_len {
+            pub fn new_unchecked(data: B) -> $name<B> {
+                $name(data)
+            }
+
+            /// Consumes the packet and returns the buffer backing it.
+            #[inline]
+            pub fn into_inner(self) -> B {
+                self.0
+            }
+        }
+
+        impl<B: AsRef<[u8]>> $name<B> {
+            /// Creates a new packet based on the given backing buffer. Returns `None` if the
+            /// buffer is shorter than the minimum length of this packet.
+            #[inline]
+            pub fn new(data: B) -> Option<$name<B>> {
+                if data.as_ref().len() >= $min_len {
                     Some($name(data))
                 } else {
                     None
                 }
             }
 
-            /// Creates a new immutable packet based on the given backing slice without checking
-            /// its length first. If the slice is too short, a subsequent read from a field might
-            /// result in invalid memory access.
+            /// Returns a reference to the bytes backing this packet.
             #[inline]
-            pub unsafe fn new_unchecked(data: &'a [u8]) -> $name<'a> {
-                $name(data)
+            pub fn data(&self) -> &[u8] {
+                self.0.as_ref()
             }
 
-            /// Returns a reference to the slice backing this packet.
+            /// Returns a packet borrowing the same backing bytes immutably.
             #[inline]
-            pub fn data(&self) -> &[u8] {
-                self.0
+            pub fn as_immutable(&self) -> $name<&[u8]> {
+                $name(self.0.as_ref())
+            }
+
+            /// Returns the length of the header in bytes. For protocols with a fixed size header
+            /// this is `MIN_LEN`, otherwise it is computed from the header length field, but never
+            /// less than `MIN_LEN` or more than the length of the backing data.
+            #[inline]
+            pub fn header_len(&self) -> usize {
+                let data = self.0.as_ref();
+                let header_len: usize = ($header_len)(data);
+                ::core::cmp::min(::core::cmp::max(header_len, $min_len), data.len())
+            }
+
+            /// Returns the length of the packet in bytes. For protocols without a length field
+            /// this is the length of the backing data, otherwise it is computed from the length
+            /// field, but never less than `header_len()` or more than the length of the backing
+            /// data.
+            #[inline]
+            pub fn packet_len(&self) -> usize {
+                let data = self.0.as_ref();
+                let packet_len: usize = ($packet_len)(data);
+                ::core::cmp::min(::core::cmp::max(packet_len, self.header_len()), data.len())
             }
 
             /// Returns a slice to the part of the backing data that represents the header.
-            /// This is simply everything up until `min_len()`.
+            /// This is everything up until `header_len()`.
             #[inline]
             pub fn header(&self) -> &[u8] {
-                &self.0[..$min_len]
+                &self.data()[..self.header_len()]
             }
 
-            /// Returns a slice to the payload part of the backing data. This is simply everything
-            /// after the header.
+            /// Returns a slice to the payload part of the backing data. This is everything after
+            /// the header and up until `packet_len()`.
             #[inline]
             pub fn payload(&self) -> &[u8] {
-                &self.0[$min_len..]
+                &self.data()[self.header_len()..self.packet_len()]
+            }
+
+            /// Returns a slice to the part of the backing data after the packet, that is,
+            /// everything after `packet_len()`. For protocols with a length field this is where
+            /// link layer padding and trailers end up. Empty for protocols without one.
+            #[inline]
+            pub fn trailer(&self) -> &[u8] {
+                &self.data()[self.packet_len()..]
             }
         }
 
-        impl<'a> $mut_name<'a> {
-            /// The minimum number of bytes in this type of packet. Usually equal to the
-            /// header size of the protocol.
-            pub const MIN_LEN: usize = $min_len;
+        impl<B: AsRef<[u8]> + AsMut<[u8]>> $name<B> {
+            /// Returns a mutable reference to the bytes backing this packet.
+            #[inline]
+            pub fn data_mut(&mut self) -> &mut [u8] {
+                self.0.as_mut()
+            }
 
-            /// Creates a new mutable packet based on the given backing slice. Returns `None` if
-            /// the buffer is shorter than the minimal length of this packet.
+            /// Returns a packet borrowing the same backing bytes mutably.
             #[inline]
-            pub fn new(data: &'a mut [u8]) -> Option<$mut_name<'a>>
-            {
-                if data.len() >= $min_len {
-                    Some($mut_name(data))
-                } else {
-                    None
-                }
+            pub fn as_mutable(&mut self) -> $name<&mut [u8]> {
+                $name(self.0.as_mut())
             }
 
-            /// Creates a new mutable packet based on the given backing slice without checking
-            /// its length first. If the slice is too short, a subsequent read from a field might
-            /// result in invalid memory access.
+            /// Returns a mutable slice to the part of the backing data that represents the header.
+            /// This is everything up until `header_len()`.
             #[inline]
-            pub unsafe fn new_unchecked(data: &'a mut [u8]) -> $mut_name<'a> {
-                $mut_name(data)
+            pub fn header_mut(&mut self) -> &mut [u8] {
+                let header_len = self.header_len();
+                &mut self.data_mut()[..header_len]
             }
 
-            /// Returns an immutable version of the same packet and backed by the same byte slice.
-            /// Used to access the getters.
+            /// Returns a mutable slice to the payload part of the backing data. This is everything
+            /// after the header and up until `packet_len()`.
             #[inline]
-            pub fn as_immutable(&'a self) -> $name<'a> {
-                $name(&self.0[..])
+            pub fn payload_mut(&mut self) -> &mut [u8] {
+                let header_len = self.header_len();
+                let packet_len = self.packet_len();
+                &mut self.data_mut()[header_len..packet_len]
             }
 
-            /// Returns a mutable reference to the slice backing this packet.
+            /// Returns a mutable slice to the part of the backing data after the packet. See
+            /// `trailer()`.
             #[inline]
-            pub fn data(&mut self) -> &mut [u8] {
-                self.0
+            pub fn trailer_mut(&mut self) -> &mut [u8] {
+                let packet_len = self.packet_len();
+                &mut self.data_mut()[packet_len..]
             }
+        }
+    }
+}
 
-            /// Returns a mutable slice to the part of the backing data that represents the header.
-            /// This is simply everything up until `min_len()`.
+/// Defines a packet type from a table of its header fields. Generates the packet type with
+/// `packet!`, where `MIN_LEN` is the number of bytes needed to hold all fields, a getter and a
+/// setter for each field, the listing `FooPacket::FIELDS` and a test module named by `tests`
+/// that sets every field to all ones and checks that exactly the bits of the field were set.
+/// The generated `Debug` implementation lists the fields when formatted with `{:#?}`, and
+/// `fmt_field(name, f)` writes a single field by name.
+///
+/// Each field is given as `getter / setter: Type = bit_offset, bit_width, endianness;` where
+/// the endianness is `be` or `le`. The type must implement `layout::LayoutValue`. A field marked
+/// with `[manual]` gets no generated accessors, so a hand-optimized getter and setter with the
+/// same names can be written with `getters!` and `setters!` instead. Manual fields are still
+/// listed and tested.
+///
+/// ```ignore
+/// packet_layout! {
+///     packet: FooPacket, MutFooPacket;
+///     header_len: |data: &[u8]| usize::from(data[0] & 0x0f) * 4;
+///     packet_len: |data: &[u8]| usize::from(u16::from_be_bytes([data[2], data[3]]));
+///     tests: foo_layout_tests;
+///     fields: {
+///         version / set_version: u4 = 0, 4, be;
+///         [manual]
+///         length / set_length: u16 = 16, 16, be;
+///     }
+/// }
+/// ```
+macro_rules! packet_layout {
+    (@accessors $name:ident [manual] $($rest:tt)*) => {};
+    (@accessors $name:ident [] [$(#[$meta:meta])*]
+        $get:ident / $set:ident: $type:ty = $offset:expr, $width:expr, $endian:ident) => {
+        impl<B: AsRef<[u8]>> $name<B> {
+            $(#[$meta])*
             #[inline]
-            pub fn header(&mut self) -> &mut [u8] {
-                &mut self.0[..$min_len]
+            pub fn $get(&self) -> $type {
+                let endianness = packet_layout!(@endianness $endian);
+                let raw = ::layout::read_bits(self.data(), $offset, $width, endianness);
+                <$type as ::layout::LayoutValue>::from_raw(raw)
             }
+        }
 
-            /// Returns a mutable slice to the payload part of the backing data. This is simply
-            /// everything after the header.
+        impl<B: AsRef<[u8]> + AsMut<[u8]>> $name<B> {
             #[inline]
-            pub fn payload(&mut self) -> &mut [u8] {
-                &mut self.0[$min_len..]
+            pub fn $set(&mut self, value: $type) {
+                let endianness = packet_layout!(@endianness $endian);
+                let raw = ::layout::LayoutValue::into_raw(value);
+                ::layout::write_bits(self.data_mut(), $offset, $width, endianness, raw);
             }
         }
-    }
+    };
+    (@endianness be) => { ::layout::Endianness::Big };
+    (@endianness le) => { ::layout::Endianness::Little };
+    (
+        packet: $name:ident, $mut_name:ident;
+        $(header_len: $header_len:expr; $(packet_len: $packet_len:expr;)?)?
+        tests: $tests:ident;
+        fields: {$(
+            $(#[$meta:meta])*
+            $([$manual:ident])?
+            $get:ident / $set:ident: $type:ty = $offset:expr, $width:expr, $endian:ident;
+        )*}
+    ) => {
+        packet!(
+            @without_debug
+            $name,
+            $mut_name,
+            {
+                const LEN: usize = ::layout::min_len($name::FIELDS);
+                LEN
+            }
+            $(, $header_len $(, $packet_len)?)?
+        );
+
+        impl $name<&[u8]> {
+            /// The header fields of this packet type, in order.
+            pub const FIELDS: &'static [::layout::FieldInfo] = &[$(
+                ::layout::FieldInfo {
+                    name: stringify!($get),
+                    bit_offset: $offset,
+                    bit_width: $width,
+                    endianness: packet_layout!(@endianness $endian),
+                },
+            )*];
+        }
+
+        $(
+            packet_layout!(@accessors $name [$($manual)?] [$(#[$meta])*]
+                $get / $set: $type = $offset, $width, $endian);
+        )*
+
+        impl<B: AsRef<[u8]>> $name<B> {
+            /// Writes the value of the field with the given name in `FIELDS`, see
+            /// `LayoutValue::fmt_value()`. Writes nothing for names not in `FIELDS`.
+            pub fn fmt_field(
+                &self,
+                name: &str,
+                f: &mut ::core::fmt::Formatter,
+            ) -> ::core::fmt::Result {
+                match name {
+                    $(stringify!($get) => ::layout::LayoutValue::fmt_value(&self.$get(), f),)*
+                    _ => Ok(()),
+                }
+            }
+        }
+
+        impl<B: AsRef<[u8]>> ::core::fmt::Debug for $name<B> {
+            /// Writes the raw bytes of the packet, or with the alternate flag, `{:#?}`, the
+            /// value of every header field followed by the payload.
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                if f.alternate() {
+                    f.debug_struct(stringify!($name))
+                        $(.field(stringify!($get), &self.$get()))*
+                        .field("payload", &self.payload())
+                        .finish()
+                } else {
+                    f.debug_tuple(stringify!($name)).field(&self.data()).finish()
+                }
+            }
+        }
+
+        #[cfg(test)]
+        mod $tests {
+            use super::*;
+
+            #[test]
+            fn fields() {
+                ::layout::assert_valid_fields($name::FIELDS);
+            }
+
+            $(
+                #[test]
+                fn $get() {
+                    let field = $name::FIELDS.iter().find(|f| f.name == stringify!($get)).unwrap();
+                    let value = <$type as ::layout::LayoutValue>::from_raw(::layout::mask($width));
+                    for &packet_offset in &[0, 1, 3, 7] {
+                        let mut backing_data = [0; 1024];
+                        {
+                            let data = &mut backing_data[packet_offset..];
+                            let mut testee = $name::new(data).unwrap();
+                            testee.$set(value);
+                            assert_eq!(value, testee.$get());
+                        }
+                        assert!(backing_data[..packet_offset].iter().all(|&v| v == 0x00));
+                        ::layout::assert_only_field_set(&backing_data[packet_offset..], field);
+                    }
+                }
+            )*
+        }
+    };
+}
+
+/// Generates `new_checked()` for a packet type that has a `validate()` method returning
+/// `Result<(), $error>`. The error type must have a `Truncated` variant, which is returned when
+/// the buffer is shorter than `MIN_LEN`.
+macro_rules! new_checked {
+    ($name:ident, $error:ident) => {
+        impl<B: AsRef<[u8]>> $name<B> {
+            /// Creates a new packet like `new()` and then checks that it is well formed with
+            /// `validate()`.
+            pub fn new_checked(data: B) -> Result<$name<B>, $error> {
+                let packet = $name::new(data).ok_or($error::Truncated)?;
+                packet.validate()?;
+                Ok(packet)
+            }
+        }
+    };
+}
+
+/// Implements `layout::LayoutValue` for a newtype around an unsigned integer, so the newtype can
+/// be used as the type of a `packet_layout!` field. Field listings show the value with its
+/// `Debug` implementation, or with the formatting trait given as a third argument, like
+/// `newtype_layout_value!(EtherType, u16, Display)`.
+macro_rules! newtype_layout_value {
+    ($type:ident, $raw:ident) => {
+        newtype_layout_value!($type, $raw, Debug);
+    };
+    ($type:ident, $raw:ident, $fmt_trait:ident) => {
+        impl ::layout::LayoutValue for $type {
+            #[inline(always)]
+            fn from_raw(raw: u64) -> Self {
+                $type(raw as $raw)
+            }
+
+            #[inline(always)]
+            fn into_raw(self) -> u64 {
+                u64::from(self.0)
+            }
+
+            fn fmt_value(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                ::core::fmt::$fmt_trait::fmt(self, f)
+            }
+        }
+    };
+}
+
+/// Defines associated constants for a newtype around an integer, together with `name()` and
+/// `from_name()` mapping the constants to and from their names, and a `FromStr` implementation
+/// that accepts the names as well as numbers. Every value and every name must be unique.
+///
+/// The inner integer type and an error type for `FromStr` are given after the type. The error
+/// type is defined by the macro and displays as the given message.
+///
+/// ```rust,ignore
+/// named_values!(Protocol, u8, ProtocolParseError, "Unknown IP protocol", {
+///     /// Transmission Control Protocol
+///     TCP = 6, "TCP";
+///     UDP = 17, "UDP";
+/// });
+/// ```
+macro_rules! named_values {
+    ($type:ident, $inner:ident, $error:ident, $message:expr, {
+        $($(#[$doc:meta])* $name:ident = $value:expr, $text:expr;)*
+    }) => {
+        impl $type {
+            $(
+                $(#[$doc])*
+                pub const $name: $type = $type($value);
+            )*
+
+            /// Returns the name of this value if it is one of the associated constants.
+            pub fn name(&self) -> Option<&'static str> {
+                match *self {
+                    $($type::$name => Some($text),)*
+                    _ => None,
+                }
+            }
+
+            /// Returns the associated constant with the given name, ignoring ASCII case. This is
+            /// the inverse of `name()`.
+            pub fn from_name(name: &str) -> Option<$type> {
+                $(
+                    if name.eq_ignore_ascii_case($text) {
+                        return Some($type::$name);
+                    }
+                )*
+                None
+            }
+        }
+
+        /// Parses a value from its name, ignoring ASCII case, or from its number in decimal or
+        /// `0x` prefixed hexadecimal.
+        impl ::core::str::FromStr for $type {
+            type Err = $error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                if let Some(value) = $type::from_name(s) {
+                    return Ok(value);
+                }
+                let number = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                    Some(hex) => $inner::from_str_radix(hex, 16),
+                    None => s.parse::<$inner>(),
+                };
+                number.map($type).map_err(|_| $error)
+            }
+        }
+
+        #[doc = concat!(
+            "Error returned when parsing a `", stringify!($type), "` from a string that is ",
+            "neither one of its names nor a number."
+        )]
+        #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+        pub struct $error;
+
+        impl ::core::fmt::Display for $error {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                write!(f, $message)
+            }
+        }
+
+        impl ::core::error::Error for $error {}
+    };
 }
 
 macro_rules! getters {
@@ -111,7 +446,7 @@ macro_rules! getters {
         $(#[$doc: meta])*
         pub fn $name:ident(&$selff:ident) -> $type:ty $body:block
     )*) => {
-        impl<'a> $pkg<'a> {
+        impl<B: AsRef<[u8]>> $pkg<B> {
             $($(#[$doc])*
             #[inline]
             pub fn $name(&$selff) -> $type {
@@ -127,7 +462,7 @@ macro_rules! setters {
         $(#[$doc: meta])*
         pub fn $name:ident(&mut $selff:ident, $arg:ident: $type:ty) $body:block
     )*) => {
-        impl<'a> $pkg<'a> {
+        impl<B: AsRef<[u8]> + AsMut<[u8]>> $pkg<B> {
             $($(#[$doc])*
             #[inline]
             pub fn $name(&mut $selff, $arg: $type) {
@@ -138,23 +473,84 @@ macro_rules! setters {
 }
 
 
+/// A field type that can be read from and written to any offset in a byte slice, regardless of
+/// alignment. Used by `read_offset!` and `write_offset!`. Integers are loaded and stored in
+/// native byte order, like dereferencing a pointer would, so the byte order conversion is up to
+/// the caller. Copying through byte arrays compiles down to plain unaligned loads and stores on
+/// platforms that support them.
+pub trait Field: Sized {
+    /// Reads the field from the start of `data`. Panics if `data` is too short.
+    fn read(data: &[u8]) -> Self;
+
+    /// Writes the field to the start of `data`. Panics if `data` is too short.
+    fn write(self, data: &mut [u8]);
+}
+
+impl Field for u8 {
+    #[inline]
+    fn read(data: &[u8]) -> Self {
+        data[0]
+    }
+
+    #[inline]
+    fn write(self, data: &mut [u8]) {
+        data[0] = self;
+    }
+}
+
+impl<const N: usize> Field for [u8; N] {
+    #[inline]
+    fn read(data: &[u8]) -> Self {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(&data[..N]);
+        bytes
+    }
+
+    #[inline]
+    fn write(self, data: &mut [u8]) {
+        data[..N].copy_from_slice(&self);
+    }
+}
+
+macro_rules! impl_integer_field {
+    ($($type:ident),*) => {$(
+        impl Field for $type {
+            #[inline]
+            fn read(data: &[u8]) -> Self {
+                $type::from_ne_bytes(Field::read(data))
+            }
+
+            #[inline]
+            fn write(self, data: &mut [u8]) {
+                self.to_ne_bytes().write(data)
+            }
+        }
+    )*}
+}
+
+impl_integer_field!(u16, u32, u64);
+
 macro_rules! read_offset {
     ($buff:expr, $offset:expr, $type:ty) => {{
-        let ptr = &$buff[$offset];
-        unsafe { *(ptr as *const _ as *const $type) }
+        <$type as ::macros::Field>::read(&$buff[$offset..])
     }};
     ($buff:expr, $offset:expr, $type:ident, from_be) => {{
         $type::from_be(read_offset!($buff, $offset, $type))
+    }};
+    ($buff:expr, $offset:expr, $type:ident, from_le) => {{
+        $type::from_le(read_offset!($buff, $offset, $type))
     }}
 }
 
 macro_rules! write_offset {
     ($buff:expr, $offset:expr, $value:expr, $type:ty) => {{
-        let ptr = (&mut $buff[$offset]) as *mut _ as *mut $type;
-        unsafe { *ptr = $value };
+        <$type as ::macros::Field>::write($value, &mut $buff[$offset..])
     }};
     ($buff:expr, $offset:expr, $value:expr, $type:ident, to_be) => {{
         write_offset!($buff, $offset, $type::to_be($value), $type)
+    }};
+    ($buff:expr, $offset:expr, $value:expr, $type:ident, to_le) => {{
+        write_offset!($buff, $offset, $type::to_le($value), $type)
     }}
 }
 
@@ -162,7 +558,8 @@ macro_rules! write_offset {
 /// `$packet`. First calls `$set_name` with `$value`. Then makes sure the
 /// `$name` getter returns `$value` again. Lastly it checks so that the only
 /// bits in the backing buffer that are non-zero are at `$offset` and contain
-/// `$expected`.
+/// `$expected`. This is repeated with the packet starting at a few odd offsets
+/// into the backing buffer, to make sure fields can be accessed unaligned.
 #[allow(unused_macros)]
 macro_rules! setget_test {
     ($packet:ident,
@@ -173,19 +570,67 @@ macro_rules! setget_test {
      $expected:expr) => {
         #[test]
         fn $name() {
-            let mut backing_data = [0; 1024];
-            {
-                let mut testee = $packet::new(&mut backing_data).unwrap();
-                testee.$set_name($value);
-                // Check that the getter returns the same value
-                assert_eq!($value, testee.as_immutable().$name());
-            }
-            // Check that only the intended bytes were affected
-            let start = $offset;
-            let end = $offset + $expected.len();
-            assert!(backing_data[0..start].iter().all(|&v| v == 0x00));
-            assert_eq!($expected, backing_data[start..end]);
-            assert!(backing_data[end..].iter().all(|&v| v == 0x00));
+            for &packet_offset in &[0, 1, 3, 7] {
+                let mut backing_data = [0; 1024];
+                {
+                    let mut testee = $packet::new(&mut backing_data[packet_offset..]).unwrap();
+                    testee.$set_name($value);
+                    // Check that the getter returns the same value
+                    assert_eq!($value, testee.as_immutable().$name());
+                }
+                // Check that only the intended bytes were affected
+                let start = packet_offset + $offset;
+                let end = start + $expected.len();
+                assert!(backing_data[0..start].iter().all(|&v| v == 0x00));
+                assert_eq!($expected, backing_data[start..end]);
+                assert!(backing_data[end..].iter().all(|&v| v == 0x00));
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    static DATA: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+
+    #[test]
+    fn read_unaligned() {
+        for offset in 0..8 {
+            let base = offset as u8;
+            assert_eq!(base, read_offset!(DATA, offset, u8));
+            assert_eq!(
+                u16::from_be_bytes([base, base + 1]),
+                read_offset!(DATA, offset, u16, from_be)
+            );
+            assert_eq!(
+                u32::from_be_bytes([base, base + 1, base + 2, base + 3]),
+                read_offset!(DATA, offset, u32, from_be)
+            );
+            assert_eq!([base, base + 1, base + 2], read_offset!(DATA, offset, [u8; 3]));
+        }
+    }
+
+    #[test]
+    fn write_unaligned() {
+        for offset in 0..8 {
+            let mut data = [0; 16];
+            write_offset!(data, offset, 0x0102, u16, to_be);
+            write_offset!(data, offset + 2, 0x0304_0506, u32, to_be);
+            write_offset!(data, offset + 6, [7, 8], [u8; 2]);
+            write_offset!(data, offset + 8, 9, u8);
+            assert_eq!([1, 2, 3, 4, 5, 6, 7, 8, 9], data[offset..offset + 9]);
+            assert!(data[..offset].iter().all(|&v| v == 0));
+            assert!(data[offset + 9..].iter().all(|&v| v == 0));
         }
     }
+
+    #[test]
+    #[should_panic]
+    fn read_out_of_bounds() {
+        let _ = read_offset!(DATA, 15, u16, from_be);
+    }
 }
@@ -0,0 +1,637 @@
+//! Reading and writing capture files in the classic libpcap format.
+//!
+//! A pcap file is a file header followed by one record per captured packet. [`PcapReader`] reads
+//! a whole file from a byte slice and hands out the records without copying, in either byte
+//! order and with microsecond or nanosecond timestamps. [`PcapWriter`], which requires the `std`
+//! feature, writes records to any `io::Write`.
+//!
+//! ```rust
+//! extern crate rips_packets;
+//!
+//! # #[cfg(feature = "std")]
+//! # mod example {
+//! use rips_packets::pcap::{LinkType, PcapReader, PcapWriter};
+//! use std::time::Duration;
+//!
+//! # pub fn main() {
+//!     let mut writer = PcapWriter::new(Vec::new(), LinkType::ETHERNET).unwrap();
+//!     writer.write_packet(Duration::from_millis(1500), &[0xff; 60]).unwrap();
+//!     let file = writer.into_inner();
+//!
+//!     let reader = PcapReader::new(&file).unwrap();
+//!     for record in reader.records() {
+//!         let record = record.unwrap();
+//!         let ethernet = record.ethernet().unwrap();
+//!         println!("{:?} {}", record.timestamp(), ethernet);
+//!     }
+//! # }
+//! # }
+//! #
+//! # fn main() {
+//! #     #[cfg(feature = "std")]
+//! #     example::main();
+//! # }
+//! ```
+//!
+//! [`PcapReader`]: struct.PcapReader.html
+//! [`PcapWriter`]: struct.PcapWriter.html
+
+use core::error::Error;
+use core::fmt;
+use core::time::Duration;
+use ethernet::{EtherType, EthernetPacket};
+use layout::Endianness;
+#[cfg(feature = "std")]
+use std::io;
+
+/// The magic number of files with microsecond timestamps, in the byte order of the writer.
+pub const MAGIC_MICROSECONDS: u32 = 0xa1b2_c3d4;
+/// The magic number of files with nanosecond timestamps, in the byte order of the writer.
+pub const MAGIC_NANOSECONDS: u32 = 0xa1b2_3c4d;
+
+/// The length of the file header.
+pub const HEADER_LEN: usize = 24;
+/// The length of the header before each record.
+pub const RECORD_HEADER_LEN: usize = 16;
+
+/// The link layer type of the packets in a capture, see [the list of link types].
+///
+/// [the list of link types]: https://www.tcpdump.org/linktypes.html
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct LinkType(pub u32);
+
+impl LinkType {
+    /// Ethernet frames, without the frame check sequence.
+    pub const ETHERNET: LinkType = LinkType(1);
+    /// IPv4 or IPv6 packets without a link layer header.
+    pub const RAW: LinkType = LinkType(101);
+    /// Linux "cooked" captures, with a `LinuxSllPacket` header instead of the link layer header.
+    pub const LINUX_SLL: LinkType = LinkType(113);
+
+    #[inline]
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+/// The resolution of the timestamps of a capture.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TimestampResolution {
+    Microseconds,
+    Nanoseconds,
+}
+
+impl TimestampResolution {
+    /// Returns the number of nanoseconds per unit of the sub-second part of a timestamp.
+    fn nanos_per_unit(&self) -> u32 {
+        match *self {
+            TimestampResolution::Microseconds => 1000,
+            TimestampResolution::Nanoseconds => 1,
+        }
+    }
+}
+
+/// The fields of a pcap file header. The time zone and accuracy fields are always zero in
+/// practice and are not represented.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PcapHeader {
+    pub byte_order: Endianness,
+    pub resolution: TimestampResolution,
+    pub version_major: u16,
+    pub version_minor: u16,
+    /// The maximum number of bytes stored of each packet. Longer packets are truncated.
+    pub snaplen: u32,
+    pub link_type: LinkType,
+}
+
+impl PcapHeader {
+    /// Returns a header for a capture of the given link type in the byte order of this machine,
+    /// with microsecond timestamps, version 2.4 and a snaplen of 65535.
+    pub fn new(link_type: LinkType) -> PcapHeader {
+        PcapHeader {
+            byte_order: native_byte_order(),
+            resolution: TimestampResolution::Microseconds,
+            version_major: 2,
+            version_minor: 4,
+            snaplen: 65535,
+            link_type,
+        }
+    }
+
+    /// Reads the file header at the start of `data`.
+    pub fn parse(data: &[u8]) -> Result<PcapHeader, PcapError> {
+        if data.len() < HEADER_LEN {
+            return Err(PcapError::Truncated);
+        }
+        let magic = read_offset!(data, 0, [u8; 4]);
+        let (byte_order, resolution) = if magic == MAGIC_MICROSECONDS.to_be_bytes() {
+            (Endianness::Big, TimestampResolution::Microseconds)
+        } else if magic == MAGIC_MICROSECONDS.to_le_bytes() {
+            (Endianness::Little, TimestampResolution::Microseconds)
+        } else if magic == MAGIC_NANOSECONDS.to_be_bytes() {
+            (Endianness::Big, TimestampResolution::Nanoseconds)
+        } else if magic == MAGIC_NANOSECONDS.to_le_bytes() {
+            (Endianness::Little, TimestampResolution::Nanoseconds)
+        } else {
+            return Err(PcapError::Magic);
+        };
+        Ok(PcapHeader {
+            byte_order,
+            resolution,
+            version_major: read_u16(data, 4, byte_order),
+            version_minor: read_u16(data, 6, byte_order),
+            snaplen: read_u32(data, 16, byte_order),
+            link_type: LinkType(read_u32(data, 20, byte_order)),
+        })
+    }
+
+    /// Returns the header as it is written to a file.
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let magic = match self.resolution {
+            TimestampResolution::Microseconds => MAGIC_MICROSECONDS,
+            TimestampResolution::Nanoseconds => MAGIC_NANOSECONDS,
+        };
+        let mut bytes = [0; HEADER_LEN];
+        write_u32(&mut bytes, 0, magic, self.byte_order);
+        write_u16(&mut bytes, 4, self.version_major, self.byte_order);
+        write_u16(&mut bytes, 6, self.version_minor, self.byte_order);
+        write_u32(&mut bytes, 16, self.snaplen, self.byte_order);
+        write_u32(&mut bytes, 20, self.link_type.value(), self.byte_order);
+        bytes
+    }
+}
+
+/// Reads a pcap file stored in a byte slice.
+#[derive(Debug, Copy, Clone)]
+pub struct PcapReader<'a> {
+    header: PcapHeader,
+    records: &'a [u8],
+}
+
+impl<'a> PcapReader<'a> {
+    /// Parses the file header of the capture file in `data`. The records are parsed lazily by
+    /// `records()`.
+    pub fn new(data: &'a [u8]) -> Result<PcapReader<'a>, PcapError> {
+        Ok(PcapReader {
+            header: PcapHeader::parse(data)?,
+            records: &data[HEADER_LEN..],
+        })
+    }
+
+    pub fn header(&self) -> &PcapHeader {
+        &self.header
+    }
+
+    /// Returns an iterator over the records in the file.
+    pub fn records(&self) -> Records<'a> {
+        Records {
+            header: self.header,
+            data: self.records,
+        }
+    }
+}
+
+/// Iterator over the records of a pcap file. Created with `PcapReader::records()`.
+///
+/// A record that does not fit in the rest of the file is returned as `PcapError::Truncated`,
+/// which ends the iteration. This is common for captures that were cut off while being written.
+#[derive(Debug, Copy, Clone)]
+pub struct Records<'a> {
+    header: PcapHeader,
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<Record<'a>, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = self.data;
+        let byte_order = self.header.byte_order;
+        if data.len() < RECORD_HEADER_LEN {
+            self.data = &[];
+            return Some(Err(PcapError::Truncated));
+        }
+        let included_len = read_u32(data, 8, byte_order) as usize;
+        // The sum can overflow on 32 bit targets
+        let end = match RECORD_HEADER_LEN.checked_add(included_len) {
+            Some(end) if end <= data.len() => end,
+            _ => {
+                self.data = &[];
+                return Some(Err(PcapError::Truncated));
+            }
+        };
+        self.data = &data[end..];
+        let nanos = read_u32(data, 4, byte_order)
+            .saturating_mul(self.header.resolution.nanos_per_unit());
+        Some(Ok(Record {
+            link_type: self.header.link_type,
+            timestamp: Duration::new(u64::from(read_u32(data, 0, byte_order)), nanos),
+            original_len: read_u32(data, 12, byte_order) as usize,
+            data: &data[RECORD_HEADER_LEN..end],
+        }))
+    }
+}
+
+/// One captured packet.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Record<'a> {
+    link_type: LinkType,
+    timestamp: Duration,
+    original_len: usize,
+    data: &'a [u8],
+}
+
+impl<'a> Record<'a> {
+    /// Creates a record of the given link type, for captures in other formats.
+    pub fn new(
+        link_type: LinkType,
+        timestamp: Duration,
+        original_len: usize,
+        data: &'a [u8],
+    ) -> Record<'a> {
+        Record {
+            link_type,
+            timestamp,
+            original_len,
+            data,
+        }
+    }
+
+    /// Returns the link type of the capture, which tells how to interpret `data()`.
+    pub fn link_type(&self) -> LinkType {
+        self.link_type
+    }
+
+    /// Returns the time the packet was captured, as time since the Unix epoch.
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    /// Returns the length of the packet on the wire. This is longer than `data()` if the packet
+    /// was truncated to the snaplen of the capture.
+    pub fn original_len(&self) -> usize {
+        self.original_len
+    }
+
+    /// Returns true if only a part of the packet was captured.
+    pub fn is_truncated(&self) -> bool {
+        self.data.len() < self.original_len
+    }
+
+    /// Returns the captured bytes of the packet, starting with the link layer header.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the Ethernet frame of a record in an Ethernet capture. Returns `None` for other
+    /// link types and for records too short to hold an Ethernet header.
+    pub fn ethernet(&self) -> Option<EthernetPacket<&'a [u8]>> {
+        if self.link_type == LinkType::ETHERNET {
+            EthernetPacket::new(self.data)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the network layer protocol and packet, skipping the link layer header of any of
+    /// the supported link types. VLAN tags in Ethernet captures are skipped as well. For raw IP
+    /// captures the protocol is taken from the IP version. Returns `None` for unsupported link
+    /// types and records too short for their link layer header.
+    pub fn network(&self) -> Option<(EtherType, &'a [u8])> {
+        match self.link_type {
+            LinkType::ETHERNET => {
+                let ethernet = EthernetPacket::new(self.data)?;
+                let header_len = ethernet.effective_header_len();
+                Some((ethernet.effective_ether_type(), &self.data[header_len..]))
+            }
+            LinkType::RAW => match self.data.first()? >> 4 {
+                4 => Some((EtherType::IPV4, self.data)),
+                6 => Some((EtherType::IPV6, self.data)),
+                _ => None,
+            },
+            LinkType::LINUX_SLL => {
+                let sll = LinuxSllPacket::new(self.data)?;
+                Some((sll.protocol(), &self.data[LinuxSllPacket::MIN_LEN..]))
+            }
+            _ => None,
+        }
+    }
+}
+
+
+packet!(LinuxSllPacket, MutLinuxSllPacket, 16);
+
+getters!(LinuxSllPacket
+    /// Returns the direction of the packet, 0 for packets sent to this host and 4 for packets
+    /// sent by it. See `LinuxSllPacket::HOST` and the other constants.
+    pub fn packet_type(&self) -> u16 {
+        read_offset!(self.data(), 0, u16, from_be)
+    }
+
+    /// Returns the ARPHRD type of the interface, 1 for Ethernet.
+    pub fn arphrd_type(&self) -> u16 {
+        read_offset!(self.data(), 2, u16, from_be)
+    }
+
+    pub fn address_length(&self) -> u16 {
+        read_offset!(self.data(), 4, u16, from_be)
+    }
+
+    /// Returns the link layer source address, padded with zeros to eight bytes.
+    pub fn address(&self) -> [u8; 8] {
+        read_offset!(self.data(), 6, [u8; 8])
+    }
+
+    /// Returns the protocol of the payload. For most interfaces this is an EtherType.
+    pub fn protocol(&self) -> EtherType {
+        EtherType(read_offset!(self.data(), 14, u16, from_be))
+    }
+);
+
+impl LinuxSllPacket<&[u8]> {
+    /// Packet type of packets addressed to this host.
+    pub const HOST: u16 = 0;
+    /// Packet type of broadcast packets.
+    pub const BROADCAST: u16 = 1;
+    /// Packet type of multicast packets.
+    pub const MULTICAST: u16 = 2;
+    /// Packet type of packets addressed to another host.
+    pub const OTHERHOST: u16 = 3;
+    /// Packet type of packets sent by this host.
+    pub const OUTGOING: u16 = 4;
+}
+
+setters!(LinuxSllPacket
+    pub fn set_packet_type(&mut self, packet_type: u16) {
+        write_offset!(self.data_mut(), 0, packet_type, u16, to_be);
+    }
+
+    pub fn set_arphrd_type(&mut self, arphrd_type: u16) {
+        write_offset!(self.data_mut(), 2, arphrd_type, u16, to_be);
+    }
+
+    pub fn set_address_length(&mut self, address_length: u16) {
+        write_offset!(self.data_mut(), 4, address_length, u16, to_be);
+    }
+
+    pub fn set_address(&mut self, address: [u8; 8]) {
+        write_offset!(self.data_mut(), 6, address, [u8; 8]);
+    }
+
+    pub fn set_protocol(&mut self, protocol: EtherType) {
+        write_offset!(self.data_mut(), 14, protocol.value(), u16, to_be);
+    }
+);
+
+
+/// Writes a pcap file to an `io::Write`. Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct PcapWriter<W: io::Write> {
+    header: PcapHeader,
+    writer: W,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> PcapWriter<W> {
+    /// Writes the file header for a capture of the given link type, with the defaults of
+    /// `PcapHeader::new()`.
+    pub fn new(writer: W, link_type: LinkType) -> io::Result<PcapWriter<W>> {
+        PcapWriter::with_header(writer, PcapHeader::new(link_type))
+    }
+
+    /// Writes the given file header. All records are written in its byte order and timestamp
+    /// resolution.
+    pub fn with_header(mut writer: W, header: PcapHeader) -> io::Result<PcapWriter<W>> {
+        writer.write_all(&header.to_bytes())?;
+        Ok(PcapWriter { header, writer })
+    }
+
+    pub fn header(&self) -> &PcapHeader {
+        &self.header
+    }
+
+    /// Writes a record with the given packet, captured at `timestamp` since the Unix epoch. The
+    /// packet is truncated to the snaplen of the file. Timestamps are truncated to the resolution
+    /// of the file and must fit in 32 bit seconds.
+    pub fn write_packet(&mut self, timestamp: Duration, data: &[u8]) -> io::Result<()> {
+        let seconds = timestamp.as_secs();
+        if seconds > u64::from(u32::MAX) || data.len() > u32::MAX as usize {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        let included_len = ::core::cmp::min(data.len(), self.header.snaplen as usize);
+        let fraction = timestamp.subsec_nanos() / self.header.resolution.nanos_per_unit();
+        let byte_order = self.header.byte_order;
+        let mut record_header = [0; RECORD_HEADER_LEN];
+        write_u32(&mut record_header, 0, seconds as u32, byte_order);
+        write_u32(&mut record_header, 4, fraction, byte_order);
+        write_u32(&mut record_header, 8, included_len as u32, byte_order);
+        write_u32(&mut record_header, 12, data.len() as u32, byte_order);
+        self.writer.write_all(&record_header)?;
+        self.writer.write_all(&data[..included_len])
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+
+/// Returns the byte order of this machine, the byte order captures are written in.
+pub(crate) fn native_byte_order() -> Endianness {
+    if cfg!(target_endian = "big") {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    }
+}
+
+// Helpers for the fields of capture files, which are in the byte order of the writer. Shared
+// with the pcapng module.
+
+pub(crate) fn read_u16(data: &[u8], offset: usize, byte_order: Endianness) -> u16 {
+    match byte_order {
+        Endianness::Big => read_offset!(data, offset, u16, from_be),
+        Endianness::Little => read_offset!(data, offset, u16, from_le),
+    }
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize, byte_order: Endianness) -> u32 {
+    match byte_order {
+        Endianness::Big => read_offset!(data, offset, u32, from_be),
+        Endianness::Little => read_offset!(data, offset, u32, from_le),
+    }
+}
+
+pub(crate) fn write_u16(data: &mut [u8], offset: usize, value: u16, byte_order: Endianness) {
+    match byte_order {
+        Endianness::Big => write_offset!(data, offset, value, u16, to_be),
+        Endianness::Little => write_offset!(data, offset, value, u16, to_le),
+    }
+}
+
+pub(crate) fn write_u32(data: &mut [u8], offset: usize, value: u32, byte_order: Endianness) {
+    match byte_order {
+        Endianness::Big => write_offset!(data, offset, value, u32, to_be),
+        Endianness::Little => write_offset!(data, offset, value, u32, to_le),
+    }
+}
+
+pub(crate) fn read_u64(data: &[u8], offset: usize, byte_order: Endianness) -> u64 {
+    match byte_order {
+        Endianness::Big => read_offset!(data, offset, u64, from_be),
+        Endianness::Little => read_offset!(data, offset, u64, from_le),
+    }
+}
+
+/// Only used by `pcapng::PcapngWriter`.
+#[cfg(feature = "std")]
+pub(crate) fn write_u64(data: &mut [u8], offset: usize, value: u64, byte_order: Endianness) {
+    match byte_order {
+        Endianness::Big => write_offset!(data, offset, value, u64, to_be),
+        Endianness::Little => write_offset!(data, offset, value, u64, to_le),
+    }
+}
+
+/// Error returned when a capture file can not be read.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PcapError {
+    /// The file ended in the middle of the file header or a record.
+    Truncated,
+    /// The file does not start with one of the pcap magic numbers.
+    Magic,
+}
+
+impl fmt::Display for PcapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PcapError::Truncated => write!(f, "Capture file truncated"),
+            PcapError::Magic => write!(f, "Not a pcap file"),
+        }
+    }
+}
+
+impl Error for PcapError {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    setget_test!(MutLinuxSllPacket, packet_type, set_packet_type, 4, 0, [0, 4]);
+    setget_test!(MutLinuxSllPacket, arphrd_type, set_arphrd_type, 0xabcd, 2, [0xab, 0xcd]);
+    setget_test!(MutLinuxSllPacket, address_length, set_address_length, 6, 4, [0, 6]);
+    setget_test!(MutLinuxSllPacket, address, set_address, [1; 8], 6, [1; 8]);
+    setget_test!(MutLinuxSllPacket, protocol, set_protocol, EtherType::IPV6, 14, [0x86, 0xdd]);
+
+    /// A big endian file with nanosecond timestamps and one raw IPv4 record of 20 bytes, of
+    /// which 4 were captured.
+    const BIG_ENDIAN_NANOS: [u8; 44] = [
+        0xa1, 0xb2, 0x3c, 0x4d, 0, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 101,
+        0, 0, 0, 10, 0, 0, 0, 20, 0, 0, 0, 4, 0, 0, 0, 20,
+        0x45, 0, 0, 20,
+    ];
+
+    #[test]
+    fn big_endian_nanoseconds() {
+        let reader = PcapReader::new(&BIG_ENDIAN_NANOS).unwrap();
+        let header = reader.header();
+        assert_eq!(Endianness::Big, header.byte_order);
+        assert_eq!(TimestampResolution::Nanoseconds, header.resolution);
+        assert_eq!((2, 4), (header.version_major, header.version_minor));
+        assert_eq!(4, header.snaplen);
+        assert_eq!(LinkType::RAW, header.link_type);
+        assert_eq!(BIG_ENDIAN_NANOS[..HEADER_LEN], header.to_bytes());
+
+        let records: Vec<_> = reader.records().collect();
+        assert_eq!(1, records.len());
+        let record = records[0].unwrap();
+        assert_eq!(Duration::new(10, 20), record.timestamp());
+        assert_eq!(20, record.original_len());
+        assert!(record.is_truncated());
+        assert_eq!(None, record.ethernet());
+        assert_eq!(Some((EtherType::IPV4, &BIG_ENDIAN_NANOS[40..])), record.network());
+    }
+
+    #[test]
+    fn truncated_record() {
+        let records: Vec<_> = PcapReader::new(&BIG_ENDIAN_NANOS[..43]).unwrap().records().collect();
+        assert_eq!(vec![Err(PcapError::Truncated)], records);
+        let records: Vec<_> = PcapReader::new(&BIG_ENDIAN_NANOS[..30]).unwrap().records().collect();
+        assert_eq!(vec![Err(PcapError::Truncated)], records);
+        assert_eq!(0, PcapReader::new(&BIG_ENDIAN_NANOS[..24]).unwrap().records().count());
+        let mut data = BIG_ENDIAN_NANOS;
+        data[32..36].copy_from_slice(&[0xff; 4]);
+        let records: Vec<_> = PcapReader::new(&data).unwrap().records().collect();
+        assert_eq!(vec![Err(PcapError::Truncated)], records);
+    }
+
+    #[test]
+    fn invalid_header() {
+        assert_eq!(Err(PcapError::Truncated), PcapHeader::parse(&BIG_ENDIAN_NANOS[..23]));
+        let mut data = BIG_ENDIAN_NANOS;
+        data[0] = 0;
+        assert_eq!(Err(PcapError::Magic), PcapHeader::parse(&data));
+    }
+
+    #[test]
+    fn linux_sll() {
+        let mut data = [0; 20];
+        {
+            let mut sll = MutLinuxSllPacket::new(&mut data).unwrap();
+            sll.set_packet_type(LinuxSllPacket::OUTGOING);
+            sll.set_protocol(EtherType::ARP);
+        }
+        let record = Record::new(LinkType::LINUX_SLL, Duration::from_secs(0), 20, &data);
+        assert_eq!(Some((EtherType::ARP, &data[16..])), record.network());
+        let record = Record::new(LinkType::LINUX_SLL, Duration::from_secs(0), 20, &data[..15]);
+        assert_eq!(None, record.network());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_and_read() {
+        let mut frame = [0; 60];
+        frame[12..14].copy_from_slice(&[0x08, 0x06]);
+        let mut writer = PcapWriter::new(Vec::new(), LinkType::ETHERNET).unwrap();
+        writer.write_packet(Duration::new(1, 2_500), &frame).unwrap();
+        writer.write_packet(Duration::new(3, 0), &frame[..14]).unwrap();
+        let file = writer.into_inner();
+        assert_eq!(HEADER_LEN + 2 * RECORD_HEADER_LEN + 74, file.len());
+
+        let reader = PcapReader::new(&file).unwrap();
+        assert_eq!(PcapHeader::new(LinkType::ETHERNET), *reader.header());
+        let records: Vec<_> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(2, records.len());
+        // Microsecond resolution drops the nanoseconds
+        assert_eq!(Duration::new(1, 2_000), records[0].timestamp());
+        assert_eq!(&frame[..], records[0].ethernet().unwrap().data());
+        assert_eq!(Some((EtherType::ARP, &frame[14..])), records[0].network());
+        assert_eq!(Duration::new(3, 0), records[1].timestamp());
+        assert!(!records[1].is_truncated());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_snaplen() {
+        let header = PcapHeader {
+            byte_order: Endianness::Big,
+            resolution: TimestampResolution::Nanoseconds,
+            snaplen: 4,
+            ..PcapHeader::new(LinkType::RAW)
+        };
+        let mut writer = PcapWriter::with_header(Vec::new(), header).unwrap();
+        writer.write_packet(Duration::new(10, 20), &[0x45, 0, 0, 20, 0, 0]).unwrap();
+        let mut expected = BIG_ENDIAN_NANOS;
+        expected[39] = 6;
+        assert_eq!(&expected[..], &writer.into_inner()[..]);
+    }
+}
@@ -0,0 +1,1249 @@
+//! Reading and writing capture files in the pcapng format.
+//!
+//! A pcapng file is a sequence of blocks. Each section starts with a section header block that
+//! sets the byte order, followed by interface description blocks that describe the interfaces
+//! packets were captured on, and packet blocks that refer to those interfaces by index. Unlike
+//! pcap files, one capture can hold packets of several link types and timestamp resolutions.
+//!
+//! [`PcapngReader`] reads a whole file from a byte slice without copying. `blocks()` returns every
+//! block, and `packets()` returns the captured packets as `pcap::Record`s together with the
+//! interface they were captured on, with the timestamps converted from the resolution of that
+//! interface. [`PcapngWriter`], which requires the `std` feature, writes a section to any
+//! `io::Write`.
+//!
+//! ```rust
+//! extern crate rips_packets;
+//!
+//! # #[cfg(feature = "std")]
+//! # mod example {
+//! use rips_packets::pcap::LinkType;
+//! use rips_packets::pcapng::{Interface, PcapngReader, PcapngWriter};
+//! use std::time::Duration;
+//!
+//! # pub fn main() {
+//!     let mut writer = PcapngWriter::new(Vec::new()).unwrap();
+//!     let eth0 = writer.add_interface(&Interface::new(LinkType::ETHERNET)).unwrap();
+//!     writer.write_packet(eth0, Duration::from_millis(1500), &[0xff; 60]).unwrap();
+//!     let file = writer.into_inner();
+//!
+//!     let reader = PcapngReader::new(&file).unwrap();
+//!     for packet in reader.packets() {
+//!         let packet = packet.unwrap();
+//!         let record = packet.record();
+//!         println!("{} {:?} {:?}", packet.interface_id(), record.timestamp(), record.network());
+//!     }
+//! # }
+//! # }
+//! #
+//! # fn main() {
+//! #     #[cfg(feature = "std")]
+//! #     example::main();
+//! # }
+//! ```
+//!
+//! [`PcapngReader`]: struct.PcapngReader.html
+//! [`PcapngWriter`]: struct.PcapngWriter.html
+
+use core::cmp;
+use core::error::Error;
+use core::fmt;
+use core::net::{Ipv4Addr, Ipv6Addr};
+#[cfg(feature = "std")]
+use core::net::IpAddr;
+use core::str;
+use core::time::Duration;
+use layout::Endianness;
+use pcap::{read_u16, read_u32, read_u64, LinkType, Record};
+#[cfg(feature = "std")]
+use pcap::{native_byte_order, write_u16, write_u32, write_u64};
+#[cfg(feature = "std")]
+use std::io;
+
+/// The byte order magic of the section header block, in the byte order of the section.
+pub const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+/// The option code that ends a list of options.
+pub const OPT_ENDOFOPT: u16 = 0;
+/// Option code of a UTF-8 comment, valid in all blocks.
+pub const OPT_COMMENT: u16 = 1;
+/// Option code of the hardware of the machine that wrote a section.
+pub const SHB_HARDWARE: u16 = 2;
+/// Option code of the operating system of the machine that wrote a section.
+pub const SHB_OS: u16 = 3;
+/// Option code of the application that wrote a section.
+pub const SHB_USERAPPL: u16 = 4;
+/// Option code of the name of an interface, such as "eth0".
+pub const IF_NAME: u16 = 2;
+/// Option code of the description of an interface.
+pub const IF_DESCRIPTION: u16 = 3;
+/// Option code of the timestamp resolution of an interface.
+pub const IF_TSRESOL: u16 = 9;
+/// Option code of the offset in seconds added to the timestamps of an interface.
+pub const IF_TSOFFSET: u16 = 14;
+/// Option code of the direction and reception flags of an enhanced packet.
+pub const EPB_FLAGS: u16 = 2;
+
+/// The timestamp resolution of interfaces without an `if_tsresol` option, microseconds.
+pub const DEFAULT_TS_RESOLUTION: u8 = 6;
+
+/// The length of the block type, the block length and the trailing copy of the block length.
+const BLOCK_OVERHEAD: usize = 12;
+
+/// Number of interfaces per section `Packets` keeps at hand. Packets of later interfaces make it
+/// search the section again.
+const MAX_CACHED_INTERFACES: usize = 8;
+
+/// The type of a block, see [the list of block types].
+///
+/// [the list of block types]: https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct BlockType(pub u32);
+
+impl BlockType {
+    pub const SECTION_HEADER: BlockType = BlockType(0x0a0d_0d0a);
+    pub const INTERFACE_DESCRIPTION: BlockType = BlockType(0x0000_0001);
+    pub const SIMPLE_PACKET: BlockType = BlockType(0x0000_0003);
+    pub const NAME_RESOLUTION: BlockType = BlockType(0x0000_0004);
+    pub const ENHANCED_PACKET: BlockType = BlockType(0x0000_0006);
+    /// A custom block that may be copied to new files by tools that do not understand it.
+    pub const CUSTOM: BlockType = BlockType(0x0000_0bad);
+    /// A custom block that must not be copied to new files by tools that do not understand it.
+    pub const CUSTOM_NO_COPY: BlockType = BlockType(0x4000_0bad);
+
+    #[inline]
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+/// One block of a pcapng file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Block<'a> {
+    SectionHeader(SectionHeader<'a>),
+    InterfaceDescription(InterfaceDescription<'a>),
+    EnhancedPacket(EnhancedPacket<'a>),
+    SimplePacket(SimplePacket<'a>),
+    NameResolution(NameResolution<'a>),
+    Custom(CustomBlock<'a>),
+    /// A block of a type this module does not parse, with its body.
+    Unknown(BlockType, &'a [u8]),
+}
+
+impl<'a> Block<'a> {
+    fn parse(
+        block_type: BlockType,
+        body: &'a [u8],
+        byte_order: Endianness,
+    ) -> Result<Block<'a>, PcapngError> {
+        Ok(match block_type {
+            BlockType::SECTION_HEADER => {
+                Block::SectionHeader(SectionHeader::parse(body, byte_order)?)
+            }
+            BlockType::INTERFACE_DESCRIPTION => {
+                Block::InterfaceDescription(InterfaceDescription::parse(body, byte_order)?)
+            }
+            BlockType::ENHANCED_PACKET => {
+                Block::EnhancedPacket(EnhancedPacket::parse(body, byte_order)?)
+            }
+            BlockType::SIMPLE_PACKET => {
+                if body.len() < 4 {
+                    return Err(PcapngError::BlockLength);
+                }
+                let original_len = read_u32(body, 0, byte_order) as usize;
+                let data = &body[4..];
+                Block::SimplePacket(SimplePacket {
+                    original_len,
+                    data: &data[..cmp::min(original_len, data.len())],
+                })
+            }
+            BlockType::NAME_RESOLUTION => Block::NameResolution(NameResolution {
+                records: body,
+                byte_order,
+            }),
+            BlockType::CUSTOM | BlockType::CUSTOM_NO_COPY => {
+                if body.len() < 4 {
+                    return Err(PcapngError::BlockLength);
+                }
+                Block::Custom(CustomBlock {
+                    copyable: block_type == BlockType::CUSTOM,
+                    pen: read_u32(body, 0, byte_order),
+                    data: &body[4..],
+                })
+            }
+            _ => Block::Unknown(block_type, body),
+        })
+    }
+}
+
+/// The section header block, which starts every section of a file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SectionHeader<'a> {
+    byte_order: Endianness,
+    version_major: u16,
+    version_minor: u16,
+    section_length: u64,
+    options: &'a [u8],
+}
+
+impl<'a> SectionHeader<'a> {
+    fn parse(body: &'a [u8], byte_order: Endianness) -> Result<SectionHeader<'a>, PcapngError> {
+        if body.len() < 16 {
+            return Err(PcapngError::BlockLength);
+        }
+        Ok(SectionHeader {
+            byte_order,
+            version_major: read_u16(body, 4, byte_order),
+            version_minor: read_u16(body, 6, byte_order),
+            section_length: read_u64(body, 8, byte_order),
+            options: &body[16..],
+        })
+    }
+
+    /// Returns the byte order of all blocks in the section.
+    pub fn byte_order(&self) -> Endianness {
+        self.byte_order
+    }
+
+    pub fn version_major(&self) -> u16 {
+        self.version_major
+    }
+
+    pub fn version_minor(&self) -> u16 {
+        self.version_minor
+    }
+
+    /// Returns the length in bytes of the rest of the section, or `None` if the writer did not
+    /// record it.
+    pub fn section_length(&self) -> Option<u64> {
+        if self.section_length == u64::MAX {
+            None
+        } else {
+            Some(self.section_length)
+        }
+    }
+
+    /// Returns an iterator over the options of the block, such as `SHB_USERAPPL`.
+    pub fn options(&self) -> Options<'a> {
+        Options::new(self.options, self.byte_order)
+    }
+}
+
+/// The interface description block, which describes an interface packets were captured on.
+/// Interfaces are numbered in the order their blocks appear in a section, starting at zero.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InterfaceDescription<'a> {
+    byte_order: Endianness,
+    link_type: LinkType,
+    snaplen: u32,
+    ts_resolution: u8,
+    ts_offset: i64,
+    options: &'a [u8],
+}
+
+impl<'a> InterfaceDescription<'a> {
+    fn parse(
+        body: &'a [u8],
+        byte_order: Endianness,
+    ) -> Result<InterfaceDescription<'a>, PcapngError> {
+        if body.len() < 8 {
+            return Err(PcapngError::BlockLength);
+        }
+        let mut interface = InterfaceDescription {
+            byte_order,
+            link_type: LinkType(u32::from(read_u16(body, 0, byte_order))),
+            snaplen: read_u32(body, 4, byte_order),
+            ts_resolution: DEFAULT_TS_RESOLUTION,
+            ts_offset: 0,
+            options: &body[8..],
+        };
+        for (code, value) in interface.options() {
+            match (code, value.len()) {
+                (IF_TSRESOL, 1) => interface.ts_resolution = value[0],
+                (IF_TSOFFSET, 8) => interface.ts_offset = read_u64(value, 0, byte_order) as i64,
+                _ => (),
+            }
+        }
+        Ok(interface)
+    }
+
+    /// Returns the link type of the packets captured on the interface.
+    pub fn link_type(&self) -> LinkType {
+        self.link_type
+    }
+
+    /// Returns the maximum number of bytes stored of each packet, or zero for no limit.
+    pub fn snaplen(&self) -> u32 {
+        self.snaplen
+    }
+
+    /// Returns the `if_tsresol` option of the interface. If the most significant bit is clear,
+    /// timestamps are in units of 10^-n seconds, otherwise in units of 2^-n seconds, where n is
+    /// the value of the remaining bits. The default is 6, microseconds.
+    pub fn ts_resolution(&self) -> u8 {
+        self.ts_resolution
+    }
+
+    /// Returns the `if_tsoffset` option of the interface, the number of seconds added to all
+    /// timestamps. The default is 0.
+    pub fn ts_offset(&self) -> i64 {
+        self.ts_offset
+    }
+
+    /// Returns the name of the interface, if it has a valid UTF-8 `if_name` option.
+    pub fn name(&self) -> Option<&'a str> {
+        self.options()
+            .find(|&(code, _)| code == IF_NAME)
+            .and_then(|(_, value)| str::from_utf8(value).ok())
+    }
+
+    /// Converts a timestamp in units of the resolution of the interface to time since the Unix
+    /// epoch, adding the offset of the interface.
+    pub fn timestamp(&self, ticks: u64) -> Result<Duration, PcapngError> {
+        let units = units_per_second(self.ts_resolution).ok_or(PcapngError::Timestamp)?;
+        let nanos = u128::from(ticks % units) * 1_000_000_000 / u128::from(units);
+        let seconds = ticks / units;
+        let seconds = if self.ts_offset < 0 {
+            seconds.checked_sub(self.ts_offset.unsigned_abs())
+        } else {
+            seconds.checked_add(self.ts_offset as u64)
+        };
+        Ok(Duration::new(seconds.ok_or(PcapngError::Timestamp)?, nanos as u32))
+    }
+
+    /// Returns an iterator over the options of the block, such as `IF_NAME`.
+    pub fn options(&self) -> Options<'a> {
+        Options::new(self.options, self.byte_order)
+    }
+}
+
+/// The enhanced packet block, which holds a packet captured on one of the interfaces of the
+/// section.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct EnhancedPacket<'a> {
+    byte_order: Endianness,
+    interface_id: u32,
+    ticks: u64,
+    original_len: usize,
+    data: &'a [u8],
+    options: &'a [u8],
+}
+
+impl<'a> EnhancedPacket<'a> {
+    fn parse(body: &'a [u8], byte_order: Endianness) -> Result<EnhancedPacket<'a>, PcapngError> {
+        if body.len() < 20 {
+            return Err(PcapngError::BlockLength);
+        }
+        let captured_len = read_u32(body, 12, byte_order) as usize;
+        if captured_len > body.len() - 20 {
+            return Err(PcapngError::BlockLength);
+        }
+        let options_start = cmp::min(20 + padded_len(captured_len), body.len());
+        Ok(EnhancedPacket {
+            byte_order,
+            interface_id: read_u32(body, 0, byte_order),
+            ticks: u64::from(read_u32(body, 4, byte_order)) << 32
+                | u64::from(read_u32(body, 8, byte_order)),
+            original_len: read_u32(body, 16, byte_order) as usize,
+            data: &body[20..20 + captured_len],
+            options: &body[options_start..],
+        })
+    }
+
+    /// Returns the index of the interface in the section the packet was captured on.
+    pub fn interface_id(&self) -> u32 {
+        self.interface_id
+    }
+
+    /// Returns the timestamp in units of the resolution of the interface. Convert it with
+    /// `InterfaceDescription::timestamp()`.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the length of the packet on the wire.
+    pub fn original_len(&self) -> usize {
+        self.original_len
+    }
+
+    /// Returns the captured bytes of the packet.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns an iterator over the options of the block, such as `EPB_FLAGS`.
+    pub fn options(&self) -> Options<'a> {
+        Options::new(self.options, self.byte_order)
+    }
+}
+
+/// The simple packet block, which holds a packet captured on the first interface of the section
+/// without a timestamp.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SimplePacket<'a> {
+    original_len: usize,
+    data: &'a [u8],
+}
+
+impl<'a> SimplePacket<'a> {
+    /// Returns the length of the packet on the wire.
+    pub fn original_len(&self) -> usize {
+        self.original_len
+    }
+
+    /// Returns the captured bytes of the packet. For packets truncated to the snaplen of the
+    /// interface this can include up to three bytes of padding. `PcapngReader::packets()` removes
+    /// them.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// The name resolution block, which maps addresses to names.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NameResolution<'a> {
+    records: &'a [u8],
+    byte_order: Endianness,
+}
+
+impl<'a> NameResolution<'a> {
+    /// Returns an iterator over the name records of the block.
+    pub fn records(&self) -> NameRecords<'a> {
+        NameRecords(Options::new(self.records, self.byte_order))
+    }
+}
+
+/// Iterator over the records of a name resolution block.
+#[derive(Debug, Clone)]
+pub struct NameRecords<'a>(Options<'a>);
+
+impl<'a> Iterator for NameRecords<'a> {
+    type Item = NameRecord<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Name records are encoded like options, with record type 0 ending the list
+        let (record_type, value) = self.0.next()?;
+        Some(match (record_type, value.len()) {
+            (1, len) if len >= 4 => {
+                let mut ip = [0; 4];
+                ip.copy_from_slice(&value[..4]);
+                NameRecord::Ipv4(Ipv4Addr::from(ip), Names(&value[4..]))
+            }
+            (2, len) if len >= 16 => {
+                let mut ip = [0; 16];
+                ip.copy_from_slice(&value[..16]);
+                NameRecord::Ipv6(Ipv6Addr::from(ip), Names(&value[16..]))
+            }
+            _ => NameRecord::Other(record_type, value),
+        })
+    }
+}
+
+/// One record of a name resolution block.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NameRecord<'a> {
+    Ipv4(Ipv4Addr, Names<'a>),
+    Ipv6(Ipv6Addr, Names<'a>),
+    /// A record of an unknown type, or a record too short for its address, with its value.
+    Other(u16, &'a [u8]),
+}
+
+/// Iterator over the names of a name record. Names that are not valid UTF-8 are skipped.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Names<'a>(&'a [u8]);
+
+impl<'a> Iterator for Names<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.0.is_empty() {
+            let end = self.0.iter().position(|&b| b == 0).unwrap_or(self.0.len());
+            let name = &self.0[..end];
+            self.0 = &self.0[cmp::min(end + 1, self.0.len())..];
+            if let Ok(name) = str::from_utf8(name) {
+                if !name.is_empty() {
+                    return Some(name);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A custom block, holding data defined by the organization with the given private enterprise
+/// number.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CustomBlock<'a> {
+    copyable: bool,
+    pen: u32,
+    data: &'a [u8],
+}
+
+impl<'a> CustomBlock<'a> {
+    /// Returns true if tools that do not understand the block may copy it to new files.
+    pub fn copyable(&self) -> bool {
+        self.copyable
+    }
+
+    /// Returns the IANA private enterprise number of the organization that defined the block.
+    pub fn pen(&self) -> u32 {
+        self.pen
+    }
+
+    /// Returns the body of the block after the enterprise number, including any padding.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// Iterator over the options of a block, as pairs of option code and value. Ends at the
+/// `OPT_ENDOFOPT` option or at an option that does not fit in the block.
+#[derive(Debug, Clone)]
+pub struct Options<'a> {
+    data: &'a [u8],
+    byte_order: Endianness,
+}
+
+impl<'a> Options<'a> {
+    fn new(data: &'a [u8], byte_order: Endianness) -> Options<'a> {
+        Options { data, byte_order }
+    }
+}
+
+impl<'a> Iterator for Options<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < 4 {
+            return None;
+        }
+        let code = read_u16(self.data, 0, self.byte_order);
+        let len = read_u16(self.data, 2, self.byte_order) as usize;
+        if code == OPT_ENDOFOPT || self.data.len() < 4 + len {
+            self.data = &[];
+            return None;
+        }
+        let value = &self.data[4..4 + len];
+        self.data = &self.data[cmp::min(4 + padded_len(len), self.data.len())..];
+        Some((code, value))
+    }
+}
+
+/// Reads a pcapng file stored in a byte slice.
+#[derive(Debug, Copy, Clone)]
+pub struct PcapngReader<'a> {
+    section_header: SectionHeader<'a>,
+    data: &'a [u8],
+}
+
+impl<'a> PcapngReader<'a> {
+    /// Parses the first section header block of the capture file in `data`. The rest of the
+    /// blocks are parsed lazily by `blocks()` and `packets()`.
+    pub fn new(data: &'a [u8]) -> Result<PcapngReader<'a>, PcapngError> {
+        if data.len() < 4 {
+            return Err(PcapngError::Truncated);
+        }
+        if read_u32(data, 0, Endianness::Big) != BlockType::SECTION_HEADER.value() {
+            return Err(PcapngError::Magic);
+        }
+        match Blocks::new(data).next() {
+            Some(Ok(Block::SectionHeader(section_header))) => {
+                Ok(PcapngReader {
+                    section_header,
+                    data,
+                })
+            }
+            Some(Err(e)) => Err(e),
+            _ => Err(PcapngError::Truncated),
+        }
+    }
+
+    /// Returns the header of the first section of the file.
+    pub fn section_header(&self) -> &SectionHeader<'a> {
+        &self.section_header
+    }
+
+    /// Returns an iterator over all blocks in the file, starting with the first section header.
+    pub fn blocks(&self) -> Blocks<'a> {
+        Blocks::new(self.data)
+    }
+
+    /// Returns an iterator over the packets in the file, from both enhanced and simple packet
+    /// blocks.
+    pub fn packets(&self) -> Packets<'a> {
+        Packets {
+            blocks: self.blocks(),
+            section: self.data,
+            interfaces: [None; MAX_CACHED_INTERFACES],
+            interface_count: 0,
+        }
+    }
+}
+
+/// Iterator over the blocks of a pcapng file. Created with `PcapngReader::blocks()`.
+///
+/// A block that does not fit in the rest of the file is returned as `PcapngError::Truncated`
+/// and a block with an invalid length as `PcapngError::BlockLength`. Both end the iteration,
+/// since the position of the next block is unknown. A block too short for the fields of its
+/// type is returned as `PcapngError::BlockLength` without ending the iteration.
+#[derive(Debug, Clone)]
+pub struct Blocks<'a> {
+    data: &'a [u8],
+    byte_order: Endianness,
+}
+
+impl<'a> Blocks<'a> {
+    fn new(data: &'a [u8]) -> Blocks<'a> {
+        Blocks {
+            data,
+            byte_order: Endianness::Big,
+        }
+    }
+
+    fn fail(&mut self, error: PcapngError) -> Option<Result<Block<'a>, PcapngError>> {
+        self.data = &[];
+        Some(Err(error))
+    }
+}
+
+impl<'a> Iterator for Blocks<'a> {
+    type Item = Result<Block<'a>, PcapngError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = self.data;
+        if data.len() < BLOCK_OVERHEAD {
+            return self.fail(PcapngError::Truncated);
+        }
+        // The section header block type reads the same in both byte orders
+        let block_type = BlockType(read_u32(data, 0, self.byte_order));
+        if block_type == BlockType::SECTION_HEADER {
+            self.byte_order = match read_u32(data, 8, Endianness::Big) {
+                BYTE_ORDER_MAGIC => Endianness::Big,
+                magic if magic.swap_bytes() == BYTE_ORDER_MAGIC => Endianness::Little,
+                _ => return self.fail(PcapngError::Magic),
+            };
+        }
+        let len = read_u32(data, 4, self.byte_order) as usize;
+        if len < BLOCK_OVERHEAD || !len.is_multiple_of(4) {
+            return self.fail(PcapngError::BlockLength);
+        }
+        if data.len() < len {
+            return self.fail(PcapngError::Truncated);
+        }
+        if read_u32(data, len - 4, self.byte_order) as usize != len {
+            return self.fail(PcapngError::BlockLength);
+        }
+        self.data = &data[len..];
+        Some(Block::parse(block_type, &data[8..len - 4], self.byte_order))
+    }
+}
+
+/// Iterator over the packets of a pcapng file. Created with `PcapngReader::packets()`.
+///
+/// Errors from `Blocks` are passed on. A packet of an interface not described earlier in its
+/// section is returned as `PcapngError::UnknownInterface`.
+#[derive(Debug, Clone)]
+pub struct Packets<'a> {
+    blocks: Blocks<'a>,
+    /// The file from the header of the current section
+    section: &'a [u8],
+    /// The first interfaces of the current section, recorded as their blocks pass
+    interfaces: [Option<InterfaceDescription<'a>>; MAX_CACHED_INTERFACES],
+    /// Number of interfaces seen so far in the current section
+    interface_count: usize,
+}
+
+impl<'a> Packets<'a> {
+    /// Finds the interface with the given index among the blocks of the current section before
+    /// `rest`. Only interfaces past the first `MAX_CACHED_INTERFACES` need to be searched for.
+    fn interface(
+        &self,
+        interface_id: u32,
+        rest: &'a [u8],
+    ) -> Result<InterfaceDescription<'a>, PcapngError> {
+        let index = interface_id as usize;
+        if index >= self.interface_count {
+            return Err(PcapngError::UnknownInterface);
+        }
+        if let Some(&Some(interface)) = self.interfaces.get(index) {
+            return Ok(interface);
+        }
+        let preceding = &self.section[..self.section.len() - rest.len()];
+        Blocks::new(preceding)
+            .filter_map(|block| match block {
+                Ok(Block::InterfaceDescription(interface)) => Some(interface),
+                _ => None,
+            })
+            .nth(index)
+            .ok_or(PcapngError::UnknownInterface)
+    }
+}
+
+impl<'a> Iterator for Packets<'a> {
+    type Item = Result<Packet<'a>, PcapngError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = self.blocks.data;
+            let block = match self.blocks.next()? {
+                Ok(block) => block,
+                Err(e) => return Some(Err(e)),
+            };
+            let (interface_id, ticks, original_len, data) = match block {
+                Block::SectionHeader(_) => {
+                    self.section = rest;
+                    self.interfaces = [None; MAX_CACHED_INTERFACES];
+                    self.interface_count = 0;
+                    continue;
+                }
+                Block::InterfaceDescription(interface) => {
+                    if let Some(slot) = self.interfaces.get_mut(self.interface_count) {
+                        *slot = Some(interface);
+                    }
+                    self.interface_count += 1;
+                    continue;
+                }
+                Block::EnhancedPacket(packet) => (
+                    packet.interface_id,
+                    Some(packet.ticks),
+                    packet.original_len,
+                    packet.data,
+                ),
+                Block::SimplePacket(packet) => (0, None, packet.original_len, packet.data),
+                _ => continue,
+            };
+            let result = self.interface(interface_id, rest).and_then(|interface| {
+                let timestamp = match ticks {
+                    Some(ticks) => interface.timestamp(ticks)?,
+                    None => Duration::from_secs(0),
+                };
+                let data = match interface.snaplen {
+                    0 => data,
+                    snaplen => &data[..cmp::min(snaplen as usize, data.len())],
+                };
+                Ok(Packet {
+                    interface_id,
+                    interface,
+                    record: Record::new(interface.link_type, timestamp, original_len, data),
+                })
+            });
+            return Some(result);
+        }
+    }
+}
+
+/// A packet of a pcapng file with the interface it was captured on.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Packet<'a> {
+    interface_id: u32,
+    interface: InterfaceDescription<'a>,
+    record: Record<'a>,
+}
+
+impl<'a> Packet<'a> {
+    /// Returns the index of the interface in its section.
+    pub fn interface_id(&self) -> u32 {
+        self.interface_id
+    }
+
+    pub fn interface(&self) -> &InterfaceDescription<'a> {
+        &self.interface
+    }
+
+    /// Returns the packet with the link type of its interface and its timestamp as time since
+    /// the Unix epoch. Packets from simple packet blocks have a timestamp of zero.
+    pub fn record(&self) -> &Record<'a> {
+        &self.record
+    }
+}
+
+
+/// The description of an interface added to a file with `PcapngWriter::add_interface()`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Interface<'a> {
+    pub link_type: LinkType,
+    /// The maximum number of bytes stored of each packet, or zero for no limit.
+    pub snaplen: u32,
+    /// The timestamp resolution, encoded like the `if_tsresol` option. See
+    /// `InterfaceDescription::ts_resolution()`.
+    pub ts_resolution: u8,
+    pub name: Option<&'a str>,
+}
+
+impl<'a> Interface<'a> {
+    /// Returns an unnamed interface of the given link type with no snaplen and microsecond
+    /// timestamps.
+    pub fn new(link_type: LinkType) -> Interface<'a> {
+        Interface {
+            link_type,
+            snaplen: 0,
+            ts_resolution: DEFAULT_TS_RESOLUTION,
+            name: None,
+        }
+    }
+}
+
+/// Writes a section of a pcapng file to an `io::Write`, in the byte order of this machine.
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct PcapngWriter<W: io::Write> {
+    writer: W,
+    byte_order: Endianness,
+    /// The snaplen and timestamp units per second of each interface
+    interfaces: Vec<(u32, u64)>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> PcapngWriter<W> {
+    /// Writes a section header block of version 1.0 with an unknown section length.
+    pub fn new(writer: W) -> io::Result<PcapngWriter<W>> {
+        let mut pcapng_writer = PcapngWriter {
+            writer,
+            byte_order: native_byte_order(),
+            interfaces: Vec::new(),
+        };
+        let mut body = [0; 16];
+        write_u32(&mut body, 0, BYTE_ORDER_MAGIC, pcapng_writer.byte_order);
+        write_u16(&mut body, 4, 1, pcapng_writer.byte_order);
+        write_u64(&mut body, 8, u64::MAX, pcapng_writer.byte_order);
+        pcapng_writer.write_block(BlockType::SECTION_HEADER, &body)?;
+        Ok(pcapng_writer)
+    }
+
+    /// Writes an interface description block and returns the index of the interface, to pass
+    /// to `write_packet()`.
+    pub fn add_interface(&mut self, interface: &Interface) -> io::Result<u32> {
+        let units = units_per_second(interface.ts_resolution)
+            .ok_or(io::ErrorKind::InvalidInput)?;
+        let mut body = vec![0; 8];
+        write_u16(&mut body, 0, interface.link_type.value() as u16, self.byte_order);
+        write_u32(&mut body, 4, interface.snaplen, self.byte_order);
+        let mut options = false;
+        if let Some(name) = interface.name {
+            self.push_option(&mut body, IF_NAME, name.as_bytes())?;
+            options = true;
+        }
+        if interface.ts_resolution != DEFAULT_TS_RESOLUTION {
+            self.push_option(&mut body, IF_TSRESOL, &[interface.ts_resolution])?;
+            options = true;
+        }
+        if options {
+            self.push_option(&mut body, OPT_ENDOFOPT, &[])?;
+        }
+        self.write_block(BlockType::INTERFACE_DESCRIPTION, &body)?;
+        self.interfaces.push((interface.snaplen, units));
+        Ok(self.interfaces.len() as u32 - 1)
+    }
+
+    /// Writes an enhanced packet block with the given packet, captured on the given interface
+    /// at `timestamp` since the Unix epoch. The packet is truncated to the snaplen of the
+    /// interface and the timestamp to its resolution. Fails with `InvalidInput` for interfaces
+    /// that were not added and timestamps that do not fit in 64 bits at that resolution.
+    pub fn write_packet(
+        &mut self,
+        interface_id: u32,
+        timestamp: Duration,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let (snaplen, units) = *self
+            .interfaces
+            .get(interface_id as usize)
+            .ok_or(io::ErrorKind::InvalidInput)?;
+        let ticks = u128::from(timestamp.as_secs()) * u128::from(units)
+            + u128::from(timestamp.subsec_nanos()) * u128::from(units) / 1_000_000_000;
+        if ticks > u128::from(u64::MAX) || data.len() > u32::MAX as usize {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        let captured = truncate(data, snaplen);
+        let mut body = vec![0; 20];
+        write_u32(&mut body, 0, interface_id, self.byte_order);
+        write_u32(&mut body, 4, (ticks >> 32) as u32, self.byte_order);
+        write_u32(&mut body, 8, ticks as u32, self.byte_order);
+        write_u32(&mut body, 12, captured.len() as u32, self.byte_order);
+        write_u32(&mut body, 16, data.len() as u32, self.byte_order);
+        body.extend_from_slice(captured);
+        self.write_block(BlockType::ENHANCED_PACKET, &body)
+    }
+
+    /// Writes a simple packet block with the given packet, which belongs to the first interface
+    /// and has no timestamp. The packet is truncated to the snaplen of the first interface.
+    /// Fails with `InvalidInput` if no interface was added.
+    pub fn write_simple_packet(&mut self, data: &[u8]) -> io::Result<()> {
+        let (snaplen, _) = *self.interfaces.first().ok_or(io::ErrorKind::InvalidInput)?;
+        if data.len() > u32::MAX as usize {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        let mut body = vec![0; 4];
+        write_u32(&mut body, 0, data.len() as u32, self.byte_order);
+        body.extend_from_slice(truncate(data, snaplen));
+        self.write_block(BlockType::SIMPLE_PACKET, &body)
+    }
+
+    /// Writes a name resolution block with one record per address and name.
+    pub fn write_name_resolution(&mut self, names: &[(IpAddr, &str)]) -> io::Result<()> {
+        let mut body = Vec::new();
+        for &(ip, name) in names {
+            let mut value = match ip {
+                IpAddr::V4(ip) => ip.octets().to_vec(),
+                IpAddr::V6(ip) => ip.octets().to_vec(),
+            };
+            value.extend_from_slice(name.as_bytes());
+            value.push(0);
+            let record_type = if ip.is_ipv4() { 1 } else { 2 };
+            self.push_option(&mut body, record_type, &value)?;
+        }
+        self.push_option(&mut body, 0, &[])?;
+        self.write_block(BlockType::NAME_RESOLUTION, &body)
+    }
+
+    /// Writes a custom block with the given private enterprise number and data. `copyable`
+    /// tells tools that do not understand the block whether they may copy it to new files.
+    pub fn write_custom_block(&mut self, pen: u32, data: &[u8], copyable: bool) -> io::Result<()> {
+        let mut body = vec![0; 4];
+        write_u32(&mut body, 0, pen, self.byte_order);
+        body.extend_from_slice(data);
+        let block_type = if copyable {
+            BlockType::CUSTOM
+        } else {
+            BlockType::CUSTOM_NO_COPY
+        };
+        self.write_block(block_type, &body)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Appends an option, or a name record, padded to 32 bits.
+    fn push_option(&self, body: &mut Vec<u8>, code: u16, value: &[u8]) -> io::Result<()> {
+        if value.len() > u16::MAX as usize {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        let mut header = [0; 4];
+        write_u16(&mut header, 0, code, self.byte_order);
+        write_u16(&mut header, 2, value.len() as u16, self.byte_order);
+        body.extend_from_slice(&header);
+        body.extend_from_slice(value);
+        body.resize(body.len() + padded_len(value.len()) - value.len(), 0);
+        Ok(())
+    }
+
+    /// Writes a block with the given body, padded to 32 bits.
+    fn write_block(&mut self, block_type: BlockType, body: &[u8]) -> io::Result<()> {
+        let len = BLOCK_OVERHEAD + padded_len(body.len());
+        if len > u32::MAX as usize {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        let mut header = [0; 8];
+        write_u32(&mut header, 0, block_type.value(), self.byte_order);
+        write_u32(&mut header, 4, len as u32, self.byte_order);
+        self.writer.write_all(&header)?;
+        self.writer.write_all(body)?;
+        self.writer.write_all(&[0; 3][..padded_len(body.len()) - body.len()])?;
+        self.writer.write_all(&header[4..])
+    }
+}
+
+#[cfg(feature = "std")]
+fn truncate(data: &[u8], snaplen: u32) -> &[u8] {
+    match snaplen {
+        0 => data,
+        snaplen => &data[..cmp::min(snaplen as usize, data.len())],
+    }
+}
+
+/// Returns the number of timestamp units per second for an `if_tsresol` option value, or `None`
+/// if it does not fit in 64 bits.
+fn units_per_second(ts_resolution: u8) -> Option<u64> {
+    let exponent = u32::from(ts_resolution & 0x7f);
+    if ts_resolution & 0x80 == 0 {
+        10u64.checked_pow(exponent)
+    } else {
+        2u64.checked_pow(exponent)
+    }
+}
+
+/// Returns `len` rounded up to a multiple of four, the alignment of blocks and options.
+fn padded_len(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+
+/// Error returned when a pcapng file can not be read.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PcapngError {
+    /// The file ended in the middle of a block.
+    Truncated,
+    /// The length of a block is invalid, or too short for the fields of its type.
+    BlockLength,
+    /// The file does not start with a section header block, or a section header block has an
+    /// invalid byte order magic.
+    Magic,
+    /// A packet refers to an interface that is not described earlier in its section.
+    UnknownInterface,
+    /// The timestamp resolution of an interface does not fit in 64 bits, or a timestamp is out
+    /// of range after adding the offset of its interface.
+    Timestamp,
+}
+
+impl fmt::Display for PcapngError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PcapngError::Truncated => write!(f, "Capture file truncated"),
+            PcapngError::BlockLength => write!(f, "Invalid block length"),
+            PcapngError::Magic => write!(f, "Not a pcapng file"),
+            PcapngError::UnknownInterface => write!(f, "Packet of an unknown interface"),
+            PcapngError::Timestamp => write!(f, "Invalid timestamp"),
+        }
+    }
+}
+
+impl Error for PcapngError {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A big endian section with one raw IP interface with millisecond timestamps, an enhanced
+    /// packet of 20 bytes of which 4 were captured, and a simple packet.
+    const BIG_ENDIAN: [u8; 116] = [
+        // Section header block with an unknown section length
+        0x0a, 0x0d, 0x0d, 0x0a, 0, 0, 0, 28, 0x1a, 0x2b, 0x3c, 0x4d, 0, 1, 0, 0,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 28,
+        // Interface description block with if_tsresol 3
+        0, 0, 0, 1, 0, 0, 0, 32, 0, 101, 0, 0, 0, 0, 0, 0,
+        0, 9, 0, 1, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32,
+        // Enhanced packet block at 1500 ms
+        0, 0, 0, 6, 0, 0, 0, 36, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x05, 0xdc, 0, 0, 0, 4,
+        0, 0, 0, 20, 0x45, 0, 0, 20, 0, 0, 0, 36,
+        // Simple packet block
+        0, 0, 0, 3, 0, 0, 0, 20, 0, 0, 0, 4, 0x60, 0, 0, 0, 0, 0, 0, 20,
+    ];
+
+    #[test]
+    fn big_endian() {
+        let reader = PcapngReader::new(&BIG_ENDIAN).unwrap();
+        let section_header = reader.section_header();
+        assert_eq!(Endianness::Big, section_header.byte_order());
+        assert_eq!((1, 0), (section_header.version_major(), section_header.version_minor()));
+        assert_eq!(None, section_header.section_length());
+        assert_eq!(0, section_header.options().count());
+
+        let blocks: Vec<_> = reader.blocks().map(Result::unwrap).collect();
+        assert_eq!(4, blocks.len());
+        match blocks[1] {
+            Block::InterfaceDescription(interface) => {
+                assert_eq!(LinkType::RAW, interface.link_type());
+                assert_eq!(3, interface.ts_resolution());
+                assert_eq!(vec![(IF_TSRESOL, &[3][..])], interface.options().collect::<Vec<_>>());
+                assert_eq!(None, interface.name());
+            }
+            block => panic!("Unexpected block {:?}", block),
+        }
+
+        let packets: Vec<_> = reader.packets().map(Result::unwrap).collect();
+        assert_eq!(2, packets.len());
+        let record = packets[0].record();
+        assert_eq!(LinkType::RAW, record.link_type());
+        assert_eq!(Duration::from_millis(1500), record.timestamp());
+        assert_eq!(20, record.original_len());
+        assert_eq!(&BIG_ENDIAN[88..92], record.data());
+        assert_eq!(
+            Some((::ethernet::EtherType::IPV6, &BIG_ENDIAN[108..112])),
+            packets[1].record().network()
+        );
+        assert_eq!(Duration::from_secs(0), packets[1].record().timestamp());
+    }
+
+    #[test]
+    fn invalid_blocks() {
+        assert_eq!(Err(PcapngError::Truncated), PcapngReader::new(&BIG_ENDIAN[..2]).map(|_| ()));
+        assert_eq!(Err(PcapngError::Magic), PcapngReader::new(&BIG_ENDIAN[28..]).map(|_| ()));
+        let mut data = BIG_ENDIAN;
+        data[8] = 0;
+        assert_eq!(Err(PcapngError::Magic), PcapngReader::new(&data).map(|_| ()));
+
+        let reader = PcapngReader::new(&BIG_ENDIAN[..100]).unwrap();
+        let blocks: Vec<_> = reader.blocks().skip(3).collect();
+        assert_eq!(vec![Err(PcapngError::Truncated)], blocks);
+
+        // Trailing block length differs from the leading one
+        let mut data = BIG_ENDIAN;
+        data[59] = 28;
+        let blocks: Vec<_> = PcapngReader::new(&data).unwrap().blocks().skip(1).collect();
+        assert_eq!(vec![Err(PcapngError::BlockLength)], blocks);
+
+        // Enhanced packet claiming more captured bytes than the block holds
+        let mut data = BIG_ENDIAN;
+        data[83] = 8;
+        let blocks: Vec<_> = PcapngReader::new(&data).unwrap().blocks().skip(2).collect();
+        assert_eq!(Err(PcapngError::BlockLength), blocks[0]);
+        assert_eq!(2, blocks.len());
+    }
+
+    #[test]
+    fn unknown_interface() {
+        let mut data = BIG_ENDIAN;
+        data[71] = 1;
+        let packets: Vec<_> = PcapngReader::new(&data).unwrap().packets().collect();
+        assert_eq!(Err(PcapngError::UnknownInterface), packets[0]);
+        assert!(packets[1].is_ok());
+        // Interfaces described after the packet do not count
+        let packets: Vec<_> = PcapngReader::new(&data[..28]).unwrap().packets().collect();
+        assert!(packets.is_empty());
+    }
+
+    #[test]
+    fn timestamp_resolution() {
+        let mut interface = InterfaceDescription::parse(&[0; 8], Endianness::Big).unwrap();
+        assert_eq!(Ok(Duration::new(1, 500_000)), interface.timestamp(1_000_500));
+        interface.ts_resolution = 0x80 | 10;
+        assert_eq!(Ok(Duration::from_millis(1500)), interface.timestamp(1536));
+        interface.ts_offset = -1;
+        assert_eq!(Ok(Duration::from_millis(500)), interface.timestamp(1536));
+        assert_eq!(Err(PcapngError::Timestamp), interface.timestamp(512));
+        interface.ts_resolution = 20;
+        assert_eq!(Err(PcapngError::Timestamp), interface.timestamp(0));
+    }
+
+    #[test]
+    fn options() {
+        let data = [0, 1, 0, 5, b'h', b'e', b'l', b'l', b'o', 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0];
+        let options: Vec<_> = Options::new(&data, Endianness::Big).collect();
+        assert_eq!(vec![(OPT_COMMENT, &b"hello"[..]), (3, &[][..])], options);
+        assert_eq!(1, Options::new(&data[..12], Endianness::Big).count());
+        // Value longer than the rest of the block
+        assert_eq!(0, Options::new(&data[..8], Endianness::Big).count());
+    }
+
+    #[test]
+    fn names() {
+        let names: Vec<_> = Names(b"a\0\0b.example\0\xff\0c").collect();
+        assert_eq!(vec!["a", "b.example", "c"], names);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn write_and_read() {
+        let mut frame = [0; 60];
+        frame[12..14].copy_from_slice(&[0x08, 0x06]);
+        let ip = [0x45; 40];
+        let mut writer = PcapngWriter::new(Vec::new()).unwrap();
+        let eth0 = writer.add_interface(&Interface::new(LinkType::ETHERNET)).unwrap();
+        let tun0 = writer
+            .add_interface(&Interface {
+                snaplen: 20,
+                ts_resolution: 9,
+                name: Some("tun0"),
+                ..Interface::new(LinkType::RAW)
+            })
+            .unwrap();
+        assert_eq!((0, 1), (eth0, tun0));
+        writer.write_packet(eth0, Duration::new(1, 2_500), &frame).unwrap();
+        writer.write_packet(tun0, Duration::new(3, 2_500), &ip).unwrap();
+        writer
+            .write_name_resolution(&[("10.0.0.1".parse().unwrap(), "a.example")])
+            .unwrap();
+        writer.write_custom_block(32473, b"abc", false).unwrap();
+        writer.write_simple_packet(&frame[..15]).unwrap();
+        assert_eq!(
+            Err(io::ErrorKind::InvalidInput),
+            writer.write_packet(2, Duration::new(0, 0), &ip).map_err(|e| e.kind())
+        );
+        let file = writer.into_inner();
+        assert_eq!(0, file.len() % 4);
+
+        let reader = PcapngReader::new(&file).unwrap();
+        assert_eq!(native_byte_order(), reader.section_header().byte_order());
+        let blocks: Vec<_> = reader.blocks().map(Result::unwrap).collect();
+        assert_eq!(8, blocks.len());
+        match blocks[5] {
+            Block::NameResolution(names) => {
+                let records: Vec<_> = names.records().collect();
+                assert_eq!(1, records.len());
+                match records[0] {
+                    NameRecord::Ipv4(ip, names) => {
+                        assert_eq!(Ipv4Addr::new(10, 0, 0, 1), ip);
+                        assert_eq!(vec!["a.example"], names.collect::<Vec<_>>());
+                    }
+                    record => panic!("Unexpected record {:?}", record),
+                }
+            }
+            block => panic!("Unexpected block {:?}", block),
+        }
+        match blocks[6] {
+            Block::Custom(custom) => {
+                assert!(!custom.copyable());
+                assert_eq!(32473, custom.pen());
+                assert_eq!(b"abc\0", custom.data());
+            }
+            block => panic!("Unexpected block {:?}", block),
+        }
+
+        let packets: Vec<_> = reader.packets().map(Result::unwrap).collect();
+        assert_eq!(3, packets.len());
+        assert_eq!(0, packets[0].interface_id());
+        // Microsecond resolution drops the nanoseconds
+        assert_eq!(Duration::new(1, 2_000), packets[0].record().timestamp());
+        assert_eq!(&frame[..], packets[0].record().data());
+        assert_eq!(1, packets[1].interface_id());
+        assert_eq!(Some("tun0"), packets[1].interface().name());
+        assert_eq!(LinkType::RAW, packets[1].record().link_type());
+        assert_eq!(Duration::new(3, 2_500), packets[1].record().timestamp());
+        assert_eq!(&ip[..20], packets[1].record().data());
+        assert!(packets[1].record().is_truncated());
+        assert_eq!(&frame[..15], packets[2].record().data());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn multiple_sections() {
+        let mut file = Vec::new();
+        for &link_type in &[LinkType::ETHERNET, LinkType::RAW] {
+            let mut writer = PcapngWriter::new(file).unwrap();
+            writer.add_interface(&Interface::new(link_type)).unwrap();
+            writer.write_packet(0, Duration::new(1, 0), &[0x45; 20]).unwrap();
+            file = writer.into_inner();
+        }
+        let reader = PcapngReader::new(&file).unwrap();
+        let link_types: Vec<_> = reader
+            .packets()
+            .map(|packet| packet.unwrap().record().link_type())
+            .collect();
+        assert_eq!(vec![LinkType::ETHERNET, LinkType::RAW], link_types);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn many_interfaces() {
+        let mut writer = PcapngWriter::new(Vec::new()).unwrap();
+        let count = MAX_CACHED_INTERFACES as u32 + 2;
+        for i in 0..count {
+            let interface = Interface {
+                snaplen: i + 1,
+                ..Interface::new(LinkType::RAW)
+            };
+            assert_eq!(i, writer.add_interface(&interface).unwrap());
+        }
+        for i in (0..count).rev().chain(0..count) {
+            writer.write_packet(i, Duration::new(1, 0), &[0x45; 20]).unwrap();
+        }
+        let file = writer.into_inner();
+        let reader = PcapngReader::new(&file).unwrap();
+        let packets: Vec<_> = reader.packets().map(Result::unwrap).collect();
+        assert_eq!(2 * count as usize, packets.len());
+        for packet in packets {
+            assert_eq!(packet.interface_id() + 1, packet.interface().snaplen());
+            assert_eq!(packet.interface_id() as usize + 1, packet.record().data().len());
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use checksum;
+use core::error::Error;
+use core::fmt;
+use ip::{Protocol, PseudoHeader};
+use types::*;
+
+mod options;
+pub use self::options::*;
+
+/// The maximum number of option bytes that fit in a TCP header.
+pub const MAX_OPTIONS_LEN: usize = 40;
+
+packet!(TcpPacket, MutTcpPacket, 20, |data: &[u8]| {
+    (read_offset!(data, 12, u8) >> 4) as usize * 4
+});
+
+getters!(TcpPacket
+    pub fn source_port(&self) -> u16 {
+        read_offset!(self.data(), 0, u16, from_be)
+    }
+
+    pub fn destination_port(&self) -> u16 {
+        read_offset!(self.data(), 2, u16, from_be)
+    }
+
+    pub fn sequence_number(&self) -> u32 {
+        read_offset!(self.data(), 4, u32, from_be)
+    }
+
+    pub fn acknowledgment_number(&self) -> u32 {
+        read_offset!(self.data(), 8, u32, from_be)
+    }
+
+    /// Returns the length of the header in 32 bit words.
+    pub fn data_offset(&self) -> u4 {
+        read_offset!(self.data(), 12, u8) >> 4
+    }
+
+    pub fn flags(&self) -> Flags {
+        Flags::from_bits_truncate(read_offset!(self.data(), 12, u16, from_be))
+    }
+
+    pub fn window_size(&self) -> u16 {
+        read_offset!(self.data(), 14, u16, from_be)
+    }
+
+    pub fn checksum(&self) -> u16 {
+        read_offset!(self.data(), 16, u16, from_be)
+    }
+
+    pub fn urgent_pointer(&self) -> u16 {
+        read_offset!(self.data(), 18, u16, from_be)
+    }
+
+    /// Returns the raw option bytes of the header. This is everything between the fixed 20 byte
+    /// header and the end of the header as given by `data_offset()`.
+    pub fn options_data(&self) -> &[u8] {
+        &self.data()[TcpPacket::MIN_LEN..self.header_len()]
+    }
+
+    /// Returns an iterator over the options in the header.
+    pub fn options(&self) -> TcpOptions<'_> {
+        TcpOptions::new(self.options_data())
+    }
+);
+
+impl<B: AsRef<[u8]>> TcpPacket<B> {
+    /// Checks that the data offset covers at least the fixed header and fits in the packet.
+    pub fn validate(&self) -> Result<(), TcpError> {
+        let header_len = usize::from(self.data_offset()) * 4;
+        if header_len < TcpPacket::MIN_LEN || header_len > self.data().len() {
+            return Err(TcpError::DataOffset);
+        }
+        Ok(())
+    }
+
+    /// Computes the checksum of this segment over the given IP pseudo-header, as it should be
+    /// written to the checksum field. The current value of the checksum field is ignored. The
+    /// segment is assumed to span the entire backing slice.
+    pub fn calculate_checksum<P: PseudoHeader>(&self, ip: &P) -> u16 {
+        let sum = ip.pseudo_header_sum(Protocol::TCP, self.data().len() as u32);
+        let sum = checksum::sum(checksum::sum(sum, &self.data()[..16]), &self.data()[18..]);
+        checksum::finish(sum)
+    }
+
+    /// Returns true if the checksum field contains the correct checksum for this segment and the
+    /// given IP pseudo-header.
+    pub fn verify_checksum<P: PseudoHeader>(&self, ip: &P) -> bool {
+        let sum = ip.pseudo_header_sum(Protocol::TCP, self.data().len() as u32);
+        checksum::finish(checksum::sum(sum, self.data())) == 0
+    }
+}
+
+new_checked!(TcpPacket, TcpError);
+
+setters!(TcpPacket
+    pub fn set_source_port(&mut self, source_port: u16) {
+        write_offset!(self.data_mut(), 0, source_port, u16, to_be);
+    }
+
+    pub fn set_destination_port(&mut self, destination_port: u16) {
+        write_offset!(self.data_mut(), 2, destination_port, u16, to_be);
+    }
+
+    pub fn set_sequence_number(&mut self, sequence_number: u32) {
+        write_offset!(self.data_mut(), 4, sequence_number, u32, to_be);
+    }
+
+    pub fn set_acknowledgment_number(&mut self, acknowledgment_number: u32) {
+        write_offset!(self.data_mut(), 8, acknowledgment_number, u32, to_be);
+    }
+
+    pub fn set_data_offset(&mut self, data_offset: u4) {
+        let new_byte = (data_offset << 4) | (read_offset!(self.data(), 12, u8) & 0x0f);
+        write_offset!(self.data_mut(), 12, new_byte, u8);
+    }
+
+    pub fn set_flags(&mut self, flags: Flags) {
+        let new_value = (read_offset!(self.data(), 12, u16, from_be) & 0xfe00) | flags.bits();
+        write_offset!(self.data_mut(), 12, new_value, u16, to_be);
+    }
+
+    pub fn set_window_size(&mut self, window_size: u16) {
+        write_offset!(self.data_mut(), 14, window_size, u16, to_be);
+    }
+
+    pub fn set_checksum(&mut self, checksum: u16) {
+        write_offset!(self.data_mut(), 16, checksum, u16, to_be);
+    }
+
+    pub fn set_urgent_pointer(&mut self, urgent_pointer: u16) {
+        write_offset!(self.data_mut(), 18, urgent_pointer, u16, to_be);
+    }
+);
+
+/// Writes a one line summary of the header in the style of tcpdump, like `TCP 80 -> 1024 [S.]
+/// seq=1 ack=2 win=1024 len=0`. The acknowledgment number is only written if the ACK flag is set
+/// and `len` is the number of payload bytes.
+impl<B: AsRef<[u8]>> fmt::Display for TcpPacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TCP {} -> {} [", self.source_port(), self.destination_port())?;
+        let flags = self.flags();
+        let names = [
+            (Flags::SYN, 'S'),
+            (Flags::FIN, 'F'),
+            (Flags::PSH, 'P'),
+            (Flags::RST, 'R'),
+            (Flags::URG, 'U'),
+            (Flags::ECE, 'E'),
+            (Flags::CWR, 'W'),
+            (Flags::NS, 'N'),
+            (Flags::ACK, '.'),
+        ];
+        for &(flag, name) in names.iter() {
+            if flags.contains(flag) {
+                write!(f, "{}", name)?;
+            }
+        }
+        write!(f, "] seq={}", self.sequence_number())?;
+        if flags.contains(Flags::ACK) {
+            write!(f, " ack={}", self.acknowledgment_number())?;
+        }
+        write!(f, " win={} len={}", self.window_size(), self.payload().len())
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> TcpPacket<B> {
+    /// Writes the given raw option bytes directly after the fixed part of the header and updates
+    /// the data offset field to include them. The options are padded with zeros (End of Option
+    /// List) to a multiple of four bytes. Since the payload starts after the options, options
+    /// should be set before the payload is written.
+    pub fn set_options(&mut self, options: &[u8]) -> Result<(), SetOptionsError> {
+        if options.len() > MAX_OPTIONS_LEN {
+            return Err(SetOptionsError::TooLong);
+        }
+        let padded_len = (options.len() + 3) & !3;
+        let header_len = TcpPacket::MIN_LEN + padded_len;
+        if self.data().len() < header_len {
+            return Err(SetOptionsError::BufferTooShort);
+        }
+        let options_end = TcpPacket::MIN_LEN + options.len();
+        self.data_mut()[TcpPacket::MIN_LEN..options_end].copy_from_slice(options);
+        for byte in &mut self.data_mut()[options_end..header_len] {
+            *byte = 0;
+        }
+        self.set_data_offset((header_len / 4) as u4);
+        Ok(())
+    }
+
+    /// Computes the checksum over the given IP pseudo-header and writes it to the checksum field.
+    /// Should be called after the rest of the header and the payload have been written.
+    pub fn fill_checksum<P: PseudoHeader>(&mut self, ip: &P) {
+        let checksum = self.calculate_checksum(ip);
+        self.set_checksum(checksum);
+    }
+}
+
+
+bitflags! {
+    /// Bitmasks for the nine control bits in the TCP header.
+    pub struct Flags: u16 {
+        /// ECN-nonce concealment protection, from [RFC 3540](https://tools.ietf.org/html/rfc3540).
+        const NS = 0b1_0000_0000;
+        /// Congestion Window Reduced.
+        const CWR = 0b0_1000_0000;
+        /// ECN-Echo.
+        const ECE = 0b0_0100_0000;
+        /// The urgent pointer field is significant.
+        const URG = 0b0_0010_0000;
+        /// The acknowledgment number field is significant.
+        const ACK = 0b0_0001_0000;
+        /// Push function.
+        const PSH = 0b0_0000_1000;
+        /// Reset the connection.
+        const RST = 0b0_0000_0100;
+        /// Synchronize sequence numbers.
+        const SYN = 0b0_0000_0010;
+        /// No more data from sender.
+        const FIN = 0b0_0000_0001;
+    }
+}
+
+
+/// Error returned when a TCP segment is not valid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TcpError {
+    /// The segment is too short to hold a TCP header.
+    Truncated,
+    /// The data offset field is less than five words or longer than the segment.
+    DataOffset,
+}
+
+impl fmt::Display for TcpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TcpError::Truncated => write!(f, "TCP segment truncated"),
+            TcpError::DataOffset => write!(f, "Invalid TCP data offset"),
+        }
+    }
+}
+
+impl Error for TcpError {}
+
+/// Error returned when setting the options of a TCP header fails.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SetOptionsError {
+    /// The options are longer than the 40 bytes that fit in the header.
+    TooLong,
+    /// The backing buffer is not large enough to hold the header with the options.
+    BufferTooShort,
+}
+
+impl fmt::Display for SetOptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SetOptionsError::TooLong => {
+                write!(f, "TCP options longer than {} bytes", MAX_OPTIONS_LEN)
+            }
+            SetOptionsError::BufferTooShort => write!(f, "Buffer too short for TCP options"),
+        }
+    }
+}
+
+impl Error for SetOptionsError {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn min_length() {
+        assert_eq!(TcpPacket::MIN_LEN, 20);
+        assert!(TcpPacket::new(&[0; 19]).is_none());
+    }
+
+    macro_rules! tcp_setget_test {
+        ($name:ident, $set_name:ident, $value:expr, $offset:expr, $expected:expr) => {
+            setget_test!(MutTcpPacket, $name, $set_name, $value, $offset, $expected);
+        }
+    }
+
+    tcp_setget_test!(source_port, set_source_port, 0xabcd, 0, [0xab, 0xcd]);
+    tcp_setget_test!(destination_port, set_destination_port, 0xabcd, 2, [0xab, 0xcd]);
+    tcp_setget_test!(
+        sequence_number,
+        set_sequence_number,
+        0x1234_5678,
+        4,
+        [0x12, 0x34, 0x56, 0x78]
+    );
+    tcp_setget_test!(
+        acknowledgment_number,
+        set_acknowledgment_number,
+        0x9abc_def0,
+        8,
+        [0x9a, 0xbc, 0xde, 0xf0]
+    );
+    tcp_setget_test!(data_offset, set_data_offset, 0xf, 12, [0xf0]);
+    tcp_setget_test!(flags, set_flags, Flags::all(), 12, [0x01, 0xff]);
+    tcp_setget_test!(window_size, set_window_size, 0xfedc, 14, [0xfe, 0xdc]);
+    tcp_setget_test!(checksum, set_checksum, 0xfeff, 16, [0xfe, 0xff]);
+    tcp_setget_test!(urgent_pointer, set_urgent_pointer, 0x1234, 18, [0x12, 0x34]);
+
+    #[test]
+    fn flags_keep_data_offset() {
+        let mut backing_data = [0xff; 20];
+        MutTcpPacket::new(&mut backing_data)
+            .unwrap()
+            .set_flags(Flags::SYN | Flags::ACK);
+        assert_eq!([0xfe, 0x12], backing_data[12..14]);
+    }
+
+    #[test]
+    fn payload_after_options() {
+        let mut backing_data = [0; 30];
+        {
+            let mut testee = MutTcpPacket::new(&mut backing_data).unwrap();
+            testee.set_options(&[2, 4, 0x05, 0xb4]).unwrap();
+            testee.payload_mut()[0] = 0xff;
+        }
+        let testee = TcpPacket::new(&backing_data).unwrap();
+        assert_eq!(6, testee.data_offset());
+        assert_eq!(24, testee.header_len());
+        assert_eq!(&[0xff, 0, 0, 0, 0, 0], testee.payload());
+        assert_eq!(
+            vec![TcpOption::MaximumSegmentSize(1460)],
+            testee.options().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn set_options_errors() {
+        let mut backing_data = [0; 64];
+        let mut testee = MutTcpPacket::new(&mut backing_data[..22]).unwrap();
+        assert_eq!(Err(SetOptionsError::TooLong), testee.set_options(&[1; 41]));
+        assert_eq!(Err(SetOptionsError::BufferTooShort), testee.set_options(&[1; 3]));
+        assert_eq!("TCP options longer than 40 bytes", SetOptionsError::TooLong.to_string());
+    }
+
+    // SYN from 192.168.0.2:47360 to 192.168.0.1:80 with MSS, SACK permitted, timestamps, NOP and
+    // window scale options.
+    static SYN: [u8; 40] = [
+        0xb9, 0x00, 0x00, 0x50, 0x3a, 0x9f, 0x6e, 0x5b, 0x00, 0x00, 0x00, 0x00, 0xa0, 0x02, 0xfa,
+        0xf0, 0x00, 0x00, 0x00, 0x00, 0x02, 0x04, 0x05, 0xb4, 0x04, 0x02, 0x08, 0x0a, 0x00, 0x02,
+        0x2c, 0x3f, 0x00, 0x00, 0x00, 0x00, 0x01, 0x03, 0x03, 0x07,
+    ];
+
+    #[test]
+    fn ipv4_checksum() {
+        let addrs = (Ipv4Addr::new(192, 168, 0, 2), Ipv4Addr::new(192, 168, 0, 1));
+        let mut backing_data = SYN;
+        let mut testee = MutTcpPacket::new(&mut backing_data).unwrap();
+        assert!(!testee.as_immutable().verify_checksum(&addrs));
+        testee.fill_checksum(&addrs);
+        assert!(testee.as_immutable().verify_checksum(&addrs));
+
+        let sum = checksum::sum(
+            0,
+            &[192, 168, 0, 2, 192, 168, 0, 1, 0, 6, 0, 40],
+        );
+        let expected = checksum::finish(checksum::sum(sum, &SYN));
+        assert_eq!(expected, testee.as_immutable().checksum());
+    }
+
+    #[test]
+    fn ipv6_checksum() {
+        let addrs = (
+            Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+            Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 2),
+        );
+        let mut backing_data = SYN;
+        let mut testee = MutTcpPacket::new(&mut backing_data).unwrap();
+        testee.fill_checksum(&addrs);
+        assert!(testee.as_immutable().verify_checksum(&addrs));
+        let ipv4_addrs = (Ipv4Addr::new(192, 168, 0, 2), Ipv4Addr::new(192, 168, 0, 1));
+        assert!(!testee.as_immutable().verify_checksum(&ipv4_addrs));
+    }
+
+    #[test]
+    fn getters() {
+        let backing_data = SYN;
+        let testee = TcpPacket::new(&backing_data).unwrap();
+        assert_eq!(47360, testee.source_port());
+        assert_eq!(80, testee.destination_port());
+        assert_eq!(0x3a9f_6e5b, testee.sequence_number());
+        assert_eq!(10, testee.data_offset());
+        assert_eq!(Flags::SYN, testee.flags());
+        assert_eq!(64240, testee.window_size());
+        assert_eq!(5, testee.options().count());
+        assert!(testee.payload().is_empty());
+    }
+
+    #[test]
+    fn validate() {
+        let mut backing_data = SYN;
+        assert!(TcpPacket::new_checked(&backing_data[..]).is_ok());
+        assert_eq!(Err(TcpError::DataOffset), TcpPacket::new_checked(&backing_data[..39]));
+        backing_data[12] = 0x40;
+        assert_eq!(Err(TcpError::DataOffset), TcpPacket::new_checked(&backing_data[..]));
+        assert_eq!(Err(TcpError::Truncated), TcpPacket::new_checked(&backing_data[..19]));
+    }
+
+    #[test]
+    fn display() {
+        let mut backing_data = SYN;
+        assert_eq!(
+            "TCP 47360 -> 80 [S] seq=983527003 win=64240 len=0",
+            TcpPacket::new(&backing_data[..]).unwrap().to_string()
+        );
+        {
+            let mut testee = MutTcpPacket::new(&mut backing_data[..]).unwrap();
+            testee.set_flags(Flags::SYN | Flags::ACK);
+            testee.set_acknowledgment_number(1);
+        }
+        assert_eq!(
+            "TCP 47360 -> 80 [S.] seq=983527003 ack=1 win=64240 len=0",
+            TcpPacket::new(&backing_data[..]).unwrap().to_string()
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use core::slice::Chunks;
+
+/// Represents the kind byte of a TCP option. See [IANA's list] for the defined values.
+///
+/// [IANA's list]: https://www.iana.org/assignments/tcp-parameters/tcp-parameters.xhtml
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct OptionKind(pub u8);
+
+impl OptionKind {
+    pub const END_OF_LIST: OptionKind = OptionKind(0);
+    pub const NOP: OptionKind = OptionKind(1);
+    pub const MAXIMUM_SEGMENT_SIZE: OptionKind = OptionKind(2);
+    pub const WINDOW_SCALE: OptionKind = OptionKind(3);
+    pub const SACK_PERMITTED: OptionKind = OptionKind(4);
+    pub const SACK: OptionKind = OptionKind(5);
+    pub const TIMESTAMPS: OptionKind = OptionKind(8);
+
+    /// Returns the numeric representation of this option kind.
+    #[inline]
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+/// A single TCP header option, as yielded by the [`TcpOptions`] iterator.
+///
+/// [`TcpOptions`]: struct.TcpOptions.html
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TcpOption<'a> {
+    /// Marks the end of the option list. Nothing after this option is parsed.
+    EndOfList,
+    Nop,
+    MaximumSegmentSize(u16),
+    /// Contains the shift count of the window scale option.
+    WindowScale(u8),
+    SackPermitted,
+    Sack(SackOption<'a>),
+    Timestamps {
+        value: u32,
+        echo_reply: u32,
+    },
+    /// An option of a kind this crate does not know how to parse. Contains the option kind and
+    /// the option data, not including the kind and length bytes.
+    Unknown(OptionKind, &'a [u8]),
+    /// The option data could not be parsed, typically because of an invalid length byte.
+    /// Contains the remaining option bytes. Iteration ends after this item.
+    Malformed(&'a [u8]),
+}
+
+/// Iterator over the options in a TCP header. Created with `TcpPacket::options()`.
+#[derive(Debug, Clone)]
+pub struct TcpOptions<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> TcpOptions<'a> {
+    /// Creates an iterator over the options encoded in `data`.
+    pub fn new(data: &'a [u8]) -> TcpOptions<'a> {
+        TcpOptions { data }
+    }
+
+    fn parse(kind: OptionKind, option: &'a [u8]) -> Option<TcpOption<'a>> {
+        let length = option.len();
+        match kind {
+            OptionKind::MAXIMUM_SEGMENT_SIZE if length == 4 => {
+                let mss = read_offset!(option, 2, u16, from_be);
+                Some(TcpOption::MaximumSegmentSize(mss))
+            }
+            OptionKind::WINDOW_SCALE if length == 3 => {
+                Some(TcpOption::WindowScale(read_offset!(option, 2, u8)))
+            }
+            OptionKind::SACK_PERMITTED if length == 2 => Some(TcpOption::SackPermitted),
+            OptionKind::SACK if length >= 2 && (length - 2).is_multiple_of(8) => {
+                Some(TcpOption::Sack(SackOption(option)))
+            }
+            OptionKind::TIMESTAMPS if length == 10 => Some(TcpOption::Timestamps {
+                value: read_offset!(option, 2, u32, from_be),
+                echo_reply: read_offset!(option, 6, u32, from_be),
+            }),
+            OptionKind::MAXIMUM_SEGMENT_SIZE |
+            OptionKind::WINDOW_SCALE |
+            OptionKind::SACK_PERMITTED |
+            OptionKind::SACK |
+            OptionKind::TIMESTAMPS => None,
+            _ => Some(TcpOption::Unknown(kind, &option[2..])),
+        }
+    }
+}
+
+impl<'a> Iterator for TcpOptions<'a> {
+    type Item = TcpOption<'a>;
+
+    fn next(&mut self) -> Option<TcpOption<'a>> {
+        let data = self.data;
+        let kind = OptionKind(*data.first()?);
+        match kind {
+            OptionKind::END_OF_LIST => {
+                self.data = &[];
+                Some(TcpOption::EndOfList)
+            }
+            OptionKind::NOP => {
+                self.data = &data[1..];
+                Some(TcpOption::Nop)
+            }
+            _ => {
+                let length = data.get(1).map(|&length| length as usize).unwrap_or(0);
+                let option = if length >= 2 && length <= data.len() {
+                    Self::parse(kind, &data[..length])
+                } else {
+                    None
+                };
+                match option {
+                    Some(option) => {
+                        self.data = &data[length..];
+                        Some(option)
+                    }
+                    None => {
+                        self.data = &[];
+                        Some(TcpOption::Malformed(data))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// View of the selective acknowledgment option from [RFC 2018].
+///
+/// [RFC 2018]: https://tools.ietf.org/html/rfc2018
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SackOption<'a>(&'a [u8]);
+
+impl<'a> SackOption<'a> {
+    /// Returns the raw bytes of this option, including the kind and length bytes.
+    pub fn data(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Returns an iterator over the acknowledged blocks.
+    pub fn blocks(&self) -> SackBlocks<'a> {
+        SackBlocks(self.0[2..].chunks(8))
+    }
+}
+
+/// A block of received data in a SACK option. `right_edge` is the sequence number following the
+/// last byte in the block.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SackBlock {
+    pub left_edge: u32,
+    pub right_edge: u32,
+}
+
+/// Iterator over the blocks in a SACK option.
+#[derive(Debug, Clone)]
+pub struct SackBlocks<'a>(Chunks<'a, u8>);
+
+impl<'a> Iterator for SackBlocks<'a> {
+    type Item = SackBlock;
+
+    fn next(&mut self) -> Option<SackBlock> {
+        self.0.next().filter(|chunk| chunk.len() == 8).map(|chunk| {
+            SackBlock {
+                left_edge: read_offset!(chunk, 0, u32, from_be),
+                right_edge: read_offset!(chunk, 4, u32, from_be),
+            }
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syn_options() {
+        let data = [2, 4, 0x05, 0xb4, 4, 2, 8, 10, 0, 0, 0, 1, 0, 0, 0, 0, 1, 3, 3, 7];
+        let options: Vec<_> = TcpOptions::new(&data).collect();
+        assert_eq!(
+            vec![
+                TcpOption::MaximumSegmentSize(1460),
+                TcpOption::SackPermitted,
+                TcpOption::Timestamps {
+                    value: 1,
+                    echo_reply: 0,
+                },
+                TcpOption::Nop,
+                TcpOption::WindowScale(7),
+            ],
+            options
+        );
+    }
+
+    #[test]
+    fn sack() {
+        let data = [1, 1, 5, 18, 0, 0, 0, 10, 0, 0, 0, 20, 0, 0, 0, 30, 0, 0, 0, 40];
+        let mut options = TcpOptions::new(&data);
+        assert_eq!(Some(TcpOption::Nop), options.next());
+        assert_eq!(Some(TcpOption::Nop), options.next());
+        match options.next() {
+            Some(TcpOption::Sack(sack)) => {
+                let blocks: Vec<_> = sack.blocks().collect();
+                assert_eq!(
+                    vec![
+                        SackBlock {
+                            left_edge: 10,
+                            right_edge: 20,
+                        },
+                        SackBlock {
+                            left_edge: 30,
+                            right_edge: 40,
+                        },
+                    ],
+                    blocks
+                );
+            }
+            option => panic!("Unexpected option {:?}", option),
+        }
+        assert_eq!(None, options.next());
+    }
+
+    #[test]
+    fn end_of_list() {
+        let data = [0, 2, 4, 5, 0xb4];
+        let options: Vec<_> = TcpOptions::new(&data).collect();
+        assert_eq!(vec![TcpOption::EndOfList], options);
+    }
+
+    #[test]
+    fn unknown() {
+        let data = [30, 3, 0xff];
+        let options: Vec<_> = TcpOptions::new(&data).collect();
+        assert_eq!(vec![TcpOption::Unknown(OptionKind(30), &[0xff])], options);
+    }
+
+    #[test]
+    fn malformed() {
+        let data = [3, 4, 7, 0];
+        let options: Vec<_> = TcpOptions::new(&data).collect();
+        assert_eq!(vec![TcpOption::Malformed(&data)], options);
+
+        let data = [1, 2, 5];
+        let options: Vec<_> = TcpOptions::new(&data).collect();
+        assert_eq!(vec![TcpOption::Nop, TcpOption::Malformed(&[2, 5])], options);
+    }
+}
@@ -4,5 +4,9 @@
 pub type u2 = u8;
 pub type u3 = u8;
 pub type u4 = u8;
+pub type u5 = u8;
 pub type u6 = u8;
+pub type u12 = u16;
 pub type u13 = u16;
+pub type u20 = u32;
+pub type u24 = u32;
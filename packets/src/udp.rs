@@ -0,0 +1,271 @@
+use checksum;
+use core::error::Error;
+use core::fmt;
+use ip::{Protocol, PseudoHeader};
+
+packet!(UdpPacket, MutUdpPacket, 8, |_: &[u8]| 8, |data: &[u8]| {
+    read_offset!(data, 4, u16, from_be) as usize
+});
+
+getters!(UdpPacket
+    pub fn source_port(&self) -> u16 {
+        read_offset!(self.data(), 0, u16, from_be)
+    }
+
+    pub fn destination_port(&self) -> u16 {
+        read_offset!(self.data(), 2, u16, from_be)
+    }
+
+    /// Returns the length of the datagram, header included, in bytes.
+    pub fn length(&self) -> u16 {
+        read_offset!(self.data(), 4, u16, from_be)
+    }
+
+    pub fn checksum(&self) -> u16 {
+        read_offset!(self.data(), 6, u16, from_be)
+    }
+);
+
+impl<B: AsRef<[u8]>> UdpPacket<B> {
+    /// Checks that the length field covers at least the header and fits in the packet.
+    pub fn validate(&self) -> Result<(), UdpError> {
+        let length = usize::from(self.length());
+        if length < UdpPacket::MIN_LEN || length > self.data().len() {
+            return Err(UdpError::Length);
+        }
+        Ok(())
+    }
+
+    /// Computes the checksum of this datagram over the given IP pseudo-header, as it should be
+    /// written to the checksum field. The current value of the checksum field is ignored. The
+    /// datagram is bounded by the length field, so it should be set before calling this.
+    ///
+    /// A computed checksum of zero is returned as `0xffff`, since zero in the checksum field
+    /// means that no checksum was computed.
+    pub fn calculate_checksum<P: PseudoHeader>(&self, ip: &P) -> u16 {
+        let data = &self.data()[..self.packet_len()];
+        let sum = ip.pseudo_header_sum(Protocol::UDP, u32::from(self.length()));
+        let sum = checksum::sum(checksum::sum(sum, &data[..6]), &data[8..]);
+        match checksum::finish(sum) {
+            0 => 0xffff,
+            checksum => checksum,
+        }
+    }
+
+    /// Returns true if the checksum field contains the correct checksum for this datagram and the
+    /// given IP pseudo-header.
+    ///
+    /// Over IPv4 a checksum field of zero means the sender did not compute a checksum, and the
+    /// datagram is accepted. Over IPv6 the checksum is mandatory, so a zero checksum field is
+    /// always invalid.
+    pub fn verify_checksum<P: PseudoHeader>(&self, ip: &P) -> bool {
+        if self.checksum() == 0 {
+            return P::VERSION == 4;
+        }
+        let sum = ip.pseudo_header_sum(Protocol::UDP, u32::from(self.length()));
+        checksum::finish(checksum::sum(sum, &self.data()[..self.packet_len()])) == 0
+    }
+}
+
+new_checked!(UdpPacket, UdpError);
+
+setters!(UdpPacket
+    pub fn set_source_port(&mut self, source_port: u16) {
+        write_offset!(self.data_mut(), 0, source_port, u16, to_be);
+    }
+
+    pub fn set_destination_port(&mut self, destination_port: u16) {
+        write_offset!(self.data_mut(), 2, destination_port, u16, to_be);
+    }
+
+    pub fn set_length(&mut self, length: u16) {
+        write_offset!(self.data_mut(), 4, length, u16, to_be);
+    }
+
+    pub fn set_checksum(&mut self, checksum: u16) {
+        write_offset!(self.data_mut(), 6, checksum, u16, to_be);
+    }
+);
+
+/// Writes a one line summary of the header, like `UDP 53 -> 1024 len=40`.
+impl<B: AsRef<[u8]>> fmt::Display for UdpPacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "UDP {} -> {} len={}",
+            self.source_port(),
+            self.destination_port(),
+            self.length()
+        )
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> UdpPacket<B> {
+    /// Computes the checksum over the given IP pseudo-header and writes it to the checksum field.
+    /// Should be called after the length, the rest of the header and the payload have been
+    /// written.
+    pub fn fill_checksum<P: PseudoHeader>(&mut self, ip: &P) {
+        let checksum = self.calculate_checksum(ip);
+        self.set_checksum(checksum);
+    }
+}
+
+
+/// Error returned when a UDP datagram is not valid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UdpError {
+    /// The datagram is too short to hold a UDP header.
+    Truncated,
+    /// The length field is shorter than the header or longer than the datagram.
+    Length,
+}
+
+impl fmt::Display for UdpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UdpError::Truncated => write!(f, "UDP datagram truncated"),
+            UdpError::Length => write!(f, "Invalid UDP length"),
+        }
+    }
+}
+
+impl Error for UdpError {}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn min_length() {
+        assert_eq!(UdpPacket::MIN_LEN, 8);
+        assert!(UdpPacket::new(&[0; 7]).is_none());
+    }
+
+    macro_rules! udp_setget_test {
+        ($name:ident, $set_name:ident, $value:expr, $offset:expr, $expected:expr) => {
+            setget_test!(MutUdpPacket, $name, $set_name, $value, $offset, $expected);
+        }
+    }
+
+    udp_setget_test!(source_port, set_source_port, 0xabcd, 0, [0xab, 0xcd]);
+    udp_setget_test!(destination_port, set_destination_port, 0xabcd, 2, [0xab, 0xcd]);
+    udp_setget_test!(length, set_length, 0x1234, 4, [0x12, 0x34]);
+    udp_setget_test!(checksum, set_checksum, 0xfeff, 6, [0xfe, 0xff]);
+
+    #[test]
+    fn payload_bounded_by_length() {
+        let mut backing_data = [0; 20];
+        backing_data[5] = 10;
+        let testee = UdpPacket::new(&backing_data).unwrap();
+        assert_eq!(10, testee.packet_len());
+        assert_eq!(&[0, 0], testee.payload());
+    }
+
+    #[test]
+    fn payload_length_larger_than_buffer() {
+        let mut backing_data = [0; 12];
+        backing_data[5] = 100;
+        let testee = UdpPacket::new(&backing_data).unwrap();
+        assert_eq!(4, testee.payload().len());
+    }
+
+    #[test]
+    fn payload_length_smaller_than_header() {
+        let backing_data = [0; 12];
+        let testee = UdpPacket::new(&backing_data).unwrap();
+        assert_eq!(8, testee.packet_len());
+        assert!(testee.payload().is_empty());
+    }
+
+    fn datagram(backing_data: &mut [u8]) -> MutUdpPacket<'_> {
+        let mut testee = MutUdpPacket::new(backing_data).unwrap();
+        testee.set_source_port(1024);
+        testee.set_destination_port(53);
+        testee.set_length(11);
+        testee.payload_mut().copy_from_slice(&[1, 2, 3]);
+        testee
+    }
+
+    #[test]
+    fn ipv4_checksum() {
+        let addrs = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let mut backing_data = [0; 16];
+        let mut testee = datagram(&mut backing_data[..11]);
+        testee.fill_checksum(&addrs);
+        let expected = checksum::checksum(&[
+            10, 0, 0, 1, 10, 0, 0, 2, 0, 17, 0, 11, 0x04, 0x00, 0x00, 53, 0, 11, 0, 0, 1, 2, 3,
+        ]);
+        assert_eq!(expected, testee.as_immutable().checksum());
+        assert!(testee.as_immutable().verify_checksum(&addrs));
+        testee.payload_mut()[0] = 0;
+        assert!(!testee.as_immutable().verify_checksum(&addrs));
+    }
+
+    #[test]
+    fn checksum_ignores_padding() {
+        let addrs = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let mut backing_data = [0; 20];
+        datagram(&mut backing_data).fill_checksum(&addrs);
+        backing_data[15] = 0xff;
+        assert!(UdpPacket::new(&backing_data).unwrap().verify_checksum(&addrs));
+    }
+
+    #[test]
+    fn ipv6_checksum() {
+        let addrs = (
+            Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+            Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 2),
+        );
+        let mut backing_data = [0; 16];
+        let mut testee = datagram(&mut backing_data[..11]);
+        testee.fill_checksum(&addrs);
+        assert!(testee.as_immutable().verify_checksum(&addrs));
+    }
+
+    #[test]
+    fn zero_checksum() {
+        let ipv4_addrs = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let ipv6_addrs = (Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1), Ipv6Addr::LOCALHOST);
+        let mut backing_data = [0; 16];
+        let testee = datagram(&mut backing_data[..11]);
+        assert_eq!(0, testee.as_immutable().checksum());
+        assert!(testee.as_immutable().verify_checksum(&ipv4_addrs));
+        assert!(!testee.as_immutable().verify_checksum(&ipv6_addrs));
+    }
+
+    #[test]
+    fn computed_zero_is_sent_as_all_ones() {
+        let addrs = (Ipv4Addr::new(0, 0, 0, 0), Ipv4Addr::new(0, 0, 0, 0));
+        let mut backing_data = [0; 16];
+        {
+            let mut testee = MutUdpPacket::new(&mut backing_data).unwrap();
+            // Pseudo-header protocol and the two length fields sum up to 0xffff
+            testee.set_length(8);
+            testee.set_source_port(0xffff - 17 - 8 - 8);
+            testee.fill_checksum(&addrs);
+        }
+        let testee = UdpPacket::new(&backing_data[..8]).unwrap();
+        assert_eq!(0xffff, testee.checksum());
+        assert!(testee.verify_checksum(&addrs));
+    }
+
+    #[test]
+    fn validate() {
+        let mut backing_data = [0; 12];
+        backing_data[5] = 12;
+        assert!(UdpPacket::new_checked(&backing_data[..]).is_ok());
+        assert_eq!(Err(UdpError::Length), UdpPacket::new_checked(&backing_data[..11]));
+        backing_data[5] = 7;
+        assert_eq!(Err(UdpError::Length), UdpPacket::new_checked(&backing_data[..]));
+        assert_eq!(Err(UdpError::Truncated), UdpPacket::new_checked(&backing_data[..7]));
+    }
+
+    #[test]
+    fn display() {
+        let backing_data = [0, 53, 0x04, 0x00, 0, 12, 0, 0, 1, 2, 3, 4];
+        let testee = UdpPacket::new(&backing_data[..]).unwrap();
+        assert_eq!("UDP 53 -> 1024 len=12", testee.to_string());
+    }
+}